@@ -2,6 +2,7 @@ extern crate bindgen;
 
 use std::env;
 use std::path::PathBuf;
+use std::process::Command;
 
 fn main() {
     let mut builder = bindgen::Builder::default()
@@ -24,10 +25,34 @@ fn main() {
         }
     }
 
+    // Some rcl APIs differ between distributions, e.g. `rcl_lifecycle_state_machine_init`
+    println!("cargo:rerun-if-env-changed=ROS_DISTRO");
+    println!("cargo:rustc-check-cfg=cfg(ros_distro, values(any()))");
+    if let Ok(ros_distro) = env::var("ROS_DISTRO") {
+        println!("cargo:rustc-cfg=ros_distro=\"{}\"", ros_distro);
+    }
+
+    // Component libraries are only loaded by containers built with the same compiler
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=RCLRS_RUSTC_VERSION={}", rustc_version.trim());
+
     println!("cargo:rustc-link-lib=dylib=rcl");
+    println!("cargo:rustc-link-lib=dylib=rcl_action");
+    println!("cargo:rustc-link-lib=dylib=rcl_lifecycle");
     println!("cargo:rustc-link-lib=dylib=rcutils");
     println!("cargo:rustc-link-lib=dylib=rmw");
     println!("cargo:rustc-link-lib=dylib=rmw_implementation");
+    println!("cargo:rustc-link-lib=dylib=action_msgs__rosidl_generator_c");
+    println!("cargo:rustc-link-lib=dylib=composition_interfaces__rosidl_generator_c");
+    println!("cargo:rustc-link-lib=dylib=composition_interfaces__rosidl_typesupport_c");
+    println!("cargo:rustc-link-lib=dylib=lifecycle_msgs__rosidl_generator_c");
+    println!("cargo:rustc-link-lib=dylib=lifecycle_msgs__rosidl_typesupport_c");
 
     let bindings = builder.generate().expect("Unable to generate bindings");
 
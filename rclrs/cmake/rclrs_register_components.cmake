@@ -0,0 +1,41 @@
+# Copyright 2016-2017 Esteve Fernandez <esteve@apache.org>
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+#
+# Register the components of a library in the ament index, so that rclrs
+# component containers can load them.
+#
+# Each package may only call this function once, listing all of its components.
+#
+# :param library: the path of the library relative to the install prefix,
+#   e.g. lib/libmy_pkg.so
+# :type library: string
+# :param ARGN: the plugin names of the components, i.e. their full type names,
+#   e.g. my_pkg::Talker
+# :type ARGN: list of strings
+#
+# @public
+#
+function(rclrs_register_components library)
+  if(${ARGC} LESS 2)
+    message(FATAL_ERROR
+      "rclrs_register_components() called without any components")
+  endif()
+
+  set(_content "")
+  foreach(_plugin_name ${ARGN})
+    set(_content "${_content}${_plugin_name};${library}\n")
+  endforeach()
+  ament_index_register_resource("rclrs_components" CONTENT "${_content}")
+endfunction()
@@ -0,0 +1,474 @@
+use crate::error::{ErrorContext, RclrsError, ToResult};
+use crate::rcl_bindings::*;
+use crate::wait::{WaitSet, Waitable, WaitableCount};
+use crate::{Node, NodeHandle};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use cstr_core::CString;
+use rclrs_common::error::{
+    to_rcl_result, ActionErrorCode, ClientErrorCode, RclReturnCode, SubscriberErrorCode,
+};
+use rclrs_common::rmw::{GetResultRequest, SendGoalResponse};
+use rclrs_common::traits::{ActionDefinition, MessageDefinition};
+
+use super::{
+    generate_goal_uuid, CancelGoalResponse, CancelReturnCode, FeedbackMessage, GetResultResponse,
+    GoalStatus, GoalUuid, SendGoalRequest,
+};
+
+#[cfg(not(feature = "std"))]
+use spin::{Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+use parking_lot::{Mutex, MutexGuard};
+
+pub struct ActionClientHandle {
+    handle: Mutex<rcl_action_client_t>,
+    node_handle: Arc<NodeHandle>,
+}
+
+// SAFETY: `rcl_action` clients may be used from any thread as long as calls on the same
+// client are not concurrent, which the mutex ensures.
+unsafe impl Send for ActionClientHandle {}
+unsafe impl Sync for ActionClientHandle {}
+
+impl ActionClientHandle {
+    pub fn lock(&self) -> MutexGuard<rcl_action_client_t> {
+        self.handle.lock()
+    }
+}
+
+impl Drop for ActionClientHandle {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        let node_handle = &mut *self.node_handle.lock();
+        unsafe {
+            rcl_action_client_fini(handle as *mut _, node_handle as *mut _)
+                .log_on_error("failed to finalize an action client");
+        }
+    }
+}
+
+type GoalResponseCallback = Box<dyn FnOnce(&GoalUuid, bool) + Send>;
+type FeedbackCallback<T> = Box<dyn FnMut(&GoalUuid, &<T as ActionDefinition>::Feedback) + Send>;
+type ResultCallback<T> =
+    Box<dyn FnOnce(&GoalUuid, GoalStatus, &<T as ActionDefinition>::Result) + Send>;
+type CancelResponseCallback = Box<dyn FnOnce(&CancelGoalResponse) + Send>;
+
+/// Callbacks of a goal sent by an [`ActionClient`]
+struct ClientGoal<T>
+where
+    T: ActionDefinition,
+{
+    goal_id: GoalUuid,
+    goal_response_callback: GoalResponseCallback,
+    feedback_callback: FeedbackCallback<T>,
+    result_callback: ResultCallback<T>,
+}
+
+/// Client side of an action, built on `rcl_action`
+///
+/// See [`Node::create_action_client`]. Action clients are `Send` and `Sync`, so their
+/// callbacks have to be `Send`.
+pub struct ActionClient<T>
+where
+    T: ActionDefinition,
+{
+    pub handle: Arc<ActionClientHandle>,
+    /// Goals whose `SendGoal` response hasn't arrived yet, by sequence number
+    pending_goal_requests: Mutex<BTreeMap<i64, ClientGoal<T>>>,
+    /// Accepted goals, waiting for feedback and their result
+    goals: Mutex<BTreeMap<GoalUuid, (FeedbackCallback<T>, ResultCallback<T>)>>,
+    /// Goals whose `GetResult` response hasn't arrived yet, by sequence number
+    pending_result_requests: Mutex<BTreeMap<i64, GoalUuid>>,
+    /// Callbacks of the `CancelGoal` requests whose response hasn't arrived yet, by sequence
+    /// number
+    pending_cancel_requests: Mutex<BTreeMap<i64, CancelResponseCallback>>,
+}
+
+impl<T> ActionClient<T>
+where
+    T: ActionDefinition,
+{
+    pub fn new(node: &Node, action_name: &str) -> Result<Self, RclrsError> {
+        let mut action_client_handle = unsafe { rcl_action_get_zero_initialized_client() };
+        let type_support = T::type_support() as *const rosidl_action_type_support_t;
+        let action_name_c_string = CString::new(action_name).map_err(|_| {
+            RclrsError::from(RclReturnCode::InvalidArgument)
+                .context(format!("action name {:?} contains a NUL byte", action_name))
+        })?;
+        let node_handle = &mut *node.handle.lock();
+
+        unsafe {
+            let action_client_options = rcl_action_client_get_default_options();
+            rcl_action_client_init(
+                &mut action_client_handle as *mut _,
+                node_handle as *mut _,
+                type_support,
+                action_name_c_string.as_ptr(),
+                &action_client_options as *const _,
+            )
+            .ok()
+            .with_context(|| format!("failed to create an action client for '{}'", action_name))?;
+        }
+
+        let handle = Arc::new(ActionClientHandle {
+            handle: Mutex::new(action_client_handle),
+            node_handle: node.handle.clone(),
+        });
+
+        Ok(Self {
+            handle,
+            pending_goal_requests: Mutex::new(BTreeMap::new()),
+            goals: Mutex::new(BTreeMap::new()),
+            pending_result_requests: Mutex::new(BTreeMap::new()),
+            pending_cancel_requests: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Checks whether the action server is available
+    pub fn server_is_ready(&self) -> Result<bool, RclrsError> {
+        let mut is_available = false;
+        let node_handle = &*self.handle.node_handle.lock();
+        unsafe {
+            rcl_action_server_is_available(
+                node_handle as *const _,
+                &*self.handle.lock() as *const _,
+                &mut is_available as *mut _,
+            )
+            .ok()?;
+        }
+        Ok(is_available)
+    }
+
+    /// Sends a new goal to the action server, returning the goal's ID
+    ///
+    /// `goal_response_callback` is called with whether the server accepted the goal. For an
+    /// accepted goal, `feedback_callback` is then called for every feedback message, and
+    /// finally `result_callback` once the goal finished.
+    pub fn send_goal<G, F, R>(
+        &self,
+        goal: &T::Goal,
+        goal_response_callback: G,
+        feedback_callback: F,
+        result_callback: R,
+    ) -> Result<GoalUuid, RclrsError>
+    where
+        G: FnOnce(&GoalUuid, bool) + Send + 'static,
+        F: FnMut(&GoalUuid, &T::Feedback) + Send + 'static,
+        R: FnOnce(&GoalUuid, GoalStatus, &T::Result) + Send + 'static,
+    {
+        let goal_id = generate_goal_uuid();
+        let request = SendGoalRequest::<T> {
+            goal_id,
+            goal: T::Goal::into_rmw(goal),
+        };
+
+        // Locked before sending, so that the response can't be handled before it's expected
+        let pending_goal_requests = &mut *self.pending_goal_requests.lock();
+        let mut sequence_number: i64 = 0;
+        unsafe {
+            rcl_action_send_goal_request(
+                &*self.handle.lock() as *const _,
+                &request as *const _ as *const _,
+                &mut sequence_number as *mut _,
+            )
+            .ok()?;
+        }
+
+        pending_goal_requests.insert(
+            sequence_number,
+            ClientGoal {
+                goal_id,
+                goal_response_callback: Box::new(goal_response_callback),
+                feedback_callback: Box::new(feedback_callback),
+                result_callback: Box::new(result_callback),
+            },
+        );
+        Ok(goal_id)
+    }
+
+    /// Asks the action server to cancel the goal with the given ID
+    ///
+    /// `cancel_response_callback` is called with the server's response, which tells whether
+    /// the goal is being canceled.
+    pub fn cancel_goal<C>(
+        &self,
+        goal_id: &GoalUuid,
+        cancel_response_callback: C,
+    ) -> Result<(), RclrsError>
+    where
+        C: FnOnce(&CancelGoalResponse) + Send + 'static,
+    {
+        let mut request: action_msgs__srv__CancelGoal_Request = unsafe { core::mem::zeroed() };
+        unsafe { action_msgs__srv__CancelGoal_Request__init(&mut request as *mut _) };
+        request.goal_info.goal_id.uuid = *goal_id;
+
+        // Locked before sending, so that the response can't be handled before it's expected
+        let pending_cancel_requests = &mut *self.pending_cancel_requests.lock();
+        let mut sequence_number: i64 = 0;
+        let send_result = unsafe {
+            rcl_action_send_cancel_request(
+                &*self.handle.lock() as *const _,
+                &request as *const _ as *const _,
+                &mut sequence_number as *mut _,
+            )
+        };
+        unsafe { action_msgs__srv__CancelGoal_Request__fini(&mut request as *mut _) };
+        send_result.ok()?;
+
+        pending_cancel_requests.insert(sequence_number, Box::new(cancel_response_callback));
+        Ok(())
+    }
+
+    fn take_goal_response(&self) -> Result<(), RclrsError> {
+        let mut response_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut response = SendGoalResponse::default();
+        let take_result = unsafe {
+            rcl_action_take_goal_response(
+                &*self.handle.lock() as *const _,
+                &mut response_header as *mut _,
+                &mut response as *mut _ as *mut _,
+            )
+        };
+        if !is_taken(take_result)? {
+            return Ok(());
+        }
+        let accepted = response.accepted;
+
+        let goal = match self
+            .pending_goal_requests
+            .lock()
+            .remove(&response_header.sequence_number)
+        {
+            Some(goal) => goal,
+            None => return Ok(()),
+        };
+        let goal_id = goal.goal_id;
+        (goal.goal_response_callback)(&goal_id, accepted);
+        if !accepted {
+            return Ok(());
+        }
+
+        self.goals
+            .lock()
+            .insert(goal_id, (goal.feedback_callback, goal.result_callback));
+        let request = GetResultRequest { goal_id };
+        let pending_result_requests = &mut *self.pending_result_requests.lock();
+        let mut sequence_number: i64 = 0;
+        unsafe {
+            rcl_action_send_result_request(
+                &*self.handle.lock() as *const _,
+                &request as *const _ as *const _,
+                &mut sequence_number as *mut _,
+            )
+            .ok()?;
+        }
+        pending_result_requests.insert(sequence_number, goal_id);
+        Ok(())
+    }
+
+    fn take_feedback(&self) -> Result<(), RclrsError> {
+        let mut message = FeedbackMessage::<T>::default();
+        let take_result = unsafe {
+            rcl_action_take_feedback(
+                &*self.handle.lock() as *const _,
+                &mut message as *mut _ as *mut _,
+            )
+        };
+        if !is_taken(take_result)? {
+            return Ok(());
+        }
+        let goal_id = message.goal_id;
+        let feedback = T::Feedback::from_rmw(&message.feedback);
+
+        if let Some((feedback_callback, _)) = self.goals.lock().get_mut(&goal_id) {
+            feedback_callback(&goal_id, &feedback);
+        }
+        Ok(())
+    }
+
+    fn take_result_response(&self) -> Result<(), RclrsError> {
+        let mut response_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut response = GetResultResponse::<T>::default();
+        let take_result = unsafe {
+            rcl_action_take_result_response(
+                &*self.handle.lock() as *const _,
+                &mut response_header as *mut _,
+                &mut response as *mut _ as *mut _,
+            )
+        };
+        if !is_taken(take_result)? {
+            return Ok(());
+        }
+        let status = GoalStatus::from(response.status);
+        let result = T::Result::from_rmw(&response.result);
+
+        let goal_id = match self
+            .pending_result_requests
+            .lock()
+            .remove(&response_header.sequence_number)
+        {
+            Some(goal_id) => goal_id,
+            None => return Ok(()),
+        };
+        let goal = self.goals.lock().remove(&goal_id);
+        if let Some((_, result_callback)) = goal {
+            result_callback(&goal_id, status, &result);
+        }
+        Ok(())
+    }
+
+    /// Status updates are taken only to clear them, goals are tracked via their responses
+    fn take_status(&self) -> Result<(), RclrsError> {
+        let mut status_array: action_msgs__msg__GoalStatusArray = unsafe { core::mem::zeroed() };
+        unsafe {
+            action_msgs__msg__GoalStatusArray__init(&mut status_array as *mut _);
+            let take_result = rcl_action_take_status(
+                &*self.handle.lock() as *const _,
+                &mut status_array as *mut _ as *mut _,
+            );
+            action_msgs__msg__GoalStatusArray__fini(&mut status_array as *mut _);
+            is_taken(take_result).map(|_| ())
+        }
+    }
+
+    fn take_cancel_response(&self) -> Result<(), RclrsError> {
+        let mut response_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut response: action_msgs__srv__CancelGoal_Response = unsafe { core::mem::zeroed() };
+        let cancel_response = unsafe {
+            action_msgs__srv__CancelGoal_Response__init(&mut response as *mut _);
+            let take_result = rcl_action_take_cancel_response(
+                &*self.handle.lock() as *const _,
+                &mut response_header as *mut _,
+                &mut response as *mut _ as *mut _,
+            );
+            let cancel_response = is_taken(take_result)
+                .map(|taken| taken.then(|| cancel_goal_response_from_msg(&response)));
+            action_msgs__srv__CancelGoal_Response__fini(&mut response as *mut _);
+            cancel_response
+        };
+        let cancel_response = match cancel_response? {
+            Some(cancel_response) => cancel_response,
+            None => return Ok(()),
+        };
+
+        let cancel_response_callback = match self
+            .pending_cancel_requests
+            .lock()
+            .remove(&response_header.sequence_number)
+        {
+            Some(cancel_response_callback) => cancel_response_callback,
+            None => return Ok(()),
+        };
+        cancel_response_callback(&cancel_response);
+        Ok(())
+    }
+}
+
+impl<T> Waitable for ActionClient<T>
+where
+    T: ActionDefinition,
+{
+    fn count(&self) -> WaitableCount {
+        let mut count = WaitableCount::default();
+        // Should this fail, nothing is counted and adding the client to the wait set fails
+        unsafe {
+            rcl_action_client_wait_set_get_num_entities(
+                &*self.handle.lock() as *const _,
+                &mut count.subscriptions as *mut _,
+                &mut count.guard_conditions as *mut _,
+                &mut count.timers as *mut _,
+                &mut count.clients as *mut _,
+                &mut count.services as *mut _,
+            );
+        }
+        count
+    }
+
+    fn add_to_wait_set(&self, wait_set: &mut WaitSet) -> Result<(), RclrsError> {
+        unsafe {
+            rcl_action_wait_set_add_action_client(
+                &mut wait_set.wait_set as *mut _,
+                &*self.handle.lock() as *const _,
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+            )
+            .ok()?;
+        }
+        Ok(())
+    }
+
+    fn execute(&self, wait_set: &WaitSet) -> Result<(), RclrsError> {
+        let mut is_feedback_ready = false;
+        let mut is_status_ready = false;
+        let mut is_goal_response_ready = false;
+        let mut is_cancel_response_ready = false;
+        let mut is_result_response_ready = false;
+        unsafe {
+            rcl_action_client_wait_set_get_entities_ready(
+                &wait_set.wait_set as *const _,
+                &*self.handle.lock() as *const _,
+                &mut is_feedback_ready as *mut _,
+                &mut is_status_ready as *mut _,
+                &mut is_goal_response_ready as *mut _,
+                &mut is_cancel_response_ready as *mut _,
+                &mut is_result_response_ready as *mut _,
+            )
+            .ok()?;
+        }
+
+        if is_goal_response_ready {
+            self.take_goal_response()?;
+        }
+        if is_feedback_ready {
+            self.take_feedback()?;
+        }
+        if is_status_ready {
+            self.take_status()?;
+        }
+        if is_cancel_response_ready {
+            self.take_cancel_response()?;
+        }
+        if is_result_response_ready {
+            self.take_result_response()?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps the "take failed" return codes, which mean that nothing was available, to `Ok(false)`
+fn is_taken(result: rcl_ret_t) -> Result<bool, RclrsError> {
+    match to_rcl_result(result) {
+        Ok(()) => Ok(true),
+        Err(RclReturnCode::ActionError(ActionErrorCode::ActionClientTakeFailed))
+        | Err(RclReturnCode::ClientError(ClientErrorCode::ClientTakeFailed))
+        | Err(RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed)) => {
+            Ok(false)
+        }
+        Err(error) => {
+            Err(RclrsError::from_rcl(error).context("failed to take from an action client"))
+        }
+    }
+}
+
+fn cancel_goal_response_from_msg(
+    msg: &action_msgs__srv__CancelGoal_Response,
+) -> CancelGoalResponse {
+    let goals_canceling = &msg.goals_canceling;
+    let goals_canceling = if goals_canceling.data.is_null() {
+        Vec::new()
+    } else {
+        unsafe { core::slice::from_raw_parts(goals_canceling.data, goals_canceling.size) }
+            .iter()
+            .map(|goal_info| goal_info.goal_id.uuid)
+            .collect()
+    };
+    CancelGoalResponse {
+        return_code: CancelReturnCode::from(msg.return_code),
+        goals_canceling,
+    }
+}
@@ -0,0 +1,224 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::RclrsError;
+use crate::rcl_bindings::*;
+use crate::Node;
+use rclrs_common::rmw;
+use rclrs_common::traits::{ActionDefinition, MessageDefinition};
+
+mod client;
+pub use self::client::*;
+mod server;
+pub use self::server::*;
+
+/// Unique identifier of a goal, as in `unique_identifier_msgs/msg/UUID`
+pub type GoalUuid = [u8; 16];
+
+/// Status of a goal, as defined in `action_msgs/msg/GoalStatus`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GoalStatus {
+    Unknown = 0,
+    Accepted = 1,
+    Executing = 2,
+    Canceling = 3,
+    Succeeded = 4,
+    Canceled = 5,
+    Aborted = 6,
+}
+
+impl GoalStatus {
+    /// Whether the goal has finished, i.e. it succeeded, was canceled or was aborted
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Canceled | Self::Aborted)
+    }
+}
+
+impl From<i8> for GoalStatus {
+    fn from(status: i8) -> Self {
+        match status {
+            1 => Self::Accepted,
+            2 => Self::Executing,
+            3 => Self::Canceling,
+            4 => Self::Succeeded,
+            5 => Self::Canceled,
+            6 => Self::Aborted,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Result of an action server's goal callback
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GoalResponse {
+    Reject,
+    AcceptAndExecute,
+}
+
+/// Result of an action server's cancel callback
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CancelResponse {
+    Reject,
+    Accept,
+}
+
+/// Return code of a response to a cancel request, as defined in `action_msgs/srv/CancelGoal`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CancelReturnCode {
+    None = 0,
+    Rejected = 1,
+    UnknownGoalId = 2,
+    GoalTerminated = 3,
+}
+
+impl From<i8> for CancelReturnCode {
+    fn from(return_code: i8) -> Self {
+        match return_code {
+            0 => Self::None,
+            2 => Self::UnknownGoalId,
+            3 => Self::GoalTerminated,
+            _ => Self::Rejected,
+        }
+    }
+}
+
+/// Response of an action server to [`ActionClient::cancel_goal`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CancelGoalResponse {
+    pub return_code: CancelReturnCode,
+    /// The goals that are now being canceled
+    pub goals_canceling: Vec<GoalUuid>,
+}
+
+impl Node {
+    /// Creates an action server, which is served while the node is spinning
+    ///
+    /// `handle_goal` decides whether a new goal is accepted. Accepted goals are passed to
+    /// `handle_accepted`, which is expected to start executing the goal and eventually
+    /// finish it through its [`ServerGoalHandle`]. `handle_cancel` decides whether a cancel
+    /// request for an active goal is accepted.
+    pub fn create_action_server<T, G, C, A>(
+        &self,
+        action_name: &str,
+        handle_goal: G,
+        handle_cancel: C,
+        handle_accepted: A,
+    ) -> Result<Arc<ActionServer<T>>, RclrsError>
+    where
+        T: ActionDefinition,
+        G: FnMut(&GoalUuid, &T::Goal) -> GoalResponse + Send + 'static,
+        C: FnMut(&ServerGoalHandle<T>) -> CancelResponse + Send + 'static,
+        A: FnMut(Arc<ServerGoalHandle<T>>) + Send + 'static,
+    {
+        let action_server = Arc::new(ActionServer::<T>::new(
+            self,
+            action_name,
+            handle_goal,
+            handle_cancel,
+            handle_accepted,
+        )?);
+        self.add_waitable(action_server.clone());
+        Ok(action_server)
+    }
+
+    /// Removes an action server from the node, so that it is no longer served
+    ///
+    /// Returns `false` if it doesn't belong to this node or was already removed.
+    pub fn remove_action_server<T>(&self, action_server: &Arc<ActionServer<T>>) -> bool
+    where
+        T: ActionDefinition,
+    {
+        self.remove_waitable(Arc::as_ptr(action_server) as *const ())
+    }
+
+    /// Creates an action client, whose responses are handled while the node is spinning
+    pub fn create_action_client<T>(
+        &self,
+        action_name: &str,
+    ) -> Result<Arc<ActionClient<T>>, RclrsError>
+    where
+        T: ActionDefinition,
+    {
+        let action_client = Arc::new(ActionClient::<T>::new(self, action_name)?);
+        self.add_waitable(action_client.clone());
+        Ok(action_client)
+    }
+
+    /// Removes an action client from the node, so that its callbacks are no longer executed
+    ///
+    /// Returns `false` if it doesn't belong to this node or was already removed.
+    pub fn remove_action_client<T>(&self, action_client: &Arc<ActionClient<T>>) -> bool
+    where
+        T: ActionDefinition,
+    {
+        self.remove_waitable(Arc::as_ptr(action_client) as *const ())
+    }
+}
+
+/// Generates a random (version 4) UUID for a new goal
+pub(crate) fn generate_goal_uuid() -> GoalUuid {
+    let mut uuid = random_bytes();
+    uuid[6] = (uuid[6] & 0x0f) | 0x40;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    uuid
+}
+
+/// Bytes from the random source of the OS, if there is one
+#[cfg(feature = "std")]
+fn random_bytes() -> GoalUuid {
+    let mut bytes = [0u8; 16];
+    match getrandom::getrandom(&mut bytes) {
+        Ok(()) => bytes,
+        Err(_) => pseudo_random_bytes(),
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn random_bytes() -> GoalUuid {
+    pseudo_random_bytes()
+}
+
+/// Mixes the current time, a counter and a stack address together, for when there is no
+/// source of randomness
+///
+/// This tells apart the goals of a client, but the bytes are predictable, and goals of
+/// clients started at the same time may collide.
+fn pseudo_random_bytes() -> GoalUuid {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut now: rcutils_time_point_value_t = 0;
+    unsafe { rcutils_system_time_now(&mut now as *mut _) };
+    let mut state = (now as u64)
+        ^ COUNTER
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ ((&now as *const _ as usize as u64) << 16);
+
+    let mut bytes = [0u8; 16];
+    for chunk in bytes.chunks_mut(8) {
+        chunk.copy_from_slice(&splitmix64(&mut state).to_le_bytes());
+    }
+    bytes
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+type RmwMsg<M> = <M as MessageDefinition>::RmwMsg;
+
+/// The `SendGoal` request of the action `T`
+pub(crate) type SendGoalRequest<T> = rmw::SendGoalRequest<RmwMsg<<T as ActionDefinition>::Goal>>;
+
+/// The `GetResult` response of the action `T`
+pub(crate) type GetResultResponse<T> =
+    rmw::GetResultResponse<RmwMsg<<T as ActionDefinition>::Result>>;
+
+/// The feedback message of the action `T`
+pub(crate) type FeedbackMessage<T> =
+    rmw::FeedbackMessage<RmwMsg<<T as ActionDefinition>::Feedback>>;
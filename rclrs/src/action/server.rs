@@ -0,0 +1,726 @@
+use crate::error::{RclrsError, ToResult};
+use crate::rcl_bindings::*;
+use crate::wait::{WaitSet, Waitable, WaitableCount};
+use crate::{Node, NodeHandle};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use cstr_core::CString;
+use rclrs_common::error::{to_rcl_result, ActionErrorCode, RclReturnCode};
+use rclrs_common::rmw::{GetResultRequest, SendGoalResponse, Time};
+use rclrs_common::traits::{ActionDefinition, MessageDefinition};
+
+use super::{
+    CancelResponse, CancelReturnCode, FeedbackMessage, GetResultResponse, GoalResponse, GoalStatus,
+    GoalUuid, SendGoalRequest,
+};
+
+#[cfg(not(feature = "std"))]
+use spin::{Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+use parking_lot::{Mutex, MutexGuard};
+
+/// Result of a finished goal, kept until the goal expires, along with the `GetResult`
+/// requests that arrived before the goal finished
+struct GoalResultEntry<T>
+where
+    T: ActionDefinition,
+{
+    response: Option<GetResultResponse<T>>,
+    pending_requests: Vec<rmw_request_id_t>,
+}
+
+pub struct ActionServerHandle<T>
+where
+    T: ActionDefinition,
+{
+    handle: Mutex<rcl_action_server_t>,
+    // `rcl_action_server_t` keeps a pointer to its clock, so it must not move
+    clock: Box<Mutex<rcl_clock_t>>,
+    node_handle: Arc<NodeHandle>,
+    results: Mutex<BTreeMap<GoalUuid, GoalResultEntry<T>>>,
+    action: core::marker::PhantomData<T>,
+}
+
+// SAFETY: `rcl_action` servers may be used from any thread as long as calls on the same
+// server are not concurrent, which the mutexes ensure. The stored result responses are the
+// C form of `T::Result`, which own their data like the `Send` result itself, and are only
+// touched while `results` is locked.
+unsafe impl<T> Send for ActionServerHandle<T>
+where
+    T: ActionDefinition,
+    T::Result: Send,
+{
+}
+unsafe impl<T> Sync for ActionServerHandle<T>
+where
+    T: ActionDefinition,
+    T::Result: Send,
+{
+}
+
+impl<T> ActionServerHandle<T>
+where
+    T: ActionDefinition,
+{
+    pub fn lock(&self) -> MutexGuard<rcl_action_server_t> {
+        self.handle.lock()
+    }
+
+    /// Current time of the server's clock, as (seconds, nanoseconds)
+    fn now(&self) -> Result<(i32, u32), RclrsError> {
+        let mut now: rcl_time_point_value_t = 0;
+        unsafe { rcl_clock_get_now(&mut *self.clock.lock() as *mut _, &mut now as *mut _).ok()? };
+        Ok(((now / 1_000_000_000) as i32, (now % 1_000_000_000) as u32))
+    }
+
+    fn publish_status(&self) -> Result<(), RclrsError> {
+        let handle = &*self.lock();
+        unsafe {
+            let mut status_array = rcl_action_get_zero_initialized_goal_status_array();
+            rcl_action_get_goal_status_array(handle as *const _, &mut status_array as *mut _)
+                .ok()?;
+            let result = rcl_action_publish_status(
+                handle as *const _,
+                &status_array.msg as *const _ as *const _,
+            );
+            rcl_action_goal_status_array_fini(&mut status_array as *mut _);
+            result.ok()
+        }
+    }
+
+    /// Stores the result of a finished goal and sends it to every client that asked for it
+    fn set_result(
+        &self,
+        goal_id: &GoalUuid,
+        status: GoalStatus,
+        result: &T::Result,
+    ) -> Result<(), RclrsError> {
+        let results = &mut *self.results.lock();
+        let entry = results.entry(*goal_id).or_insert_with(|| GoalResultEntry {
+            response: None,
+            pending_requests: Vec::new(),
+        });
+        let response = entry.response.insert(GetResultResponse::<T> {
+            status: status as i8,
+            result: T::Result::into_rmw(result),
+        });
+
+        let handle = &*self.lock();
+        let mut send_result = Ok(());
+        for mut request_header in entry.pending_requests.drain(..) {
+            let result = unsafe {
+                rcl_action_send_result_response(
+                    handle as *const _,
+                    &mut request_header as *mut _,
+                    response as *mut _ as *mut _,
+                )
+            };
+            send_result = send_result.and(result.ok());
+        }
+        send_result
+    }
+}
+
+impl<T> Drop for ActionServerHandle<T>
+where
+    T: ActionDefinition,
+{
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        let node_handle = &mut *self.node_handle.lock();
+        unsafe {
+            rcl_action_server_fini(handle as *mut _, node_handle as *mut _)
+                .log_on_error("failed to finalize an action server");
+            rcl_clock_fini(self.clock.get_mut() as *mut _)
+                .log_on_error("failed to finalize the clock of an action server");
+        }
+    }
+}
+
+/// Handle to a goal accepted by an [`ActionServer`], used to report on its execution
+///
+/// A goal has to be finished with [`succeed`](Self::succeed), [`abort`](Self::abort) or,
+/// once a cancel request was accepted, [`canceled`](Self::canceled).
+///
+/// Goal handles are `Send` and `Sync`, so goals can be executed on worker threads.
+pub struct ServerGoalHandle<T>
+where
+    T: ActionDefinition,
+{
+    goal_id: GoalUuid,
+    goal: T::Goal,
+    // Owned by the `rcl_action_server_t`, and only valid until the goal expires, which
+    // can only happen once the goal is in a terminal state
+    rcl_handle: *mut rcl_action_goal_handle_t,
+    status: Mutex<GoalStatus>,
+    server: Arc<ActionServerHandle<T>>,
+}
+
+// SAFETY: `rcl_handle` is only dereferenced while the server is locked, and never after
+// the goal reached a terminal state, after which `rcl_action` may expire it. The goal is
+// shared like the goal handle, and the server is `Send` and `Sync` under the same bounds.
+unsafe impl<T> Send for ServerGoalHandle<T>
+where
+    T: ActionDefinition,
+    T::Goal: Send + Sync,
+    T::Result: Send,
+{
+}
+unsafe impl<T> Sync for ServerGoalHandle<T>
+where
+    T: ActionDefinition,
+    T::Goal: Send + Sync,
+    T::Result: Send,
+{
+}
+
+impl<T> ServerGoalHandle<T>
+where
+    T: ActionDefinition,
+{
+    pub fn goal_id(&self) -> &GoalUuid {
+        &self.goal_id
+    }
+
+    pub fn goal(&self) -> &T::Goal {
+        &self.goal
+    }
+
+    pub fn status(&self) -> GoalStatus {
+        *self.status.lock()
+    }
+
+    /// Whether a cancel request for this goal was accepted
+    pub fn is_canceling(&self) -> bool {
+        self.status() == GoalStatus::Canceling
+    }
+
+    /// Whether the goal is still executing or canceling
+    pub fn is_active(&self) -> bool {
+        !self.status().is_terminal()
+    }
+
+    pub fn publish_feedback(&self, feedback: &T::Feedback) -> Result<(), RclrsError> {
+        let mut message = FeedbackMessage::<T> {
+            goal_id: self.goal_id,
+            feedback: T::Feedback::into_rmw(feedback),
+        };
+        unsafe {
+            rcl_action_publish_feedback(
+                &*self.server.lock() as *const _,
+                &mut message as *mut _ as *mut _,
+            )
+            .ok()
+        }
+    }
+
+    /// Finishes the goal successfully with the given result
+    pub fn succeed(&self, result: &T::Result) -> Result<(), RclrsError> {
+        self.finish(
+            rcl_action_goal_event_t::GOAL_EVENT_SUCCEED,
+            GoalStatus::Succeeded,
+            result,
+        )
+    }
+
+    /// Finishes the goal unsuccessfully with the given result
+    pub fn abort(&self, result: &T::Result) -> Result<(), RclrsError> {
+        self.finish(
+            rcl_action_goal_event_t::GOAL_EVENT_ABORT,
+            GoalStatus::Aborted,
+            result,
+        )
+    }
+
+    /// Finishes a canceling goal with the given result
+    pub fn canceled(&self, result: &T::Result) -> Result<(), RclrsError> {
+        self.finish(
+            rcl_action_goal_event_t::GOAL_EVENT_CANCELED,
+            GoalStatus::Canceled,
+            result,
+        )
+    }
+
+    fn update_state(
+        &self,
+        event: rcl_action_goal_event_t,
+        new_status: GoalStatus,
+    ) -> Result<(), RclrsError> {
+        let status = &mut *self.status.lock();
+        if status.is_terminal() {
+            return Err(RclrsError::from(ActionErrorCode::ActionGoalEventInvalid)
+                .context("the goal has already finished"));
+        }
+        let _server = self.server.lock();
+        unsafe { rcl_action_update_goal_state(self.rcl_handle, event).ok()? };
+        *status = new_status;
+        Ok(())
+    }
+
+    fn finish(
+        &self,
+        event: rcl_action_goal_event_t,
+        status: GoalStatus,
+        result: &T::Result,
+    ) -> Result<(), RclrsError> {
+        self.update_state(event, status)?;
+        self.server.publish_status()?;
+        self.server.set_result(&self.goal_id, status, result)?;
+        unsafe { rcl_action_notify_goal_done(&*self.server.lock() as *const _).ok() }
+    }
+}
+
+type GoalCallback<T> =
+    Box<dyn FnMut(&GoalUuid, &<T as ActionDefinition>::Goal) -> GoalResponse + Send>;
+type CancelCallback<T> = Box<dyn FnMut(&ServerGoalHandle<T>) -> CancelResponse + Send>;
+type AcceptedCallback<T> = Box<dyn FnMut(Arc<ServerGoalHandle<T>>) + Send>;
+
+/// Server side of an action, built on `rcl_action`
+///
+/// See [`Node::create_action_server`]. Action servers are `Send` and `Sync`, so their
+/// callbacks have to be `Send`.
+pub struct ActionServer<T>
+where
+    T: ActionDefinition,
+{
+    pub handle: Arc<ActionServerHandle<T>>,
+    handle_goal: Mutex<GoalCallback<T>>,
+    handle_cancel: Mutex<CancelCallback<T>>,
+    handle_accepted: Mutex<AcceptedCallback<T>>,
+    goal_handles: Mutex<BTreeMap<GoalUuid, Arc<ServerGoalHandle<T>>>>,
+}
+
+impl<T> ActionServer<T>
+where
+    T: ActionDefinition,
+{
+    pub fn new<G, C, A>(
+        node: &Node,
+        action_name: &str,
+        handle_goal: G,
+        handle_cancel: C,
+        handle_accepted: A,
+    ) -> Result<Self, RclrsError>
+    where
+        G: FnMut(&GoalUuid, &T::Goal) -> GoalResponse + Send + 'static,
+        C: FnMut(&ServerGoalHandle<T>) -> CancelResponse + Send + 'static,
+        A: FnMut(Arc<ServerGoalHandle<T>>) + Send + 'static,
+    {
+        let mut action_server_handle = unsafe { rcl_action_get_zero_initialized_server() };
+        let type_support = T::type_support() as *const rosidl_action_type_support_t;
+        let action_name_c_string = CString::new(action_name).map_err(|_| {
+            RclrsError::from(RclReturnCode::InvalidArgument)
+                .context(format!("action name {:?} contains a NUL byte", action_name))
+        })?;
+
+        let mut clock: Box<Mutex<rcl_clock_t>> =
+            Box::new(Mutex::new(unsafe { core::mem::zeroed() }));
+        unsafe {
+            let mut allocator = rcutils_get_default_allocator();
+            rcl_clock_init(
+                rcl_clock_type_t::RCL_ROS_TIME,
+                clock.get_mut() as *mut _,
+                &mut allocator as *mut _,
+            )
+            .ok()?;
+        }
+
+        let node_handle = &mut *node.handle.lock();
+        let init_result = unsafe {
+            let action_server_options = rcl_action_server_get_default_options();
+            rcl_action_server_init(
+                &mut action_server_handle as *mut _,
+                node_handle as *mut _,
+                clock.get_mut() as *mut _,
+                type_support,
+                action_name_c_string.as_ptr(),
+                &action_server_options as *const _,
+            )
+        };
+        if let Err(error) = init_result.ok() {
+            unsafe { rcl_clock_fini(clock.get_mut() as *mut _) };
+            return Err(error.context(format!(
+                "failed to create an action server for '{}'",
+                action_name
+            )));
+        }
+
+        let handle = Arc::new(ActionServerHandle {
+            handle: Mutex::new(action_server_handle),
+            clock,
+            node_handle: node.handle.clone(),
+            results: Mutex::new(BTreeMap::new()),
+            action: core::marker::PhantomData,
+        });
+
+        Ok(Self {
+            handle,
+            handle_goal: Mutex::new(Box::new(handle_goal)),
+            handle_cancel: Mutex::new(Box::new(handle_cancel)),
+            handle_accepted: Mutex::new(Box::new(handle_accepted)),
+            goal_handles: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Serves one pending `SendGoal` request, if there is one
+    fn handle_goal_request(&self) -> Result<(), RclrsError> {
+        let mut request_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut request = SendGoalRequest::<T>::default();
+        let take_result = unsafe {
+            rcl_action_take_goal_request(
+                &*self.handle.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut request as *mut _ as *mut _,
+            )
+        };
+        match to_rcl_result(take_result) {
+            Ok(()) => (),
+            Err(RclReturnCode::ActionError(ActionErrorCode::ActionServerTakeFailed)) => {
+                return Ok(())
+            }
+            Err(error) => {
+                return Err(RclrsError::from_rcl(error).context("failed to take a goal request"))
+            }
+        };
+
+        let goal_id = request.goal_id;
+        let goal = T::Goal::from_rmw(&request.goal);
+
+        let accepted =
+            (*self.handle_goal.lock())(&goal_id, &goal) == GoalResponse::AcceptAndExecute;
+        let (stamp_sec, stamp_nanosec) = self.handle.now()?;
+
+        // The goal is accepted before responding, so that the client's `GetResult` request
+        // can't arrive before the server knows about the goal
+        let rcl_goal_handle = if accepted {
+            let mut goal_info = unsafe { rcl_action_get_zero_initialized_goal_info() };
+            goal_info.goal_id.uuid = goal_id;
+            goal_info.stamp.sec = stamp_sec;
+            goal_info.stamp.nanosec = stamp_nanosec;
+            let rcl_goal_handle = unsafe {
+                rcl_action_accept_new_goal(
+                    &mut *self.handle.lock() as *mut _,
+                    &goal_info as *const _,
+                )
+            };
+            if rcl_goal_handle.is_null() {
+                return Err(
+                    RclrsError::from_rcl(RclReturnCode::Error).context("failed to accept a goal")
+                );
+            }
+            Some(rcl_goal_handle)
+        } else {
+            None
+        };
+
+        let mut response = SendGoalResponse {
+            accepted,
+            stamp: Time {
+                sec: stamp_sec,
+                nanosec: stamp_nanosec,
+            },
+        };
+        unsafe {
+            rcl_action_send_goal_response(
+                &*self.handle.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut response as *mut _ as *mut _,
+            )
+            .ok()?;
+        }
+
+        let rcl_goal_handle = match rcl_goal_handle {
+            Some(rcl_goal_handle) => rcl_goal_handle,
+            None => return Ok(()),
+        };
+        let goal_handle = Arc::new(ServerGoalHandle {
+            goal_id,
+            goal,
+            rcl_handle: rcl_goal_handle,
+            status: Mutex::new(GoalStatus::Accepted),
+            server: self.handle.clone(),
+        });
+        goal_handle.update_state(
+            rcl_action_goal_event_t::GOAL_EVENT_EXECUTE,
+            GoalStatus::Executing,
+        )?;
+        self.handle.publish_status()?;
+
+        self.goal_handles
+            .lock()
+            .insert(goal_id, goal_handle.clone());
+        (*self.handle_accepted.lock())(goal_handle);
+        Ok(())
+    }
+
+    /// Serves one pending `CancelGoal` request, if there is one
+    ///
+    /// `rcl_action` picks the goals the request applies to, and the cancel callback then
+    /// decides for each of them whether it is actually canceled.
+    fn handle_cancel_request(&self) -> Result<(), RclrsError> {
+        let mut request_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut request: action_msgs__srv__CancelGoal_Request = unsafe { core::mem::zeroed() };
+        let mut response = unsafe { rcl_action_get_zero_initialized_cancel_response() };
+        unsafe { action_msgs__srv__CancelGoal_Request__init(&mut request as *mut _) };
+
+        let process_result = {
+            let handle = &*self.handle.lock();
+            unsafe {
+                rcl_action_take_cancel_request(
+                    handle as *const _,
+                    &mut request_header as *mut _,
+                    &mut request as *mut _ as *mut _,
+                )
+                .ok()
+                .and_then(|()| {
+                    rcl_action_process_cancel_request(
+                        handle as *const _,
+                        &request as *const _,
+                        &mut response as *mut _,
+                    )
+                    .ok()
+                })
+            }
+        };
+        unsafe { action_msgs__srv__CancelGoal_Request__fini(&mut request as *mut _) };
+        match process_result {
+            Ok(()) => (),
+            Err(error)
+                if error.code()
+                    == &RclReturnCode::ActionError(ActionErrorCode::ActionServerTakeFailed) =>
+            {
+                return Ok(())
+            }
+            Err(error) => {
+                unsafe { rcl_action_cancel_response_fini(&mut response as *mut _) };
+                return Err(error);
+            }
+        };
+
+        // The goal handles are looked up first, so that the cancel callback runs without
+        // `goal_handles` locked and may e.g. finish the goal right away
+        let goals_canceling = &mut response.msg.goals_canceling;
+        let goal_handles: Vec<Option<Arc<ServerGoalHandle<T>>>> = {
+            let goal_handles = &*self.goal_handles.lock();
+            (0..goals_canceling.size)
+                .map(|index| {
+                    let goal_info = unsafe { &*goals_canceling.data.add(index) };
+                    goal_handles.get(&goal_info.goal_id.uuid).cloned()
+                })
+                .collect()
+        };
+
+        // Keep only the goals whose cancellation was accepted, compacting them in place
+        let mut num_canceling = 0;
+        for (index, goal_handle) in goal_handles.into_iter().enumerate() {
+            let canceled = match goal_handle {
+                Some(goal_handle) => {
+                    (*self.handle_cancel.lock())(&goal_handle) == CancelResponse::Accept
+                        && goal_handle
+                            .update_state(
+                                rcl_action_goal_event_t::GOAL_EVENT_CANCEL_GOAL,
+                                GoalStatus::Canceling,
+                            )
+                            .is_ok()
+                }
+                None => false,
+            };
+            if canceled {
+                unsafe {
+                    core::ptr::swap(
+                        goals_canceling.data.add(num_canceling),
+                        goals_canceling.data.add(index),
+                    )
+                };
+                num_canceling += 1;
+            }
+        }
+        if goals_canceling.size > 0 && num_canceling == 0 {
+            response.msg.return_code = CancelReturnCode::Rejected as i8;
+        } else if num_canceling > 0 {
+            response.msg.return_code = CancelReturnCode::None as i8;
+        }
+        goals_canceling.size = num_canceling;
+
+        let send_result = unsafe {
+            rcl_action_send_cancel_response(
+                &*self.handle.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut response.msg as *mut _ as *mut _,
+            )
+        };
+        unsafe { rcl_action_cancel_response_fini(&mut response as *mut _) };
+        send_result.ok()?;
+
+        if num_canceling > 0 {
+            self.handle.publish_status()?;
+        }
+        Ok(())
+    }
+
+    /// Serves one pending `GetResult` request, if there is one
+    ///
+    /// If the goal hasn't finished yet, the response is sent once it does.
+    fn handle_result_request(&self) -> Result<(), RclrsError> {
+        let mut request_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut request = GetResultRequest::default();
+        let take_result = unsafe {
+            rcl_action_take_result_request(
+                &*self.handle.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut request as *mut _ as *mut _,
+            )
+        };
+        match to_rcl_result(take_result) {
+            Ok(()) => (),
+            Err(RclReturnCode::ActionError(ActionErrorCode::ActionServerTakeFailed)) => {
+                return Ok(())
+            }
+            Err(error) => {
+                return Err(RclrsError::from_rcl(error).context("failed to take a result request"))
+            }
+        };
+
+        let goal_id = request.goal_id;
+        let results = &mut *self.handle.results.lock();
+        let handle = &*self.handle.lock();
+        let mut goal_info = unsafe { rcl_action_get_zero_initialized_goal_info() };
+        goal_info.goal_id.uuid = goal_id;
+        let goal_exists =
+            unsafe { rcl_action_server_goal_exists(handle as *const _, &goal_info as *const _) };
+
+        let response = match results.get_mut(&goal_id) {
+            Some(GoalResultEntry {
+                response: Some(response),
+                ..
+            }) => response,
+            _ if goal_exists => {
+                results
+                    .entry(goal_id)
+                    .or_insert_with(|| GoalResultEntry {
+                        response: None,
+                        pending_requests: Vec::new(),
+                    })
+                    .pending_requests
+                    .push(request_header);
+                return Ok(());
+            }
+            _ => {
+                // Unknown goal, respond right away with an unknown status
+                let mut response = GetResultResponse::<T> {
+                    status: GoalStatus::Unknown as i8,
+                    result: Default::default(),
+                };
+                return unsafe {
+                    rcl_action_send_result_response(
+                        handle as *const _,
+                        &mut request_header as *mut _,
+                        &mut response as *mut _ as *mut _,
+                    )
+                    .ok()
+                };
+            }
+        };
+        unsafe {
+            rcl_action_send_result_response(
+                handle as *const _,
+                &mut request_header as *mut _,
+                response as *mut _ as *mut _,
+            )
+            .ok()
+        }
+    }
+
+    /// Drops the goals whose results have been kept around for long enough
+    fn handle_expired_goals(&self) -> Result<(), RclrsError> {
+        loop {
+            let mut expired_goal = unsafe { rcl_action_get_zero_initialized_goal_info() };
+            let mut num_expired: usize = 0;
+            unsafe {
+                rcl_action_expire_goals(
+                    &*self.handle.lock() as *const _,
+                    &mut expired_goal as *mut _,
+                    1,
+                    &mut num_expired as *mut _,
+                )
+                .ok()?;
+            }
+            if num_expired == 0 {
+                return Ok(());
+            }
+
+            let goal_id = expired_goal.goal_id.uuid;
+            self.goal_handles.lock().remove(&goal_id);
+            self.handle.results.lock().remove(&goal_id);
+        }
+    }
+}
+
+impl<T> Waitable for ActionServer<T>
+where
+    T: ActionDefinition,
+{
+    fn count(&self) -> WaitableCount {
+        let mut count = WaitableCount::default();
+        // Should this fail, nothing is counted and adding the server to the wait set fails
+        unsafe {
+            rcl_action_server_wait_set_get_num_entities(
+                &*self.handle.lock() as *const _,
+                &mut count.subscriptions as *mut _,
+                &mut count.guard_conditions as *mut _,
+                &mut count.timers as *mut _,
+                &mut count.clients as *mut _,
+                &mut count.services as *mut _,
+            );
+        }
+        count
+    }
+
+    fn add_to_wait_set(&self, wait_set: &mut WaitSet) -> Result<(), RclrsError> {
+        unsafe {
+            rcl_action_wait_set_add_action_server(
+                &mut wait_set.wait_set as *mut _,
+                &*self.handle.lock() as *const _,
+                core::ptr::null_mut(),
+            )
+            .ok()?;
+        }
+        Ok(())
+    }
+
+    fn execute(&self, wait_set: &WaitSet) -> Result<(), RclrsError> {
+        let mut is_goal_request_ready = false;
+        let mut is_cancel_request_ready = false;
+        let mut is_result_request_ready = false;
+        let mut is_goal_expired = false;
+        unsafe {
+            rcl_action_server_wait_set_get_entities_ready(
+                &wait_set.wait_set as *const _,
+                &*self.handle.lock() as *const _,
+                &mut is_goal_request_ready as *mut _,
+                &mut is_cancel_request_ready as *mut _,
+                &mut is_result_request_ready as *mut _,
+                &mut is_goal_expired as *mut _,
+            )
+            .ok()?;
+        }
+
+        if is_goal_request_ready {
+            self.handle_goal_request()?;
+        }
+        if is_cancel_request_ready {
+            self.handle_cancel_request()?;
+        }
+        if is_result_request_ready {
+            self.handle_result_request()?;
+        }
+        if is_goal_expired {
+            self.handle_expired_goals()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::error::{RclrsError, ToResult};
+use crate::rcl_bindings::*;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use cstr_core::{c_char, CStr, CString};
+use rclrs_common::error::RclReturnCode;
+
+/// Parses command line arguments, e.g. `["--ros-args", "-r", "foo:=bar"]`, for `rcl`
+///
+/// Invalid ROS arguments are reported as [`RclReturnCode::ParsingError`].
+pub(crate) fn parse_arguments(arguments: &[String]) -> Result<rcl_arguments_t, RclrsError> {
+    let c_arguments = arguments
+        .iter()
+        .map(|argument| {
+            CString::new(argument.as_str()).map_err(|_| {
+                RclrsError::from(RclReturnCode::InvalidArgument)
+                    .context(format!("argument {:?} contains a NUL byte", argument))
+            })
+        })
+        .collect::<Result<Vec<CString>, _>>()?;
+    let c_argument_ptrs: Vec<*const c_char> = c_arguments
+        .iter()
+        .map(|argument| argument.as_ptr())
+        .collect();
+
+    let mut rcl_arguments = unsafe { rcl_get_zero_initialized_arguments() };
+    unsafe {
+        rcl_parse_arguments(
+            c_argument_ptrs.len() as i32,
+            c_argument_ptrs.as_ptr(),
+            rcutils_get_default_allocator(),
+            &mut rcl_arguments as *mut _,
+        )
+        .ok()?;
+    }
+    Ok(rcl_arguments)
+}
+
+/// Returns the arguments that `rcl` did not recognize, i.e. everything outside of
+/// `--ros-args ... [--]`, including the program name
+///
+/// `arguments` have to be the arguments `rcl_arguments` was parsed from.
+pub(crate) fn get_unparsed_arguments(
+    rcl_arguments: &rcl_arguments_t,
+    arguments: &[String],
+) -> Result<Vec<String>, RclrsError> {
+    let count = unsafe { rcl_arguments_get_count_unparsed(rcl_arguments as *const _) };
+    if count <= 0 {
+        return Ok(Vec::new());
+    }
+
+    let allocator = unsafe { rcutils_get_default_allocator() };
+    let mut indices: *mut i32 = core::ptr::null_mut();
+    unsafe {
+        rcl_arguments_get_unparsed(
+            rcl_arguments as *const _,
+            rcutils_get_default_allocator(),
+            &mut indices as *mut _,
+        )
+        .ok()?;
+    }
+
+    let unparsed_arguments = (0..count as usize)
+        .filter_map(|i| arguments.get(unsafe { *indices.add(i) } as usize))
+        .cloned()
+        .collect();
+    unsafe { deallocate(indices as *mut _, &allocator) };
+    Ok(unparsed_arguments)
+}
+
+/// Copies a string allocated by `rcl` with `allocator` and frees it, `None` if it's null
+pub(crate) unsafe fn take_allocated_string(
+    ptr: *mut c_char,
+    allocator: &rcl_allocator_t,
+) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let string = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+    deallocate(ptr as *mut _, allocator);
+    Some(string)
+}
+
+unsafe fn deallocate(ptr: *mut cty::c_void, allocator: &rcl_allocator_t) {
+    if let Some(deallocate) = allocator.deallocate {
+        deallocate(ptr, allocator.state);
+    }
+}
@@ -0,0 +1,659 @@
+use crate::error::{ErrorContext, RclrsError, ToResult};
+use crate::node::graph::string_from_ptr;
+use crate::rcl_bindings::*;
+use crate::wait::{WaitSet, Waitable, WaitableCount};
+use crate::{Context, Node, NodeHandle};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use cstr_core::{c_char, CString};
+use rclrs_common::error::{to_rcl_result, RclErrorCode, RclReturnCode, ServiceErrorCode};
+use std::path::PathBuf;
+
+use super::{
+    Component, ComponentConstructor, ComponentFactory, ComponentLibraryFunction, ComponentOptions,
+    COMPONENT_ABI_VERSION, COMPONENT_BUILD_ID, COMPONENT_LIBRARY_SYMBOL,
+};
+
+use parking_lot::Mutex;
+
+/// Resource type under which libraries list their components in the ament index
+pub const COMPONENT_RESOURCE_TYPE: &str = "rclrs_components";
+
+// Types of parameter values, as defined in `rcl_interfaces/msg/ParameterType`
+const PARAMETER_NOT_SET: u8 = 0;
+const PARAMETER_BOOL: u8 = 1;
+const PARAMETER_INTEGER: u8 = 2;
+const PARAMETER_DOUBLE: u8 = 3;
+const PARAMETER_STRING: u8 = 4;
+const PARAMETER_BYTE_ARRAY: u8 = 5;
+const PARAMETER_BOOL_ARRAY: u8 = 6;
+const PARAMETER_INTEGER_ARRAY: u8 = 7;
+const PARAMETER_DOUBLE_ARRAY: u8 = 8;
+const PARAMETER_STRING_ARRAY: u8 = 9;
+
+/// A component that was loaded into a [`ComponentManager`]
+#[derive(Clone)]
+pub struct LoadedComponent {
+    pub unique_id: u64,
+    pub full_node_name: String,
+    pub component: Arc<dyn Component>,
+}
+
+/// The contents of a `LoadNode` request
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadComponentRequest {
+    pub package_name: String,
+    pub plugin_name: String,
+    pub options: ComponentOptions,
+}
+
+/// Loads components at runtime and spins their nodes together with its own
+///
+/// Like the `rclcpp` component container, it serves the `composition_interfaces` services
+/// `~/_container/load_node`, `~/_container/unload_node` and `~/_container/list_nodes`, so
+/// `ros2 component load` and launch files work with it. See [`Component`] for how
+/// components are registered.
+///
+/// Libraries are never unloaded, since code from them may still run while a component is
+/// being dropped.
+pub struct ComponentManager {
+    node: Node,
+    services: Arc<ComponentManagerServices>,
+}
+
+impl ComponentManager {
+    /// Creates the container node, which is called `node_name`
+    pub fn new(context: &Context, node_name: &str) -> Result<Self, RclrsError> {
+        let node = Node::new(node_name, context)?;
+        let services = Arc::new(ComponentManagerServices::new(&node, context.clone())?);
+        node.add_waitable(services.clone());
+        Ok(Self { node, services })
+    }
+
+    /// The container node
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Returns the currently loaded components
+    pub fn components(&self) -> Vec<LoadedComponent> {
+        self.services.components.lock().clone()
+    }
+
+    /// Loads a component, as if requested through `~/_container/load_node`
+    ///
+    /// Returns the loaded component, or an error message like the one in the response.
+    pub fn load(&self, request: LoadComponentRequest) -> Result<LoadedComponent, String> {
+        self.services.load(request)
+    }
+
+    /// Unloads the component with the given unique ID, returning `false` if there is none
+    pub fn unload(&self, unique_id: u64) -> bool {
+        self.services.unload(unique_id)
+    }
+
+    /// Spins the container node and the nodes of all loaded components until the context
+    /// is shut down, see [`spin_nodes`](crate::spin_nodes)
+    pub fn spin(&self) -> Result<(), RclrsError> {
+        crate::spin_until_shutdown(&self.node.context, || {
+            // Components loaded or unloaded by a request are picked up in the next iteration
+            let components = self.components();
+            let mut nodes = Vec::with_capacity(1 + components.len());
+            nodes.push(&self.node);
+            nodes.extend(components.iter().map(|loaded| loaded.component.node()));
+            crate::spin_nodes_once(&nodes, -1)
+        })
+    }
+}
+
+/// The services of a [`ComponentManager`], along with the components it loaded
+struct ComponentManagerServices {
+    load_node: Mutex<rcl_service_t>,
+    unload_node: Mutex<rcl_service_t>,
+    list_nodes: Mutex<rcl_service_t>,
+    node_handle: Arc<NodeHandle>,
+    context: Context,
+    components: Mutex<Vec<LoadedComponent>>,
+    next_unique_id: Mutex<u64>,
+    /// The components of every library that was loaded, by library path
+    libraries: Mutex<Vec<(PathBuf, Vec<LibraryComponent>)>>,
+}
+
+/// A component type that a library registered
+#[derive(Clone)]
+struct LibraryComponent {
+    name: String,
+    constructor: ComponentConstructor,
+}
+
+// SAFETY: The `rcl` services are only accessed through their mutexes, and the components
+// are `Send` and `Sync`.
+unsafe impl Send for ComponentManagerServices {}
+unsafe impl Sync for ComponentManagerServices {}
+
+impl ComponentManagerServices {
+    fn new(node: &Node, context: Context) -> Result<Self, RclrsError> {
+        let node_handle = &mut *node.handle.lock();
+        let (load_node, unload_node, list_nodes) = unsafe {
+            (
+                init_service(
+                    node_handle,
+                    rosidl_typesupport_c__get_service_type_support_handle__composition_interfaces__srv__LoadNode(),
+                    "~/_container/load_node",
+                )?,
+                init_service(
+                    node_handle,
+                    rosidl_typesupport_c__get_service_type_support_handle__composition_interfaces__srv__UnloadNode(),
+                    "~/_container/unload_node",
+                )?,
+                init_service(
+                    node_handle,
+                    rosidl_typesupport_c__get_service_type_support_handle__composition_interfaces__srv__ListNodes(),
+                    "~/_container/list_nodes",
+                )?,
+            )
+        };
+
+        Ok(Self {
+            load_node: Mutex::new(load_node),
+            unload_node: Mutex::new(unload_node),
+            list_nodes: Mutex::new(list_nodes),
+            node_handle: node.handle.clone(),
+            context,
+            components: Mutex::new(Vec::new()),
+            next_unique_id: Mutex::new(1),
+            libraries: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn load(&self, request: LoadComponentRequest) -> Result<LoadedComponent, String> {
+        let library_path = find_library(&request.package_name, &request.plugin_name)?;
+        let factories = self.load_library(library_path)?;
+        let factory = factories
+            .iter()
+            .find(|factory| factory.name == request.plugin_name)
+            .ok_or_else(|| format!("No class found with name '{}'", request.plugin_name))?;
+        let component = construct(factory.constructor, &self.context, &request.options)
+            .map_err(|error| format!("Failed to create component: {}", error))?;
+
+        let unique_id = {
+            let next_unique_id = &mut *self.next_unique_id.lock();
+            let unique_id = *next_unique_id;
+            *next_unique_id += 1;
+            unique_id
+        };
+        let loaded = LoadedComponent {
+            unique_id,
+            full_node_name: component.node().fully_qualified_name(),
+            component: Arc::from(component),
+        };
+        self.components.lock().push(loaded.clone());
+        Ok(loaded)
+    }
+
+    fn unload(&self, unique_id: u64) -> bool {
+        let components = &mut *self.components.lock();
+        let len = components.len();
+        components.retain(|loaded| loaded.unique_id != unique_id);
+        components.len() != len
+    }
+
+    /// Returns the components of the library, opening it if it wasn't already
+    fn load_library(&self, path: PathBuf) -> Result<Vec<LibraryComponent>, String> {
+        let libraries = &mut *self.libraries.lock();
+        if let Some((_, factories)) = libraries.iter().find(|(loaded, _)| *loaded == path) {
+            return Ok(factories.clone());
+        }
+
+        let path_c_string = CString::new(path.to_string_lossy().into_owned())
+            .map_err(|_| format!("Invalid library path {}", path.display()))?;
+        let symbol_c_string = CString::new(COMPONENT_LIBRARY_SYMBOL)
+            .map_err(|_| format!("Invalid symbol name {}", COMPONENT_LIBRARY_SYMBOL))?;
+        let mut factories: Vec<LibraryComponent> = Vec::new();
+        unsafe {
+            let library = libc::dlopen(path_c_string.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+            if library.is_null() {
+                return Err(format!("Failed to load library: {}", dlerror_string()));
+            }
+            let symbol = libc::dlsym(library, symbol_c_string.as_ptr());
+            if symbol.is_null() {
+                return Err(format!(
+                    "Library {} does not register any components: {}",
+                    path.display(),
+                    dlerror_string()
+                ));
+            }
+            let library_function: ComponentLibraryFunction = core::mem::transmute(symbol);
+            let component_library = library_function();
+            if component_library.abi_version != COMPONENT_ABI_VERSION {
+                return Err(format!(
+                    "Library {} uses component ABI version {}, expected {}",
+                    path.display(),
+                    component_library.abi_version,
+                    COMPONENT_ABI_VERSION
+                ));
+            }
+            let build_id = string_from_ptr(component_library.build_id);
+            let expected_build_id = COMPONENT_BUILD_ID.trim_end_matches('\0');
+            if build_id != expected_build_id {
+                return Err(format!(
+                    "Library {} was built with {}, expected {}",
+                    path.display(),
+                    build_id,
+                    expected_build_id
+                ));
+            }
+            (component_library.register_components)(
+                register_factory,
+                &mut factories as *mut Vec<LibraryComponent> as *mut c_void,
+            );
+        }
+        libraries.push((path, factories.clone()));
+        Ok(factories)
+    }
+
+    /// Serves one pending `~/_container/load_node` request, if there is one
+    fn handle_load_node(&self) -> Result<(), RclrsError> {
+        let mut request_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut request: composition_interfaces__srv__LoadNode_Request =
+            unsafe { core::mem::zeroed() };
+        unsafe { composition_interfaces__srv__LoadNode_Request__init(&mut request as *mut _) };
+        let take_result = unsafe {
+            rcl_take_request(
+                &*self.load_node.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut request as *mut _ as *mut _,
+            )
+        };
+        let request_result =
+            match to_rcl_result(take_result) {
+                Ok(()) => load_request_from_rcl(&request),
+                Err(error) => {
+                    unsafe {
+                        composition_interfaces__srv__LoadNode_Request__fini(&mut request as *mut _)
+                    };
+                    return match error {
+                        RclReturnCode::ServiceError(ServiceErrorCode::ServiceTakeFailed) => Ok(()),
+                        error => Err(RclrsError::from_rcl(error)
+                            .context("failed to take a load_node request")),
+                    };
+                }
+            };
+        unsafe { composition_interfaces__srv__LoadNode_Request__fini(&mut request as *mut _) };
+
+        let mut response: composition_interfaces__srv__LoadNode_Response =
+            unsafe { core::mem::zeroed() };
+        unsafe { composition_interfaces__srv__LoadNode_Response__init(&mut response as *mut _) };
+        match request_result.and_then(|request| self.load(request)) {
+            Ok(loaded) => {
+                response.success = true;
+                response.unique_id = loaded.unique_id;
+                assign_string(&mut response.full_node_name, &loaded.full_node_name);
+            }
+            Err(error_message) => {
+                response.success = false;
+                assign_string(&mut response.error_message, &error_message);
+            }
+        }
+        let send_result = unsafe {
+            rcl_send_response(
+                &*self.load_node.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut response as *mut _ as *mut _,
+            )
+        };
+        unsafe { composition_interfaces__srv__LoadNode_Response__fini(&mut response as *mut _) };
+        send_result.ok()
+    }
+
+    /// Serves one pending `~/_container/unload_node` request, if there is one
+    fn handle_unload_node(&self) -> Result<(), RclrsError> {
+        let mut request_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut request: composition_interfaces__srv__UnloadNode_Request =
+            unsafe { core::mem::zeroed() };
+        unsafe { composition_interfaces__srv__UnloadNode_Request__init(&mut request as *mut _) };
+        let take_result = unsafe {
+            rcl_take_request(
+                &*self.unload_node.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut request as *mut _ as *mut _,
+            )
+        };
+        let unique_id = request.unique_id;
+        unsafe { composition_interfaces__srv__UnloadNode_Request__fini(&mut request as *mut _) };
+        match to_rcl_result(take_result) {
+            Ok(()) => (),
+            Err(RclReturnCode::ServiceError(ServiceErrorCode::ServiceTakeFailed)) => return Ok(()),
+            Err(error) => {
+                return Err(
+                    RclrsError::from_rcl(error).context("failed to take an unload_node request")
+                )
+            }
+        };
+
+        let mut response: composition_interfaces__srv__UnloadNode_Response =
+            unsafe { core::mem::zeroed() };
+        unsafe { composition_interfaces__srv__UnloadNode_Response__init(&mut response as *mut _) };
+        response.success = self.unload(unique_id);
+        if !response.success {
+            assign_string(
+                &mut response.error_message,
+                &format!("No node found with unique_id: {}", unique_id),
+            );
+        }
+        let send_result = unsafe {
+            rcl_send_response(
+                &*self.unload_node.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut response as *mut _ as *mut _,
+            )
+        };
+        unsafe { composition_interfaces__srv__UnloadNode_Response__fini(&mut response as *mut _) };
+        send_result.ok()
+    }
+
+    /// Serves one pending `~/_container/list_nodes` request, if there is one
+    fn handle_list_nodes(&self) -> Result<(), RclrsError> {
+        let mut request_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+        let mut request: composition_interfaces__srv__ListNodes_Request =
+            unsafe { core::mem::zeroed() };
+        unsafe { composition_interfaces__srv__ListNodes_Request__init(&mut request as *mut _) };
+        let take_result = unsafe {
+            rcl_take_request(
+                &*self.list_nodes.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut request as *mut _ as *mut _,
+            )
+        };
+        unsafe { composition_interfaces__srv__ListNodes_Request__fini(&mut request as *mut _) };
+        match to_rcl_result(take_result) {
+            Ok(()) => (),
+            Err(RclReturnCode::ServiceError(ServiceErrorCode::ServiceTakeFailed)) => return Ok(()),
+            Err(error) => {
+                return Err(
+                    RclrsError::from_rcl(error).context("failed to take a list_nodes request")
+                )
+            }
+        };
+
+        let components = self.components.lock().clone();
+        let mut response: composition_interfaces__srv__ListNodes_Response =
+            unsafe { core::mem::zeroed() };
+        unsafe {
+            composition_interfaces__srv__ListNodes_Response__init(&mut response as *mut _);
+            if !rosidl_runtime_c__String__Sequence__init(
+                &mut response.full_node_names as *mut _,
+                components.len(),
+            ) || !rosidl_runtime_c__uint64__Sequence__init(
+                &mut response.unique_ids as *mut _,
+                components.len(),
+            ) {
+                composition_interfaces__srv__ListNodes_Response__fini(&mut response as *mut _);
+                return Err(RclrsError::from(RclReturnCode::BadAlloc)
+                    .context("failed to allocate the list_nodes response"));
+            }
+            for (i, loaded) in components.iter().enumerate() {
+                assign_string(
+                    &mut *response.full_node_names.data.add(i),
+                    &loaded.full_node_name,
+                );
+                *response.unique_ids.data.add(i) = loaded.unique_id;
+            }
+        }
+        let send_result = unsafe {
+            rcl_send_response(
+                &*self.list_nodes.lock() as *const _,
+                &mut request_header as *mut _,
+                &mut response as *mut _ as *mut _,
+            )
+        };
+        unsafe { composition_interfaces__srv__ListNodes_Response__fini(&mut response as *mut _) };
+        send_result.ok()
+    }
+}
+
+impl Waitable for ComponentManagerServices {
+    fn count(&self) -> WaitableCount {
+        WaitableCount {
+            services: 3,
+            ..WaitableCount::default()
+        }
+    }
+
+    fn add_to_wait_set(&self, wait_set: &mut WaitSet) -> Result<(), RclrsError> {
+        wait_set.add_service(&self.load_node.lock())?;
+        wait_set.add_service(&self.unload_node.lock())?;
+        wait_set.add_service(&self.list_nodes.lock())?;
+        Ok(())
+    }
+
+    fn execute(&self, _wait_set: &WaitSet) -> Result<(), RclrsError> {
+        self.handle_load_node()?;
+        self.handle_unload_node()?;
+        self.handle_list_nodes()
+    }
+}
+
+impl Drop for ComponentManagerServices {
+    fn drop(&mut self) {
+        let node_handle = &mut *self.node_handle.lock();
+        unsafe {
+            for service in [
+                self.load_node.get_mut(),
+                self.unload_node.get_mut(),
+                self.list_nodes.get_mut(),
+            ] {
+                rcl_service_fini(service as *mut _, node_handle as *mut _)
+                    .log_on_error("failed to finalize a component manager service");
+            }
+        }
+    }
+}
+
+unsafe fn init_service(
+    node_handle: &mut rcl_node_t,
+    type_support: *const rosidl_service_type_support_t,
+    service_name: &str,
+) -> Result<rcl_service_t, RclrsError> {
+    let mut service = rcl_get_zero_initialized_service();
+    let service_name_c_string = CString::new(service_name).map_err(|_| {
+        RclrsError::from(RclErrorCode::ServiceNameInvalid).context(format!(
+            "service name {:?} contains a NUL byte",
+            service_name
+        ))
+    })?;
+    let service_options = rcl_service_get_default_options();
+    rcl_service_init(
+        &mut service as *mut _,
+        node_handle as *mut _,
+        type_support,
+        service_name_c_string.as_ptr(),
+        &service_options as *const _,
+    )
+    .ok()
+    .with_context(|| format!("failed to create service '{}'", service_name))?;
+    Ok(service)
+}
+
+/// Looks up the library of a component in the ament index of every `AMENT_PREFIX_PATH`
+/// entry, see [`COMPONENT_RESOURCE_TYPE`]
+fn find_library(package_name: &str, plugin_name: &str) -> Result<PathBuf, String> {
+    let ament_prefix_path = std::env::var("AMENT_PREFIX_PATH").unwrap_or_default();
+    let mut found_package = false;
+    for prefix in ament_prefix_path
+        .split(':')
+        .filter(|prefix| !prefix.is_empty())
+    {
+        let resource_path = PathBuf::from(prefix)
+            .join("share/ament_index/resource_index")
+            .join(COMPONENT_RESOURCE_TYPE)
+            .join(package_name);
+        let resource = match std::fs::read_to_string(&resource_path) {
+            Ok(resource) => resource,
+            Err(_) => continue,
+        };
+        found_package = true;
+        for line in resource.lines() {
+            let mut parts = line.splitn(2, ';');
+            if let (Some(name), Some(library_path)) = (parts.next(), parts.next()) {
+                if name.trim() == plugin_name {
+                    return Ok(PathBuf::from(prefix).join(library_path.trim()));
+                }
+            }
+        }
+    }
+
+    if found_package {
+        Err(format!("No class found with name '{}'", plugin_name))
+    } else {
+        Err(String::from(
+            "Could not find requested resource in ament index",
+        ))
+    }
+}
+
+/// The [`ComponentRegistrar`](super::ComponentRegistrar) of the container, `user_data` is
+/// the `Vec<LibraryComponent>` to add the factory to
+unsafe extern "C" fn register_factory(user_data: *mut c_void, factory: *const ComponentFactory) {
+    let factories = &mut *(user_data as *mut Vec<LibraryComponent>);
+    let factory = &*factory;
+    factories.push(LibraryComponent {
+        name: string_from_ptr(factory.name),
+        constructor: factory.constructor,
+    });
+}
+
+/// Creates a component through the constructor a library registered
+fn construct(
+    constructor: ComponentConstructor,
+    context: &Context,
+    options: &ComponentOptions,
+) -> Result<Box<dyn Component>, String> {
+    let mut error_message = [0 as c_char; 1024];
+    let component = options.with_raw(|raw_options| unsafe {
+        constructor(
+            context as *const Context as *const c_void,
+            raw_options as *const _,
+            error_message.as_mut_ptr(),
+            error_message.len(),
+        )
+    });
+    if component.is_null() {
+        return Err(string_from_ptr(error_message.as_ptr()));
+    }
+    // The constructor returns a `Box<Box<dyn Component>>`
+    Ok(*unsafe { Box::from_raw(component as *mut Box<dyn Component>) })
+}
+
+fn dlerror_string() -> String {
+    string_from_ptr(unsafe { libc::dlerror() })
+}
+
+fn assign_string(string: &mut rosidl_runtime_c__String, value: &str) {
+    // Strings with a NUL byte can't be assigned, so they are cut off there
+    let value = value.split('\0').next().unwrap_or_default();
+    let value_c_string = CString::new(value).unwrap_or_default();
+    unsafe { rosidl_runtime_c__String__assign(string as *mut _, value_c_string.as_ptr()) };
+}
+
+/// Borrows the elements of a `rosidl` sequence
+unsafe fn sequence_slice<'a, T>(data: *const T, size: usize) -> &'a [T] {
+    if data.is_null() {
+        &[]
+    } else {
+        core::slice::from_raw_parts(data, size)
+    }
+}
+
+fn load_request_from_rcl(
+    request: &composition_interfaces__srv__LoadNode_Request,
+) -> Result<LoadComponentRequest, String> {
+    let string = |string: &rosidl_runtime_c__String| string_from_ptr(string.data);
+    let non_empty = |string: String| Some(string).filter(|string| !string.is_empty());
+
+    let mut options = ComponentOptions {
+        node_name: non_empty(string(&request.node_name)),
+        node_namespace: non_empty(string(&request.node_namespace)),
+        ..ComponentOptions::default()
+    };
+    unsafe {
+        options.remap_rules = sequence_slice(request.remap_rules.data, request.remap_rules.size)
+            .iter()
+            .map(string)
+            .collect();
+        for parameter in sequence_slice(request.parameters.data, request.parameters.size) {
+            if let Some(value) = parameter_value_to_yaml(&parameter.value) {
+                options
+                    .parameter_overrides
+                    .push((string(&parameter.name), value));
+            }
+        }
+        for argument in sequence_slice(request.extra_arguments.data, request.extra_arguments.size) {
+            if string(&argument.name) == "use_intra_process_comms" {
+                if argument.value.type_ != PARAMETER_BOOL {
+                    return Err(String::from(
+                        "Extra component argument 'use_intra_process_comms' must be a boolean",
+                    ));
+                }
+                options.use_intra_process_comms = argument.value.bool_value;
+            }
+        }
+    }
+
+    Ok(LoadComponentRequest {
+        package_name: string(&request.package_name),
+        plugin_name: string(&request.plugin_name),
+        options,
+    })
+}
+
+/// Renders a parameter value as YAML, for a `-p name:=value` argument
+///
+/// Returns `None` for unset parameters.
+unsafe fn parameter_value_to_yaml(value: &rcl_interfaces__msg__ParameterValue) -> Option<String> {
+    fn list<T>(values: &[T], to_yaml: impl Fn(&T) -> String) -> String {
+        let values: Vec<String> = values.iter().map(to_yaml).collect();
+        format!("[{}]", values.join(", "))
+    }
+    fn quote(value: &rosidl_runtime_c__String) -> String {
+        // Single-quoted, so that e.g. "true" stays a string
+        format!("'{}'", string_from_ptr(value.data).replace('\'', "''"))
+    }
+
+    let yaml = match value.type_ {
+        PARAMETER_NOT_SET => return None,
+        PARAMETER_BOOL => value.bool_value.to_string(),
+        PARAMETER_INTEGER => value.integer_value.to_string(),
+        PARAMETER_DOUBLE => format!("{:?}", value.double_value),
+        PARAMETER_STRING => quote(&value.string_value),
+        PARAMETER_BYTE_ARRAY => list(
+            sequence_slice(value.byte_array_value.data, value.byte_array_value.size),
+            u8::to_string,
+        ),
+        PARAMETER_BOOL_ARRAY => list(
+            sequence_slice(value.bool_array_value.data, value.bool_array_value.size),
+            bool::to_string,
+        ),
+        PARAMETER_INTEGER_ARRAY => list(
+            sequence_slice(
+                value.integer_array_value.data,
+                value.integer_array_value.size,
+            ),
+            i64::to_string,
+        ),
+        PARAMETER_DOUBLE_ARRAY => list(
+            sequence_slice(value.double_array_value.data, value.double_array_value.size),
+            |value| format!("{:?}", value),
+        ),
+        PARAMETER_STRING_ARRAY => list(
+            sequence_slice(value.string_array_value.data, value.string_array_value.size),
+            quote,
+        ),
+        _ => return None,
+    };
+    Some(yaml)
+}
@@ -0,0 +1,412 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::{ptr, slice};
+use cstr_core::{c_char, CStr, CString};
+
+use crate::error::RclrsError;
+use crate::{Context, Node, NodeBuilder};
+
+#[cfg(all(feature = "std", unix))]
+mod manager;
+#[cfg(all(feature = "std", unix))]
+pub use self::manager::*;
+
+/// A node, or an object owning one, that can be loaded into a component container at runtime
+///
+/// Components are compiled into a `cdylib` crate that registers them with
+/// [`register_components!`](crate::register_components), and are installed to the ament index
+/// under the `rclrs_components` resource type. The resource file is named after the package
+/// and has one `<plugin name>;<library path>` line per component, e.g.
+/// `my_pkg::Talker;lib/libmy_pkg.so`, with the library path relative to the install prefix.
+///
+/// The library exports an `extern "C"` function that only passes `#[repr(C)]` data, see
+/// [`ComponentLibrary`]. The components themselves are still Rust objects, so the container
+/// only loads libraries built with the same compiler and version of `rclrs`, see
+/// [`COMPONENT_BUILD_ID`]. The `rclrs_register_components()` CMake function writes the
+/// resource file.
+///
+/// ```ignore
+/// pub struct Talker {
+///     node: rclrs::Node,
+/// }
+///
+/// impl rclrs::Component for Talker {
+///     fn new(context: &rclrs::Context, options: rclrs::ComponentOptions) -> Result<Self, rclrs::RclrsError> {
+///         let node = options.node_builder(context, "talker").build()?;
+///         Ok(Self { node })
+///     }
+///
+///     fn node(&self) -> &rclrs::Node {
+///         &self.node
+///     }
+/// }
+///
+/// rclrs::register_components!(Talker);
+/// ```
+pub trait Component: Send + Sync {
+    /// Creates the component, whose node should be built with
+    /// [`ComponentOptions::node_builder`]
+    fn new(context: &Context, options: ComponentOptions) -> Result<Self, RclrsError>
+    where
+        Self: Sized;
+
+    /// The node that the container spins
+    fn node(&self) -> &Node;
+}
+
+/// How a component's node should be set up, as requested when loading it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComponentOptions {
+    /// Overrides the component's default node name
+    pub node_name: Option<String>,
+    /// Overrides the root namespace
+    pub node_namespace: Option<String>,
+    /// Remap rules for the node, e.g. `chatter:=talk`
+    pub remap_rules: Vec<String>,
+    /// Parameter overrides as name and YAML value, see [`NodeOptions::parameter_overrides`](crate::NodeOptions::parameter_overrides)
+    pub parameter_overrides: Vec<(String, String)>,
+    pub use_intra_process_comms: bool,
+}
+
+impl ComponentOptions {
+    /// Returns a builder for the component's node, called `default_name` unless overridden
+    pub fn node_builder<'ctxt>(
+        &self,
+        context: &'ctxt Context,
+        default_name: &str,
+    ) -> NodeBuilder<'ctxt> {
+        let node_name = self.node_name.as_deref().unwrap_or(default_name);
+        let mut builder =
+            Node::builder(context, node_name).use_intra_process_comms(self.use_intra_process_comms);
+        if let Some(node_namespace) = &self.node_namespace {
+            builder = builder.namespace(node_namespace);
+        }
+        if !self.remap_rules.is_empty() {
+            let mut arguments = Vec::with_capacity(1 + 2 * self.remap_rules.len());
+            arguments.push(String::from("--ros-args"));
+            for remap_rule in &self.remap_rules {
+                arguments.push(String::from("-r"));
+                arguments.push(remap_rule.clone());
+            }
+            builder = builder.arguments(arguments);
+        }
+        for (name, value) in &self.parameter_overrides {
+            builder = builder.parameter_override(name, value);
+        }
+        builder
+    }
+}
+
+/// Version of the interface between component libraries and containers, i.e. of
+/// [`ComponentLibrary`] and the types it refers to
+pub const COMPONENT_ABI_VERSION: u32 = 1;
+
+/// The `rclrs` version and compiler a library or container was built with
+///
+/// Components share Rust types with the container, e.g. [`Context`] and [`Node`], so a
+/// library is only loaded if it was built the same way as the container.
+pub const COMPONENT_BUILD_ID: &str = concat!(
+    "rclrs ",
+    env!("CARGO_PKG_VERSION"),
+    ", ",
+    env!("RCLRS_RUSTC_VERSION"),
+    "\0"
+);
+
+/// Name of the function that [`register_components!`](crate::register_components) exports
+pub const COMPONENT_LIBRARY_SYMBOL: &str = "rclrs_component_library";
+
+/// Signature of the function that [`register_components!`](crate::register_components)
+/// exports, which describes the library to the container
+pub type ComponentLibraryFunction = unsafe extern "C" fn() -> ComponentLibrary;
+
+/// What a component library exports, as returned by its [`COMPONENT_LIBRARY_SYMBOL`]
+/// function
+///
+/// The container checks `abi_version` and `build_id` before calling anything else.
+#[repr(C)]
+pub struct ComponentLibrary {
+    /// The [`COMPONENT_ABI_VERSION`] of the library
+    pub abi_version: u32,
+    /// The NUL-terminated [`COMPONENT_BUILD_ID`] of the library
+    pub build_id: *const c_char,
+    /// Passes each component of the library to `register`, along with `user_data`
+    pub register_components:
+        unsafe extern "C" fn(register: ComponentRegistrar, user_data: *mut c_void),
+}
+
+/// Callback that receives the components of a library, see [`ComponentLibrary`]
+///
+/// The factory, including its name, is only valid during the call.
+pub type ComponentRegistrar =
+    unsafe extern "C" fn(user_data: *mut c_void, factory: *const ComponentFactory);
+
+/// A component type exported by a library, as registered by
+/// [`register_components!`](crate::register_components)
+#[repr(C)]
+pub struct ComponentFactory {
+    /// The NUL-terminated plugin name under which the component is loaded, i.e. its full
+    /// type name, such as `my_pkg::Talker`
+    pub name: *const c_char,
+    pub constructor: ComponentConstructor,
+}
+
+/// Creates a component
+///
+/// `context` points to the container's [`Context`]. On success, the component is returned
+/// as a `Box<Box<dyn Component>>`. Otherwise, null is returned and the error is written to
+/// `error_message`, NUL-terminated and cut off at `error_message_capacity` bytes.
+pub type ComponentConstructor = unsafe extern "C" fn(
+    context: *const c_void,
+    options: *const RawComponentOptions,
+    error_message: *mut c_char,
+    error_message_capacity: usize,
+) -> *mut c_void;
+
+/// [`ComponentOptions`] as passed to a [`ComponentConstructor`], with NUL-terminated
+/// strings
+#[repr(C)]
+pub struct RawComponentOptions {
+    /// Null unless overridden
+    pub node_name: *const c_char,
+    /// Null unless overridden
+    pub node_namespace: *const c_char,
+    pub remap_rules: *const *const c_char,
+    pub remap_rule_count: usize,
+    pub parameter_names: *const *const c_char,
+    pub parameter_values: *const *const c_char,
+    pub parameter_count: usize,
+    pub use_intra_process_comms: bool,
+}
+
+impl ComponentOptions {
+    /// Passes the options to `f` as [`RawComponentOptions`], which are only valid during
+    /// the call
+    ///
+    /// Strings with a NUL byte are cut off there.
+    pub(crate) fn with_raw<R>(&self, f: impl FnOnce(&RawComponentOptions) -> R) -> R {
+        fn c_string(string: &str) -> CString {
+            let string = string.split('\0').next().unwrap_or_default();
+            CString::new(string).unwrap_or_default()
+        }
+        fn pointers(strings: &[CString]) -> Vec<*const c_char> {
+            strings.iter().map(|string| string.as_ptr()).collect()
+        }
+
+        let node_name = self.node_name.as_deref().map(c_string);
+        let node_namespace = self.node_namespace.as_deref().map(c_string);
+        let remap_rules: Vec<CString> =
+            self.remap_rules.iter().map(|rule| c_string(rule)).collect();
+        let parameter_names: Vec<CString> = self
+            .parameter_overrides
+            .iter()
+            .map(|(name, _)| c_string(name))
+            .collect();
+        let parameter_values: Vec<CString> = self
+            .parameter_overrides
+            .iter()
+            .map(|(_, value)| c_string(value))
+            .collect();
+        let remap_rule_pointers = pointers(&remap_rules);
+        let parameter_name_pointers = pointers(&parameter_names);
+        let parameter_value_pointers = pointers(&parameter_values);
+
+        f(&RawComponentOptions {
+            node_name: node_name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+            node_namespace: node_namespace
+                .as_ref()
+                .map_or(ptr::null(), |namespace| namespace.as_ptr()),
+            remap_rules: remap_rule_pointers.as_ptr(),
+            remap_rule_count: remap_rule_pointers.len(),
+            parameter_names: parameter_name_pointers.as_ptr(),
+            parameter_values: parameter_value_pointers.as_ptr(),
+            parameter_count: parameter_name_pointers.len(),
+            use_intra_process_comms: self.use_intra_process_comms,
+        })
+    }
+
+    /// Copies the options out of [`RawComponentOptions`]
+    ///
+    /// # Safety
+    ///
+    /// All pointers must be valid as described in [`RawComponentOptions`].
+    pub(crate) unsafe fn from_raw(raw: &RawComponentOptions) -> Self {
+        let string = |string: *const c_char| CStr::from_ptr(string).to_string_lossy().into_owned();
+        let strings = |strings: *const *const c_char, count: usize| -> Vec<String> {
+            if count == 0 {
+                return Vec::new();
+            }
+            slice::from_raw_parts(strings, count)
+                .iter()
+                .map(|s| string(*s))
+                .collect()
+        };
+        let optional_string = |s: *const c_char| Some(s).filter(|s| !s.is_null()).map(string);
+
+        Self {
+            node_name: optional_string(raw.node_name),
+            node_namespace: optional_string(raw.node_namespace),
+            remap_rules: strings(raw.remap_rules, raw.remap_rule_count),
+            parameter_overrides: strings(raw.parameter_names, raw.parameter_count)
+                .into_iter()
+                .zip(strings(raw.parameter_values, raw.parameter_count))
+                .collect(),
+            use_intra_process_comms: raw.use_intra_process_comms,
+        }
+    }
+}
+
+/// Describes the library that invokes [`register_components!`](crate::register_components)
+#[doc(hidden)]
+pub fn component_library(
+    register_components: unsafe extern "C" fn(ComponentRegistrar, *mut c_void),
+) -> ComponentLibrary {
+    ComponentLibrary {
+        abi_version: COMPONENT_ABI_VERSION,
+        build_id: COMPONENT_BUILD_ID.as_ptr() as *const c_char,
+        register_components,
+    }
+}
+
+/// Passes the component type `T` to a [`ComponentRegistrar`]
+///
+/// # Safety
+///
+/// `register` and `user_data` must be the ones the container passed to the library.
+#[doc(hidden)]
+pub unsafe fn register_component<T>(register: ComponentRegistrar, user_data: *mut c_void)
+where
+    T: Component + 'static,
+{
+    let name = CString::new(core::any::type_name::<T>()).unwrap_or_default();
+    let factory = ComponentFactory {
+        name: name.as_ptr(),
+        constructor: construct_component::<T>,
+    };
+    register(user_data, &factory as *const _);
+}
+
+/// The [`ComponentConstructor`] of `T`
+///
+/// A panic in [`Component::new`] aborts the process, since it can't unwind into the
+/// container.
+unsafe extern "C" fn construct_component<T>(
+    context: *const c_void,
+    options: *const RawComponentOptions,
+    error_message: *mut c_char,
+    error_message_capacity: usize,
+) -> *mut c_void
+where
+    T: Component + 'static,
+{
+    let context = &*(context as *const Context);
+    let options = ComponentOptions::from_raw(&*options);
+    match T::new(context, options) {
+        Ok(component) => {
+            let component: Box<dyn Component> = Box::new(component);
+            Box::into_raw(Box::new(component)) as *mut c_void
+        }
+        Err(error) => {
+            if error_message_capacity > 0 {
+                let message = format!("{}", error);
+                let len = message.len().min(error_message_capacity - 1);
+                ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, error_message, len);
+                *error_message.add(len) = 0;
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Exports the given [`Component`] types from a `cdylib`, so that a component container
+/// can load them
+///
+/// The library exports a single `extern "C"` function, see [`ComponentLibrary`], and only
+/// `#[repr(C)]` data crosses the library boundary. Each library may only invoke this macro
+/// once, listing all of its components.
+#[macro_export]
+macro_rules! register_components {
+    ($($component:ty),+ $(,)?) => {
+        #[no_mangle]
+        pub extern "C" fn rclrs_component_library() -> $crate::ComponentLibrary {
+            unsafe extern "C" fn register_components(
+                register: $crate::ComponentRegistrar,
+                user_data: *mut ::core::ffi::c_void,
+            ) {
+                $($crate::register_component::<$component>(register, user_data);)+
+            }
+            $crate::component_library(register_components)
+        }
+
+        const _: $crate::ComponentLibraryFunction = rclrs_component_library;
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    struct TestComponent {
+        node: Node,
+    }
+
+    impl Component for TestComponent {
+        fn new(context: &Context, options: ComponentOptions) -> Result<Self, RclrsError> {
+            let node = options.node_builder(context, "test_component").build()?;
+            Ok(Self { node })
+        }
+
+        fn node(&self) -> &Node {
+            &self.node
+        }
+    }
+
+    crate::register_components!(TestComponent);
+
+    unsafe extern "C" fn collect_name(user_data: *mut c_void, factory: *const ComponentFactory) {
+        let names = &mut *(user_data as *mut Vec<String>);
+        names.push(
+            CStr::from_ptr((*factory).name)
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+
+    #[test]
+    fn libraries_describe_their_components() {
+        let library = rclrs_component_library();
+        assert_eq!(library.abi_version, COMPONENT_ABI_VERSION);
+        let build_id = unsafe { CStr::from_ptr(library.build_id) };
+        assert_eq!(
+            build_id.to_bytes(),
+            COMPONENT_BUILD_ID.trim_end_matches('\0').as_bytes()
+        );
+
+        let mut names: Vec<String> = Vec::new();
+        unsafe {
+            (library.register_components)(collect_name, &mut names as *mut Vec<String> as *mut _)
+        };
+        assert_eq!(names, [core::any::type_name::<TestComponent>()]);
+    }
+
+    #[test]
+    fn options_round_trip_through_their_raw_form() {
+        let options = ComponentOptions {
+            node_name: Some(String::from("talker")),
+            node_namespace: None,
+            remap_rules: vec![String::from("chatter:=talk")],
+            parameter_overrides: vec![(String::from("rate"), String::from("2.0"))],
+            use_intra_process_comms: true,
+        };
+        let round_tripped = options.with_raw(|raw| unsafe { ComponentOptions::from_raw(raw) });
+        assert_eq!(round_tripped, options);
+        assert_eq!(
+            ComponentOptions::default().with_raw(|raw| unsafe { ComponentOptions::from_raw(raw) }),
+            ComponentOptions::default()
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use crate::error::ToResult;
+use crate::arguments::{get_unparsed_arguments, take_allocated_string};
+use crate::error::{ErrorContext, RclrsError, ToResult};
+use crate::intra_process::IntraProcessManager;
 use crate::rcl_bindings::*;
 use crate::Node;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use cstr_core::{c_char, CString};
-use rclrs_common::error::RclReturnCode;
+use rclrs_common::error::{RclErrorCode, RclReturnCode};
 
 #[cfg(not(feature = "std"))]
 use spin::{Mutex, MutexGuard};
@@ -12,72 +17,404 @@ use spin::{Mutex, MutexGuard};
 #[cfg(feature = "std")]
 use parking_lot::{Mutex, MutexGuard};
 
-pub struct ContextHandle(Mutex<rcl_context_t>);
+pub struct ContextHandle {
+    handle: Mutex<rcl_context_t>,
+    /// Triggered on shutdown, to wake up every wait set waiting on this context
+    interrupt_guard_condition: Mutex<rcl_guard_condition_t>,
+    on_shutdown_callbacks: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    pub(crate) intra_process_manager: IntraProcessManager,
+    /// Foxy has no domain ID in the init options, so it is set on every node instead
+    #[cfg(ros_distro = "foxy")]
+    pub(crate) domain_id: Option<usize>,
+}
+
+// SAFETY: The rcl context and guard condition are only accessed through their mutexes, and
+// the on-shutdown callbacks are `Send`
+unsafe impl Send for ContextHandle {}
+unsafe impl Sync for ContextHandle {}
 
 impl ContextHandle {
     pub fn get_mut(&mut self) -> &mut rcl_context_t {
-        self.0.get_mut()
+        self.handle.get_mut()
     }
 
     pub fn lock(&self) -> MutexGuard<rcl_context_t> {
-        self.0.lock()
+        self.handle.lock()
     }
 
     pub fn try_lock(&self) -> Option<MutexGuard<rcl_context_t>> {
-        self.0.try_lock()
+        self.handle.try_lock()
+    }
+
+    pub(crate) fn interrupt_guard_condition(&self) -> MutexGuard<rcl_guard_condition_t> {
+        self.interrupt_guard_condition.lock()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        unsafe { rcl_context_is_valid(&mut *self.lock() as *mut _) }
+    }
+
+    /// Runs the on-shutdown callbacks, shuts the context down and wakes up its wait sets
+    pub(crate) fn shutdown(&self) -> Result<(), RclrsError> {
+        if !self.is_valid() {
+            return Err(RclErrorCode::AlreadyShutdown.into());
+        }
+
+        let callbacks = core::mem::take(&mut *self.on_shutdown_callbacks.lock());
+        for callback in callbacks {
+            callback();
+        }
+
+        unsafe {
+            rcl_shutdown(&mut *self.lock() as *mut _).ok()?;
+            rcl_trigger_guard_condition(&mut *self.interrupt_guard_condition() as *mut _).ok()
+        }
     }
 }
 
 impl Drop for ContextHandle {
     fn drop(&mut self) {
+        #[cfg(all(feature = "std", unix))]
+        crate::signal_handler::unregister(self as *const _);
         unsafe {
-            rcl_shutdown(&mut *self.get_mut() as *mut _);
+            rcl_guard_condition_fini(self.interrupt_guard_condition.get_mut() as *mut _)
+                .log_on_error("failed to finalize the interrupt guard condition");
+            let handle = self.get_mut();
+            if rcl_context_is_valid(handle as *mut _) {
+                rcl_shutdown(handle as *mut _).log_on_error("failed to shut down the context");
+            }
+            rcl_context_fini(handle as *mut _).log_on_error("failed to finalize the context");
         }
     }
 }
 
-pub struct Context {
-    pub handle: Arc<ContextHandle>,
+/// The `rcutils` allocator used by `rcl` for the init options of a [`Context`]
+pub type Allocator = rcutils_allocator_t;
+
+/// Builder for a [`Context`] with non-default init options
+///
+/// ```ignore
+/// let context = rclrs::ContextBuilder::new(args)
+///     .domain_id(42)
+///     .localhost_only(true)
+///     .build()?;
+/// ```
+pub struct ContextBuilder {
+    args: Vec<CString>,
+    domain_id: Option<usize>,
+    localhost_only: Option<bool>,
+    allocator: Option<Allocator>,
+    install_signal_handlers: bool,
 }
 
-impl Context {
-    fn init(&self, context_env_args: Vec<CString>) -> Result<(), RclReturnCode> {
-        let c_args: Vec<*const c_char> = context_env_args.iter().map(|arg| arg.as_ptr()).collect();
-        let handle = &mut *self.handle.lock();
+impl ContextBuilder {
+    pub fn new(args: Vec<CString>) -> Self {
+        Self {
+            args,
+            domain_id: None,
+            localhost_only: None,
+            allocator: None,
+            install_signal_handlers: true,
+        }
+    }
+
+    /// Sets the DDS domain ID, overriding the `ROS_DOMAIN_ID` environment variable
+    ///
+    /// On Foxy, the domain ID is applied to the nodes created in the context instead.
+    pub fn domain_id(mut self, domain_id: usize) -> Self {
+        self.domain_id = Some(domain_id);
+        self
+    }
+
+    /// Restricts communication to localhost, overriding the `ROS_LOCALHOST_ONLY`
+    /// environment variable
+    pub fn localhost_only(mut self, localhost_only: bool) -> Self {
+        self.localhost_only = Some(localhost_only);
+        self
+    }
+
+    /// Sets the allocator of the init options, instead of the default `rcutils` allocator
+    pub fn allocator(mut self, allocator: Allocator) -> Self {
+        self.allocator = Some(allocator);
+        self
+    }
+
+    /// Whether SIGINT and SIGTERM shut the context down, `true` by default
+    ///
+    /// The handlers are shared by all contexts that ask for them: they are installed when the
+    /// first of these contexts is built and a signal shuts all of them down. Once a signal was
+    /// handled or the last of these contexts is dropped, the handlers that were in place
+    /// before are restored, so a second Ctrl-C usually terminates the process right away.
+    /// Only available on Unix with the `std` feature.
+    pub fn install_signal_handlers(mut self, install_signal_handlers: bool) -> Self {
+        self.install_signal_handlers = install_signal_handlers;
+        self
+    }
+
+    /// Initializes the context, parsing the ROS arguments among `args`
+    ///
+    /// Invalid ROS arguments, e.g. a malformed remap rule, are reported as
+    /// [`RclReturnCode::ParsingError`].
+    pub fn build(self) -> Result<Context, RclrsError> {
+        let c_args: Vec<*const c_char> = self.args.iter().map(|arg| arg.as_ptr()).collect();
+        let mut context_handle = unsafe { rcl_get_zero_initialized_context() };
 
         unsafe {
-            let allocator = rcutils_get_default_allocator();
+            let allocator = match self.allocator {
+                Some(allocator) => allocator,
+                None => rcutils_get_default_allocator(),
+            };
             let mut init_options = rcl_get_zero_initialized_init_options();
-            rcl_init_options_init(&mut init_options as *mut _, allocator);
-            rcl_init(
-                c_args.len() as i32,
-                c_args.as_ptr(),
-                &init_options as *const _,
-                handle as *mut _,
+            rcl_init_options_init(&mut init_options as *mut _, allocator).ok()?;
+
+            let init_result =
+                Self::set_init_options(&mut init_options, self.domain_id, self.localhost_only)
+                    .and_then(|()| {
+                        rcl_init(
+                            c_args.len() as i32,
+                            c_args.as_ptr(),
+                            &init_options as *const _,
+                            &mut context_handle as *mut _,
+                        )
+                        .ok()
+                    })
+                    .with_context(|| String::from("failed to initialize the context"));
+            rcl_init_options_fini(&mut init_options as *mut _).ok()?;
+            init_result?;
+        }
+
+        // From here on, dropping the handle finalizes the context
+        let mut handle = ContextHandle {
+            handle: Mutex::new(context_handle),
+            interrupt_guard_condition: Mutex::new(unsafe {
+                rcl_get_zero_initialized_guard_condition()
+            }),
+            on_shutdown_callbacks: Mutex::new(Vec::new()),
+            intra_process_manager: IntraProcessManager::default(),
+            #[cfg(ros_distro = "foxy")]
+            domain_id: self.domain_id,
+        };
+        unsafe {
+            rcl_guard_condition_init(
+                handle.interrupt_guard_condition.get_mut() as *mut _,
+                handle.handle.get_mut() as *mut _,
+                rcl_guard_condition_get_default_options(),
             )
             .ok()?;
-            rcl_init_options_fini(&mut init_options as *mut _).ok()?;
+        }
+        let handle = Arc::new(handle);
+
+        #[cfg(all(feature = "std", unix))]
+        if self.install_signal_handlers {
+            crate::signal_handler::register(&handle)?;
+        }
+
+        let arguments = self
+            .args
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        Ok(Context { handle, arguments })
+    }
+
+    unsafe fn set_init_options(
+        init_options: &mut rcl_init_options_t,
+        domain_id: Option<usize>,
+        localhost_only: Option<bool>,
+    ) -> Result<(), RclrsError> {
+        #[cfg(not(ros_distro = "foxy"))]
+        if let Some(domain_id) = domain_id {
+            rcl_init_options_set_domain_id(init_options as *mut _, domain_id).ok()?;
+        }
+        // On Foxy, the domain ID is set on the nodes instead, see `Node::new_with_options`
+        #[cfg(ros_distro = "foxy")]
+        let _ = domain_id;
+
+        if let Some(localhost_only) = localhost_only {
+            let rmw_init_options = rcl_init_options_get_rmw_init_options(init_options as *mut _);
+            if rmw_init_options.is_null() {
+                return Err(RclrsError::from_rcl(RclReturnCode::Error));
+            }
+            (*rmw_init_options).localhost_only = if localhost_only {
+                rmw_localhost_only_t::RMW_LOCALHOST_ONLY_ENABLED
+            } else {
+                rmw_localhost_only_t::RMW_LOCALHOST_ONLY_DISABLED
+            };
         }
 
         Ok(())
     }
+}
 
-    pub fn default(args: Vec<CString>) -> Self {
-        let context = Self {
-            handle: Arc::new(ContextHandle(Mutex::new(unsafe {
-                rcl_get_zero_initialized_context()
-            }))),
-        };
-        context.init(args).unwrap(); // If we can't initialize the context, ROS 2 cannot function
-        context
+/// Clones share the same underlying `rcl` context
+#[derive(Clone)]
+pub struct Context {
+    pub handle: Arc<ContextHandle>,
+    arguments: Vec<String>,
+}
+
+impl Context {
+    /// Initializes a context with the default init options
+    ///
+    /// See [`ContextBuilder`] to customize them.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(args: Vec<CString>) -> Result<Self, RclrsError> {
+        ContextBuilder::new(args).build()
+    }
+
+    /// All arguments the context was initialized with, including the program name
+    pub fn arguments(&self) -> &[String] {
+        &self.arguments
     }
 
-    pub fn ok(&self) -> Result<bool, RclReturnCode> {
+    /// Arguments that are not ROS arguments, i.e. everything outside of `--ros-args ... [--]`
+    ///
+    /// The program name is included, just like in [`arguments`](Self::arguments).
+    pub fn unparsed_arguments(&self) -> Result<Vec<String>, RclrsError> {
+        let handle = &*self.handle.lock();
+        get_unparsed_arguments(&handle.global_arguments, &self.arguments)
+    }
+
+    /// Applies the node name remap rules of the global arguments, e.g. `__node:=new_name`
+    ///
+    /// Returns `None` if no rule applies to `node_name`.
+    pub fn remap_node_name(&self, node_name: &str) -> Result<Option<String>, RclrsError> {
+        let node_name_c_string = CString::new(node_name).map_err(|_| {
+            RclrsError::from(RclReturnCode::InvalidArgument)
+                .context(format!("node name {:?} contains a NUL byte", node_name))
+        })?;
+        let handle = &*self.handle.lock();
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        let mut output_name: *mut c_char = core::ptr::null_mut();
+        unsafe {
+            rcl_remap_node_name(
+                core::ptr::null(),
+                &handle.global_arguments as *const _,
+                node_name_c_string.as_ptr(),
+                rcutils_get_default_allocator(),
+                &mut output_name as *mut _,
+            )
+            .ok()?;
+            Ok(take_allocated_string(output_name, &allocator))
+        }
+    }
+
+    /// Applies the namespace remap rules of the global arguments, e.g. `__ns:=/new_ns`
+    ///
+    /// Returns `None` if no rule applies to the node called `node_name`.
+    pub fn remap_node_namespace(&self, node_name: &str) -> Result<Option<String>, RclrsError> {
+        let node_name_c_string = CString::new(node_name).map_err(|_| {
+            RclrsError::from(RclReturnCode::InvalidArgument)
+                .context(format!("node name {:?} contains a NUL byte", node_name))
+        })?;
+        let handle = &*self.handle.lock();
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        let mut output_namespace: *mut c_char = core::ptr::null_mut();
+        unsafe {
+            rcl_remap_node_namespace(
+                core::ptr::null(),
+                &handle.global_arguments as *const _,
+                node_name_c_string.as_ptr(),
+                rcutils_get_default_allocator(),
+                &mut output_namespace as *mut _,
+            )
+            .ok()?;
+            Ok(take_allocated_string(output_namespace, &allocator))
+        }
+    }
+
+    pub fn ok(&self) -> Result<bool, RclrsError> {
         let handle = &mut *self.handle.lock();
         unsafe { Ok(rcl_context_is_valid(handle as *mut _)) }
     }
 
-    pub fn create_node(&self, node_name: &str) -> Result<Node, RclReturnCode> {
+    /// Shuts the context down, invalidating it along with its nodes
+    ///
+    /// The on-shutdown callbacks are run first, then [`spin`](crate::spin) is woken up and
+    /// returns `Ok`. Only this context is affected, other contexts in the same process keep
+    /// running. Shutting down a context twice returns [`RclErrorCode::AlreadyShutdown`].
+    pub fn shutdown(&self) -> Result<(), RclrsError> {
+        self.handle.shutdown()
+    }
+
+    /// Registers a callback that is run when the context is shut down, either explicitly or
+    /// by a signal
+    ///
+    /// The callbacks run before `rcl_shutdown`, so they can still publish, e.g. to stop
+    /// motors. They may run on the signal handling thread.
+    pub fn on_shutdown<F>(&self, callback: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle
+            .on_shutdown_callbacks
+            .lock()
+            .push(Box::new(callback));
+    }
+
+    pub fn create_node(&self, node_name: &str) -> Result<Node, RclrsError> {
         Ok(Node::new(node_name, self)?)
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use rclrs_common::error::NodeErrorCode;
+
+    fn context() -> Context {
+        ContextBuilder::new(Vec::new())
+            .install_signal_handlers(false)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn invalid_names_are_reported_as_errors() {
+        let context = context();
+        let error = context.create_node("invalid node name").err().unwrap();
+        assert_eq!(
+            error.code(),
+            &RclReturnCode::NodeError(NodeErrorCode::NodeInvalidName)
+        );
+
+        let node = context.create_node("invalid_names_test").unwrap();
+        for topic_name in ["invalid topic name", "nul\0byte"] {
+            let error = node.resolve_topic_name(topic_name, true).err().unwrap();
+            assert_eq!(
+                error.code(),
+                &RclReturnCode::RclError(RclErrorCode::TopicNameInvalid)
+            );
+        }
+    }
+
+    #[test]
+    fn use_after_shutdown_is_reported_as_an_error() {
+        let context = context();
+        let node = context.create_node("shutdown_test").unwrap();
+        let graph_events = node.graph_events(|| {}).unwrap();
+
+        context.shutdown().unwrap();
+        assert!(!context.ok().unwrap());
+        assert_eq!(
+            context.shutdown().err().unwrap().code(),
+            &RclReturnCode::RclError(RclErrorCode::AlreadyShutdown)
+        );
+        assert!(context.create_node("too_late").is_err());
+
+        // Finalizing entities of a shut down context logs errors instead of panicking
+        drop(graph_events);
+        drop(context);
+        drop(node);
+    }
+
+    #[test]
+    fn nodes_can_be_created_in_another_domain() {
+        let context = ContextBuilder::new(Vec::new())
+            .domain_id(42)
+            .install_signal_handlers(false)
+            .build()
+            .unwrap();
+        context.create_node("domain_test").unwrap();
+    }
+}
@@ -1,16 +1,289 @@
 use crate::rcl_bindings::*;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display};
+use core_error::Error;
+use cstr_core::{CStr, CString};
 pub use rclrs_common::error::{to_rcl_result, RclReturnCode};
+use rclrs_common::error::{
+    ActionErrorCode, ClientErrorCode, EventErrorCode, LifecycleErrorCode, NodeErrorCode,
+    ParsingErrorCode, RclErrorCode, ServiceErrorCode, SubscriberErrorCode, TimerErrorCode,
+    WaitSetErrorCode,
+};
+
+/// Error returned by the functions of `rclrs`
+///
+/// It keeps the error message that `rcl` set along with the return code, so that it's
+/// possible to tell why e.g. `rcl_publisher_init` failed, and may describe which operation
+/// failed on which entity or topic. The underlying errors are available through
+/// [`Error::source`], down to the [`RclReturnCode`], which is also returned by
+/// [`code`](Self::code):
+///
+/// ```ignore
+/// match node.create_publisher::<Msg>("~/chatter", QOS_PROFILE_DEFAULT) {
+///     Err(error) if error.code() == &RclReturnCode::RclError(RclErrorCode::TopicNameInvalid) => {}
+///     ...
+/// }
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum RclrsError {
+    /// An `rcl` function failed, with the `rcutils` error message it set, if any
+    RclError {
+        code: RclReturnCode,
+        message: Option<String>,
+    },
+    /// `context` describes the operation that failed, e.g.
+    /// `failed to create a publisher on topic '/chatter'`
+    WithContext {
+        context: String,
+        source: Box<RclrsError>,
+    },
+}
+
+impl RclrsError {
+    /// Creates the error for a failed `rcl` call, taking the error message out of the
+    /// `rcutils` error state
+    pub(crate) fn from_rcl(code: RclReturnCode) -> Self {
+        Self::RclError {
+            code,
+            message: take_error_message(),
+        }
+    }
+
+    /// The return code of the underlying `rcl` error
+    pub fn code(&self) -> &RclReturnCode {
+        match self {
+            Self::RclError { code, .. } => code,
+            Self::WithContext { source, .. } => source.code(),
+        }
+    }
+
+    /// The `rcutils` error message of the underlying `rcl` error, if there was one
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::RclError { message, .. } => message.as_deref(),
+            Self::WithContext { source, .. } => source.message(),
+        }
+    }
+
+    /// Wraps the error with a description of the operation that failed
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Self::WithContext {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+/// Like other errors with a source, only the outermost layer is displayed, e.g. the
+/// context; the rest of the chain is available through [`Error::source`]
+impl Display for RclrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RclError {
+                message: Some(message),
+                ..
+            } => write!(f, "{}", message),
+            Self::RclError {
+                code,
+                message: None,
+            } => write!(f, "{}", code),
+            Self::WithContext { context, .. } => write!(f, "{}", context),
+        }
+    }
+}
+
+impl Error for RclrsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::RclError {
+                code,
+                message: Some(_),
+            } => Some(code),
+            Self::RclError { message: None, .. } => None,
+            Self::WithContext { source, .. } => Some(&**source),
+        }
+    }
+}
+
+impl From<RclReturnCode> for RclrsError {
+    fn from(code: RclReturnCode) -> Self {
+        Self::RclError {
+            code,
+            message: None,
+        }
+    }
+}
+
+macro_rules! impl_from_error_code {
+    ($($error_code:ty),+) => {
+        $(
+            impl From<$error_code> for RclrsError {
+                fn from(code: $error_code) -> Self {
+                    RclReturnCode::from(code).into()
+                }
+            }
+        )+
+    };
+}
+
+impl_from_error_code!(
+    RclErrorCode,
+    NodeErrorCode,
+    SubscriberErrorCode,
+    ClientErrorCode,
+    ServiceErrorCode,
+    TimerErrorCode,
+    WaitSetErrorCode,
+    ParsingErrorCode,
+    EventErrorCode,
+    ActionErrorCode,
+    LifecycleErrorCode
+);
+
+/// Adds a description of the failed operation to the error of a result, see
+/// [`RclrsError::context`]
+pub(crate) trait ErrorContext<T> {
+    fn with_context<F>(self, context: F) -> Result<T, RclrsError>
+    where
+        F: FnOnce() -> String;
+}
+
+impl<T, E> ErrorContext<T> for Result<T, E>
+where
+    E: Into<RclrsError>,
+{
+    fn with_context<F>(self, context: F) -> Result<T, RclrsError>
+    where
+        F: FnOnce() -> String,
+    {
+        self.map_err(|error| error.into().context(context()))
+    }
+}
+
+/// Takes the message out of the `rcutils` error state, which is thread-local
+fn take_error_message() -> Option<String> {
+    unsafe {
+        if !rcutils_error_is_set() {
+            return None;
+        }
+        let error_string = rcutils_get_error_string();
+        rcutils_reset_error();
+        let message = CStr::from_ptr(error_string.str_.as_ptr())
+            .to_string_lossy()
+            .into_owned();
+        Some(message)
+    }
+}
+
+const LOGGER_NAME: &str = "rclrs\0";
+
+/// Logs an error through the `rcutils` logging system, under the `rclrs` logger
+///
+/// This is for errors that can't be returned, e.g. in `Drop` impls, where panicking could
+/// abort the process or unwind through the stack of an `rcl` call.
+pub(crate) fn log_error(error: &RclrsError) {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    // The message is passed as a C string, so it can't contain NUL bytes
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    unsafe {
+        rcutils_log(
+            core::ptr::null(),
+            RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_ERROR as i32,
+            LOGGER_NAME.as_ptr() as *const _,
+            "%s\0".as_ptr() as *const _,
+            message.as_ptr(),
+        );
+    }
+}
 
 pub(crate) trait ToResult {
-    fn ok(&self) -> Result<(), RclReturnCode>;
+    fn ok(&self) -> Result<(), RclrsError>;
 
-    fn unwrap(&self) {
-        self.ok().unwrap();
+    /// For the `rcl` calls of `Drop` impls, which can't return errors: logs a failure with
+    /// a description of the operation, see [`log_error`]
+    fn log_on_error(&self, operation: &str) {
+        if let Err(error) = self.ok() {
+            log_error(&error.context(operation));
+        }
     }
 }
 
 impl ToResult for rcl_ret_t {
-    fn ok(&self) -> Result<(), RclReturnCode> {
-        to_rcl_result(*self as i32)
+    fn ok(&self) -> Result<(), RclrsError> {
+        to_rcl_result(*self as i32).map_err(RclrsError::from_rcl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_error_message(message: &str) {
+        let message = CString::new(message).unwrap();
+        unsafe { rcutils_set_error_state(message.as_ptr(), "error.rs\0".as_ptr() as *const _, 0) };
+    }
+
+    #[test]
+    fn ok_maps_failing_return_codes() {
+        assert_eq!((RCL_RET_OK as rcl_ret_t).ok(), Ok(()));
+        let error = (RCL_RET_TOPIC_NAME_INVALID as rcl_ret_t).ok().unwrap_err();
+        assert_eq!(
+            error.code(),
+            &RclReturnCode::RclError(RclErrorCode::TopicNameInvalid)
+        );
+        let error = (RCL_RET_NODE_INVALID as rcl_ret_t).ok().unwrap_err();
+        assert_eq!(
+            error.code(),
+            &RclReturnCode::NodeError(NodeErrorCode::NodeInvalid)
+        );
+    }
+
+    #[test]
+    fn ok_takes_the_rcutils_error_message() {
+        set_error_message("publisher is invalid");
+        let error = (RCL_RET_PUBLISHER_INVALID as rcl_ret_t).ok().unwrap_err();
+        assert!(error.message().unwrap().contains("publisher is invalid"));
+        assert!(unsafe { !rcutils_error_is_set() });
+        // The message isn't reused for the next failure
+        let error = (RCL_RET_ERROR as rcl_ret_t).ok().unwrap_err();
+        assert_eq!(error.message(), None);
+    }
+
+    #[test]
+    fn context_is_displayed_before_the_source() {
+        let error = RclrsError::from(RclErrorCode::TopicNameInvalid)
+            .context("failed to create a publisher on topic 'a b'");
+        assert_eq!(
+            error.to_string(),
+            "failed to create a publisher on topic 'a b'"
+        );
+        assert_eq!(
+            error.code(),
+            &RclReturnCode::RclError(RclErrorCode::TopicNameInvalid)
+        );
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn log_on_error_does_not_panic_on_failing_return_codes() {
+        for ret in [
+            RCL_RET_ERROR,
+            RCL_RET_NODE_INVALID,
+            RCL_RET_WAIT_SET_INVALID,
+        ] {
+            set_error_message("injected failure");
+            (ret as rcl_ret_t).log_on_error("failed to finalize the entity");
+            assert!(unsafe { !rcutils_error_is_set() });
+        }
+        // Unknown return codes and interior NUL bytes in messages are logged as well
+        (12345 as rcl_ret_t).log_on_error("failed to finalize the entity");
+        log_error(&RclrsError::from(RclReturnCode::Error).context("nul \0 byte"));
     }
 }
@@ -0,0 +1,452 @@
+use crate::error::{RclrsError, ToResult};
+use crate::qos::{QoSDurabilityPolicy, QoSHistoryPolicy, QoSProfile, QoSReliabilityPolicy};
+use crate::rcl_bindings::*;
+use crate::{ContextHandle, Gid, MessageInfo};
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::any::Any;
+use libc::uintptr_t;
+
+#[cfg(not(feature = "std"))]
+use spin::{Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+use parking_lot::{Mutex, MutexGuard};
+
+/// A message shared between a publisher and the intra-process subscriptions of its topic
+pub(crate) type SharedMessage = Arc<dyn Any + Send + Sync>;
+
+/// Receiving end of intra-process communication, implemented by
+/// [`Subscription`](crate::Subscription)
+pub(crate) trait IntraProcessSubscription: Send + Sync {
+    /// Queues a message, which must be an `Arc<T>` of the subscription's message type
+    fn deliver(&self, message: SharedMessage, info: &MessageInfo);
+}
+
+struct IntraProcessEntry {
+    topic_name: String,
+    type_support: uintptr_t,
+    qos: QoSProfile,
+    subscription: Weak<dyn IntraProcessSubscription>,
+}
+
+struct IntraProcessPublisherEntry {
+    topic_name: String,
+    type_support: uintptr_t,
+    qos: QoSProfile,
+    gid: Gid,
+}
+
+/// Keeps track of the intra-process subscriptions of a context, so that publishers of
+/// nodes with [`use_intra_process_comms`](crate::NodeOptions::use_intra_process_comms) can
+/// hand messages to them directly
+///
+/// The intra-process publishers are tracked as well, so that subscriptions can tell which
+/// of the messages they take from the middleware they already received intra-process.
+#[derive(Default)]
+pub(crate) struct IntraProcessManager {
+    subscriptions: Mutex<Vec<IntraProcessEntry>>,
+    publishers: Mutex<Vec<IntraProcessPublisherEntry>>,
+}
+
+/// Whether an endpoint with the given QoS communicates intra-process
+///
+/// Transient local endpoints go through the middleware, which keeps the published samples
+/// for subscriptions that join late.
+pub(crate) fn uses_intra_process(qos: &QoSProfile) -> bool {
+    qos.durability != QoSDurabilityPolicy::TransientLocal
+}
+
+impl IntraProcessManager {
+    /// Registers a subscription, which is forgotten once it is dropped
+    pub(crate) fn add_subscription(
+        &self,
+        topic_name: String,
+        type_support: uintptr_t,
+        qos: QoSProfile,
+        subscription: Weak<dyn IntraProcessSubscription>,
+    ) {
+        let subscriptions = &mut *self.subscriptions.lock();
+        subscriptions.retain(|entry| entry.subscription.strong_count() > 0);
+        subscriptions.push(IntraProcessEntry {
+            topic_name,
+            type_support,
+            qos,
+            subscription,
+        });
+    }
+
+    /// Returns the live subscriptions that a publisher with the given topic, message type
+    /// and QoS delivers to
+    pub(crate) fn matching_subscriptions(
+        &self,
+        topic_name: &str,
+        type_support: uintptr_t,
+        qos: &QoSProfile,
+    ) -> Vec<Arc<dyn IntraProcessSubscription>> {
+        self.subscriptions
+            .lock()
+            .iter()
+            .filter(|entry| {
+                entry.topic_name == topic_name
+                    && entry.type_support == type_support
+                    && is_compatible(qos, &entry.qos)
+            })
+            .filter_map(|entry| entry.subscription.upgrade())
+            .collect()
+    }
+
+    fn add_publisher(&self, entry: IntraProcessPublisherEntry) {
+        self.publishers.lock().push(entry);
+    }
+
+    fn remove_publisher(&self, gid: &Gid) {
+        self.publishers.lock().retain(|entry| entry.gid != *gid);
+    }
+
+    /// Whether the publisher with the given GID delivers to a subscription with the given
+    /// topic, message type and QoS intra-process, so that its messages from the middleware
+    /// are duplicates
+    pub(crate) fn delivers_intra_process(
+        &self,
+        gid: &Gid,
+        topic_name: &str,
+        type_support: uintptr_t,
+        qos: &QoSProfile,
+    ) -> bool {
+        self.publishers.lock().iter().any(|entry| {
+            entry.gid == *gid
+                && entry.topic_name == topic_name
+                && entry.type_support == type_support
+                && is_compatible(&entry.qos, qos)
+        })
+    }
+}
+
+/// Sending end of intra-process communication, owned by a [`Publisher`](crate::Publisher)
+pub(crate) struct IntraProcessPublisher {
+    context_handle: Arc<ContextHandle>,
+    topic_name: String,
+    type_support: uintptr_t,
+    qos: QoSProfile,
+    gid: Gid,
+}
+
+impl IntraProcessPublisher {
+    /// Registers the publisher with the manager of its context until it is dropped
+    pub(crate) fn new(
+        context_handle: &Arc<ContextHandle>,
+        topic_name: String,
+        type_support: uintptr_t,
+        qos: QoSProfile,
+        gid: Gid,
+    ) -> Self {
+        context_handle
+            .intra_process_manager
+            .add_publisher(IntraProcessPublisherEntry {
+                topic_name: topic_name.clone(),
+                type_support,
+                qos,
+                gid,
+            });
+        Self {
+            context_handle: context_handle.clone(),
+            topic_name,
+            type_support,
+            qos,
+            gid,
+        }
+    }
+
+    pub(crate) fn matching_subscriptions(&self) -> Vec<Arc<dyn IntraProcessSubscription>> {
+        self.context_handle
+            .intra_process_manager
+            .matching_subscriptions(&self.topic_name, self.type_support, &self.qos)
+    }
+
+    /// Hands the message to each of the subscriptions
+    pub(crate) fn deliver(
+        &self,
+        subscriptions: &[Arc<dyn IntraProcessSubscription>],
+        message: SharedMessage,
+    ) {
+        let mut now: rcutils_time_point_value_t = 0;
+        unsafe { rcutils_system_time_now(&mut now as *mut _) };
+        let info = MessageInfo {
+            source_timestamp: now,
+            received_timestamp: now,
+            publication_sequence_number: None,
+            reception_sequence_number: None,
+            publisher_gid: self.gid,
+            from_intra_process: true,
+        };
+        for subscription in subscriptions {
+            subscription.deliver(message.clone(), &info);
+        }
+    }
+}
+
+impl Drop for IntraProcessPublisher {
+    fn drop(&mut self) {
+        self.context_handle
+            .intra_process_manager
+            .remove_publisher(&self.gid);
+    }
+}
+
+/// Queue of the intra-process messages of a [`Subscription`](crate::Subscription), along
+/// with the guard condition that wakes up the wait set when a message arrives
+pub(crate) struct IntraProcessBuffer<T> {
+    guard_condition: Mutex<rcl_guard_condition_t>,
+    queue: Mutex<VecDeque<(Arc<T>, MessageInfo)>>,
+    depth: usize,
+    topic_name: String,
+    type_support: uintptr_t,
+    qos: QoSProfile,
+    // The guard condition has to be finalized before its context
+    context_handle: Arc<ContextHandle>,
+}
+
+// SAFETY: The guard condition is only accessed through its mutex, and `rcl` guard
+// conditions may be triggered from any thread. The queued messages are `Send` and `Sync`.
+unsafe impl<T> Send for IntraProcessBuffer<T> where T: Send + Sync {}
+unsafe impl<T> Sync for IntraProcessBuffer<T> where T: Send + Sync {}
+
+impl<T> IntraProcessBuffer<T> {
+    pub(crate) fn new(
+        context_handle: &Arc<ContextHandle>,
+        topic_name: String,
+        type_support: uintptr_t,
+        qos: QoSProfile,
+    ) -> Result<Self, RclrsError> {
+        let mut guard_condition = unsafe { rcl_get_zero_initialized_guard_condition() };
+        unsafe {
+            rcl_guard_condition_init(
+                &mut guard_condition as *mut _,
+                &mut *context_handle.lock() as *mut _,
+                rcl_guard_condition_get_default_options(),
+            )
+            .ok()?;
+        }
+        // Like the middleware, keep the last `depth` messages, or all of them
+        let depth = match qos.history {
+            QoSHistoryPolicy::KeepLast if qos.depth > 0 => qos.depth as usize,
+            _ => usize::MAX,
+        };
+        Ok(Self {
+            guard_condition: Mutex::new(guard_condition),
+            queue: Mutex::new(VecDeque::new()),
+            depth,
+            topic_name,
+            type_support,
+            qos,
+            context_handle: context_handle.clone(),
+        })
+    }
+
+    /// Whether a message taken from the middleware was already delivered intra-process
+    ///
+    /// Only the messages of intra-process publishers that deliver to this subscription are
+    /// duplicates, those of other nodes in the process are not.
+    pub(crate) fn is_duplicate(&self, info: &MessageInfo) -> bool {
+        self.context_handle
+            .intra_process_manager
+            .delivers_intra_process(
+                &info.publisher_gid,
+                &self.topic_name,
+                self.type_support,
+                &self.qos,
+            )
+    }
+
+    pub(crate) fn guard_condition(&self) -> MutexGuard<rcl_guard_condition_t> {
+        self.guard_condition.lock()
+    }
+
+    /// Queues a message, dropping the oldest one if the queue is full
+    pub(crate) fn push(&self, message: Arc<T>, info: MessageInfo) {
+        {
+            let queue = &mut *self.queue.lock();
+            if queue.len() >= self.depth {
+                queue.pop_front();
+            }
+            queue.push_back((message, info));
+        }
+        unsafe { rcl_trigger_guard_condition(&mut *self.guard_condition() as *mut _) };
+    }
+
+    pub(crate) fn pop(&self) -> Option<(Arc<T>, MessageInfo)> {
+        self.queue.lock().pop_front()
+    }
+}
+
+impl<T> Drop for IntraProcessBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            rcl_guard_condition_fini(self.guard_condition.get_mut() as *mut _)
+                .log_on_error("failed to finalize an intra-process guard condition")
+        };
+    }
+}
+
+/// Whether a publisher's QoS can serve a subscription's, following the DDS request/offer
+/// rules for reliability and durability
+fn is_compatible(publisher: &QoSProfile, subscription: &QoSProfile) -> bool {
+    let reliability = publisher.reliability != QoSReliabilityPolicy::BestEffort
+        || subscription.reliability == QoSReliabilityPolicy::BestEffort;
+    let durability = publisher.durability != QoSDurabilityPolicy::Volatile
+        || subscription.durability != QoSDurabilityPolicy::TransientLocal;
+    reliability && durability
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qos::{QOS_PROFILE_DEFAULT, QOS_PROFILE_SENSOR_DATA};
+
+    const TYPE_SUPPORT: uintptr_t = 1;
+    const OTHER_TYPE_SUPPORT: uintptr_t = 2;
+
+    #[derive(Default)]
+    struct TestSubscription {
+        received: Mutex<Vec<u32>>,
+    }
+
+    impl IntraProcessSubscription for TestSubscription {
+        fn deliver(&self, message: SharedMessage, _info: &MessageInfo) {
+            if let Ok(message) = message.downcast::<u32>() {
+                self.received.lock().push(*message);
+            }
+        }
+    }
+
+    fn add_subscription(
+        manager: &IntraProcessManager,
+        topic_name: &str,
+        type_support: uintptr_t,
+        qos: QoSProfile,
+    ) -> Arc<TestSubscription> {
+        let subscription = Arc::new(TestSubscription::default());
+        let weak = Arc::downgrade(&subscription) as Weak<dyn IntraProcessSubscription>;
+        manager.add_subscription(String::from(topic_name), type_support, qos, weak);
+        subscription
+    }
+
+    fn add_publisher(manager: &IntraProcessManager, gid: Gid, qos: QoSProfile) {
+        manager.add_publisher(IntraProcessPublisherEntry {
+            topic_name: String::from("/chatter"),
+            type_support: TYPE_SUPPORT,
+            qos,
+            gid,
+        });
+    }
+
+    fn message_info(gid: Gid) -> MessageInfo {
+        MessageInfo {
+            source_timestamp: 0,
+            received_timestamp: 0,
+            publication_sequence_number: None,
+            reception_sequence_number: None,
+            publisher_gid: gid,
+            from_intra_process: false,
+        }
+    }
+
+    #[test]
+    fn delivers_to_the_matching_subscriptions() {
+        let manager = IntraProcessManager::default();
+        let matching = add_subscription(&manager, "/chatter", TYPE_SUPPORT, QOS_PROFILE_DEFAULT);
+        let other_topic = add_subscription(&manager, "/other", TYPE_SUPPORT, QOS_PROFILE_DEFAULT);
+        let other_type = add_subscription(
+            &manager,
+            "/chatter",
+            OTHER_TYPE_SUPPORT,
+            QOS_PROFILE_DEFAULT,
+        );
+
+        let subscriptions =
+            manager.matching_subscriptions("/chatter", TYPE_SUPPORT, &QOS_PROFILE_DEFAULT);
+        assert_eq!(subscriptions.len(), 1);
+        let message: SharedMessage = Arc::new(5u32);
+        for subscription in &subscriptions {
+            subscription.deliver(message.clone(), &message_info([0; 24]));
+        }
+        assert_eq!(*matching.received.lock(), [5]);
+        assert!(other_topic.received.lock().is_empty());
+        assert!(other_type.received.lock().is_empty());
+
+        // Dropped subscriptions are no longer delivered to
+        drop(subscriptions);
+        drop(matching);
+        assert!(manager
+            .matching_subscriptions("/chatter", TYPE_SUPPORT, &QOS_PROFILE_DEFAULT)
+            .is_empty());
+    }
+
+    #[test]
+    fn incompatible_qos_falls_back_to_the_middleware() {
+        let manager = IntraProcessManager::default();
+        let _reliable = add_subscription(&manager, "/chatter", TYPE_SUPPORT, QOS_PROFILE_DEFAULT);
+        let _best_effort =
+            add_subscription(&manager, "/chatter", TYPE_SUPPORT, QOS_PROFILE_SENSOR_DATA);
+
+        // A best effort publisher can't serve a reliable subscription
+        let best_effort =
+            manager.matching_subscriptions("/chatter", TYPE_SUPPORT, &QOS_PROFILE_SENSOR_DATA);
+        assert_eq!(best_effort.len(), 1);
+        let reliable =
+            manager.matching_subscriptions("/chatter", TYPE_SUPPORT, &QOS_PROFILE_DEFAULT);
+        assert_eq!(reliable.len(), 2);
+
+        // So the reliable subscription keeps its messages from the middleware
+        let gid = [1; 24];
+        add_publisher(&manager, gid, QOS_PROFILE_SENSOR_DATA);
+        assert!(!manager.delivers_intra_process(
+            &gid,
+            "/chatter",
+            TYPE_SUPPORT,
+            &QOS_PROFILE_DEFAULT
+        ));
+        assert!(manager.delivers_intra_process(
+            &gid,
+            "/chatter",
+            TYPE_SUPPORT,
+            &QOS_PROFILE_SENSOR_DATA
+        ));
+
+        // Transient local endpoints leave the replay to late joiners to the middleware
+        let transient_local = QoSProfile {
+            durability: QoSDurabilityPolicy::TransientLocal,
+            ..QOS_PROFILE_DEFAULT
+        };
+        assert!(!uses_intra_process(&transient_local));
+        assert!(uses_intra_process(&QOS_PROFILE_DEFAULT));
+    }
+
+    #[test]
+    fn only_intra_process_publications_are_duplicates() {
+        let manager = IntraProcessManager::default();
+        let intra_process_gid = [1; 24];
+        let inter_process_gid = [2; 24];
+        add_publisher(&manager, intra_process_gid, QOS_PROFILE_DEFAULT);
+
+        // Messages of a node in the same process without intra-process communication are
+        // only delivered by the middleware
+        let is_duplicate = |info: &MessageInfo| {
+            manager.delivers_intra_process(
+                &info.publisher_gid,
+                "/chatter",
+                TYPE_SUPPORT,
+                &QOS_PROFILE_DEFAULT,
+            )
+        };
+        assert!(is_duplicate(&message_info(intra_process_gid)));
+        assert!(!is_duplicate(&message_info(inter_process_gid)));
+
+        // Once the publisher is gone, its GID is no longer known
+        manager.remove_publisher(&intra_process_gid);
+        assert!(!is_duplicate(&message_info(intra_process_gid)));
+    }
+}
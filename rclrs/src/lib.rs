@@ -11,23 +11,34 @@ extern crate parking_lot;
 #[cfg(not(feature = "std"))]
 extern crate spin;
 
+pub mod action;
+pub mod component;
 pub mod context;
 pub mod error;
+pub mod lifecycle;
 pub mod node;
 pub mod qos;
 // pub mod spinlock;
 pub mod wait;
 
+mod arguments;
+mod intra_process;
 mod rcl_bindings;
+#[cfg(all(feature = "std", unix))]
+mod signal_handler;
 
+pub use self::action::*;
+pub use self::component::*;
 pub use self::context::*;
 pub use self::error::*;
+pub use self::lifecycle::*;
 pub use self::node::*;
 pub use self::qos::*;
 
-use self::rcl_bindings::*;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::ops::{Deref, DerefMut};
-use wait::{WaitSet, WaitSetErrorResponse};
+use wait::{WaitSet, Waitable, WaitableCount};
 
 pub trait Handle<T> {
     type DerefT: Deref<Target = T>;
@@ -37,19 +48,28 @@ pub trait Handle<T> {
     fn get_mut(self) -> Self::DerefMutT;
 }
 
-/// Wrapper around [`spin_once`]
-pub fn spin<'node>(node: &'node node::Node) -> Result<(), WaitSetErrorResponse> {
-    while unsafe { rcl_context_is_valid(&mut *node.context.lock() as *mut _) } {
-        if let Some(error) = spin_once(node, 500).err() {
-            match error {
-                WaitSetErrorResponse::DroppedSubscription
-                | WaitSetErrorResponse::ReturnCode(RclReturnCode::Timeout) => continue,
-                error => return Err(error),
-            };
-        }
-    }
+// Nodes and their entities are meant to be shared between threads. Every `rcl` handle is
+// owned by a Rust type that keeps it in a mutex and only accesses it through that mutex, which
+// is what the `unsafe impl`s of `Send` and `Sync` on these types rely on; `rcl` allows using
+// an entity from any thread as long as calls on it are not concurrent.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Context>();
+    assert_send_sync::<Node>();
+    assert_send_sync::<NodeHandle>();
+    assert_send_sync::<PublisherHandle>();
+    assert_send_sync::<SubscriptionHandle>();
+    assert_send_sync::<GraphEvents>();
+    assert_send_sync::<ActionClientHandle>();
+    assert_send_sync::<LifecycleNode>();
+};
 
-    Ok(())
+/// Wrapper around [`spin_once`]
+///
+/// Blocks until the node's context is shut down, e.g. by [`Context::shutdown`] or by
+/// SIGINT, and then returns `Ok`.
+pub fn spin<'node>(node: &'node node::Node) -> Result<(), RclrsError> {
+    spin_until_shutdown(&node.context, || spin_once(node, -1))
 }
 
 /// Main function for waiting.
@@ -89,16 +109,90 @@ pub fn spin<'node>(node: &'node node::Node) -> Result<(), WaitSetErrorResponse>
 ///         +--------------------+
 ///
 ///
-pub fn spin_once<'node>(node: &'node Node, timeout: i64) -> Result<(), WaitSetErrorResponse> {
-    let number_of_subscriptions = node.subscriptions.len();
-    let number_of_guard_conditions = 0;
-    let number_of_timers = 0;
-    let number_of_clients = 0;
-    let number_of_services = 0;
-    let number_of_events = 0;
+pub fn spin_once<'node>(node: &'node Node, timeout: i64) -> Result<(), RclrsError> {
+    spin_nodes_once(&[node], timeout)
+}
 
-    let context = &mut *node.context.lock();
+/// Like [`spin`], but for several nodes of the same context, which share one wait set
+///
+/// This is how a component container executes the nodes loaded into it.
+pub fn spin_nodes(nodes: &[&Node]) -> Result<(), RclrsError> {
+    match nodes.first() {
+        Some(node) => spin_until_shutdown(&node.context, || spin_nodes_once(nodes, -1)),
+        None => Ok(()),
+    }
+}
+
+/// Calls `spin_once` until the context is shut down, which is how [`spin`], [`spin_nodes`]
+/// and the component manager spin their nodes
+pub(crate) fn spin_until_shutdown<F>(
+    context: &ContextHandle,
+    mut spin_once: F,
+) -> Result<(), RclrsError>
+where
+    F: FnMut() -> Result<(), RclrsError>,
+{
+    while context.is_valid() {
+        if let Some(error) = spin_once().err() {
+            match error {
+                error if error.code() == &RclReturnCode::Timeout => continue,
+                // The context was shut down while setting up the wait set
+                _ if !context.is_valid() => break,
+                error => return Err(error),
+            };
+        }
+    }
 
+    Ok(())
+}
+
+/// Like [`spin_once`], but waits on the entities of all `nodes` at once
+///
+/// The nodes must belong to the same context, otherwise
+/// [`RclReturnCode::InvalidArgument`] is returned.
+pub fn spin_nodes_once(nodes: &[&Node], timeout: i64) -> Result<(), RclrsError> {
+    let context = match nodes.first() {
+        Some(node) => &node.context,
+        None => return Ok(()),
+    };
+    if nodes
+        .iter()
+        .any(|node| !Arc::ptr_eq(&node.context, context))
+    {
+        return Err(RclReturnCode::InvalidArgument.into());
+    }
+
+    // Snapshot the entity tables, so that callbacks and other threads can add or remove
+    // entities without deadlocking; changes take effect in the next call
+    let mut subscriptions: Vec<Arc<dyn SubscriptionBase>> = Vec::new();
+    let mut graph_events: Vec<Vec<Arc<GraphEvents>>> = Vec::new();
+    let mut waitables: Vec<Arc<dyn Waitable>> = Vec::new();
+    for node in nodes {
+        subscriptions.extend(node.subscriptions.lock().iter().cloned());
+        graph_events.push(node.graph_events.lock().clone());
+        waitables.extend(node.waitables.lock().iter().cloned());
+    }
+
+    let mut waitable_count = WaitableCount::default();
+    for waitable in &waitables {
+        waitable_count += waitable.count();
+    }
+
+    let number_of_subscriptions = subscriptions.len() + waitable_count.subscriptions;
+    // All graph event handles of a node share the node's single graph guard condition, and
+    // every wait set also waits on the context's interrupt guard condition
+    let number_of_guard_conditions = 1
+        + graph_events
+            .iter()
+            .filter(|graph_events| !graph_events.is_empty())
+            .count()
+        + waitable_count.guard_conditions;
+    let number_of_timers = waitable_count.timers;
+    let number_of_clients = waitable_count.clients;
+    let number_of_services = waitable_count.services;
+    let number_of_events = waitable_count.events;
+
+    // The context must not stay locked while waiting, or it couldn't be shut down
     let mut wait_set = WaitSet::new(
         number_of_subscriptions,
         number_of_guard_conditions,
@@ -106,27 +200,173 @@ pub fn spin_once<'node>(node: &'node Node, timeout: i64) -> Result<(), WaitSetEr
         number_of_clients,
         number_of_services,
         number_of_events,
-        context,
+        &mut *context.lock(),
     )?;
 
-    for subscription in &node.subscriptions {
-        match wait_set.add_subscription(subscription) {
-            Ok(()) => (),
-            Err(WaitSetErrorResponse::DroppedSubscription) => (),
-            Err(err) => return Err(err),
+    let interrupt_guard_condition_index =
+        wait_set.add_guard_condition(&*context.interrupt_guard_condition())?;
+
+    for subscription in &subscriptions {
+        wait_set.add_subscription(subscription)?;
+    }
+
+    let mut graph_guard_condition_indices = Vec::with_capacity(graph_events.len());
+    for node_graph_events in &graph_events {
+        let index = match node_graph_events.first() {
+            Some(graph_event) => {
+                Some(wait_set.add_guard_condition(unsafe { &*graph_event.guard_condition() })?)
+            }
+            None => None,
         };
+        graph_guard_condition_indices.push(index);
+    }
+
+    for waitable in &waitables {
+        waitable.add_to_wait_set(&mut wait_set)?;
     }
 
     wait_set.wait(timeout)?;
-    for subscription in &node.subscriptions {
-        if let Some(subscription) = subscription.upgrade() {
-            let mut message = subscription.create_message();
-            let result = subscription.take(&mut *message).unwrap();
-            if result {
-                subscription.callback_fn(message);
+
+    if wait_set.is_guard_condition_ready(interrupt_guard_condition_index) {
+        return Ok(());
+    }
+
+    // One failing entity must not keep the other ready entities from being executed, so the
+    // first error is only returned at the end
+    let mut first_error = None;
+
+    for waitable in &waitables {
+        keep_first_error(&mut first_error, waitable.execute(&wait_set));
+    }
+
+    for (node_graph_events, index) in graph_events.iter().zip(graph_guard_condition_indices) {
+        if let Some(index) = index {
+            if wait_set.is_guard_condition_ready(index) {
+                for graph_event in node_graph_events {
+                    graph_event.callback_fn();
+                }
             }
         }
     }
 
-    Ok(())
+    for subscription in &subscriptions {
+        keep_first_error(&mut first_error, subscription.execute());
+    }
+
+    match first_error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Keeps the first error of the entities executed by [`spin_nodes_once`] and logs the others
+fn keep_first_error(first_error: &mut Option<RclrsError>, result: Result<(), RclrsError>) {
+    if let Err(error) = result {
+        match first_error {
+            Some(_) => error::log_error(&error),
+            None => *first_error = Some(error),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::rcl_bindings::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::time::Duration;
+    use parking_lot::Mutex;
+    use std::thread;
+
+    /// Wakes up the wait set with its own guard condition and then fails to execute
+    struct FailingWaitable {
+        guard_condition: Mutex<rcl_guard_condition_t>,
+        executions: AtomicUsize,
+    }
+
+    // SAFETY: The guard condition is only accessed through its mutex
+    unsafe impl Send for FailingWaitable {}
+    unsafe impl Sync for FailingWaitable {}
+
+    impl Waitable for FailingWaitable {
+        fn count(&self) -> WaitableCount {
+            WaitableCount {
+                guard_conditions: 1,
+                ..Default::default()
+            }
+        }
+
+        fn add_to_wait_set(&self, wait_set: &mut WaitSet) -> Result<(), RclrsError> {
+            wait_set.add_guard_condition(&self.guard_condition.lock())?;
+            Ok(())
+        }
+
+        fn execute(&self, _wait_set: &WaitSet) -> Result<(), RclrsError> {
+            self.executions.fetch_add(1, Ordering::SeqCst);
+            Err(RclReturnCode::Error.into())
+        }
+    }
+
+    #[test]
+    fn shutdown_wakes_up_a_blocked_spin() {
+        let context = ContextBuilder::new(Vec::new())
+            .install_signal_handlers(false)
+            .build()
+            .unwrap();
+        let node = context.create_node("spin_test").unwrap();
+        let shutdown_context = context.clone();
+        let shutdown_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            shutdown_context.shutdown()
+        });
+        // Blocks until the context is shut down
+        spin(&node).unwrap();
+        assert!(!context.ok().unwrap());
+        assert!(shutdown_thread.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn a_failing_entity_does_not_keep_the_others_from_executing() {
+        let context = ContextBuilder::new(Vec::new())
+            .install_signal_handlers(false)
+            .build()
+            .unwrap();
+        let node = context.create_node("failing_entity_test").unwrap();
+        let waitables: Vec<Arc<FailingWaitable>> = (0..2)
+            .map(|_| unsafe {
+                let mut guard_condition = rcl_get_zero_initialized_guard_condition();
+                rcl_guard_condition_init(
+                    &mut guard_condition as *mut _,
+                    &mut *context.handle.lock() as *mut _,
+                    rcl_guard_condition_get_default_options(),
+                )
+                .ok()
+                .unwrap();
+                rcl_trigger_guard_condition(&mut guard_condition as *mut _)
+                    .ok()
+                    .unwrap();
+                Arc::new(FailingWaitable {
+                    guard_condition: Mutex::new(guard_condition),
+                    executions: AtomicUsize::new(0),
+                })
+            })
+            .collect();
+        for waitable in &waitables {
+            node.add_waitable(waitable.clone());
+        }
+
+        let error = spin_once(&node, 1_000_000_000).unwrap_err();
+        assert_eq!(error.code(), &RclReturnCode::Error);
+        for waitable in &waitables {
+            assert_eq!(waitable.executions.load(Ordering::SeqCst), 1);
+        }
+
+        for waitable in waitables {
+            unsafe {
+                rcl_guard_condition_fini(&mut *waitable.guard_condition.lock() as *mut _)
+                    .ok()
+                    .unwrap()
+            };
+        }
+    }
 }
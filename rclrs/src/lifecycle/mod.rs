@@ -0,0 +1,373 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::ops::{Deref, DerefMut};
+
+use crate::error::RclrsError;
+use crate::qos::QoSProfile;
+use crate::{Context, Node};
+
+mod publisher;
+pub use self::publisher::*;
+mod state_machine;
+pub(crate) use self::state_machine::*;
+
+/// IDs of the states of the default lifecycle state machine, as defined in `lifecycle_msgs/msg/State`
+pub mod state_id {
+    pub const UNKNOWN: u8 = 0;
+    pub const UNCONFIGURED: u8 = 1;
+    pub const INACTIVE: u8 = 2;
+    pub const ACTIVE: u8 = 3;
+    pub const FINALIZED: u8 = 4;
+    pub const CONFIGURING: u8 = 10;
+    pub const CLEANING_UP: u8 = 11;
+    pub const SHUTTING_DOWN: u8 = 12;
+    pub const ACTIVATING: u8 = 13;
+    pub const DEACTIVATING: u8 = 14;
+    pub const ERROR_PROCESSING: u8 = 15;
+}
+
+/// Snapshot of a lifecycle node's state
+#[derive(Clone, Debug, PartialEq)]
+pub struct LifecycleState {
+    pub id: u8,
+    pub label: String,
+}
+
+/// Result of a user-provided transition callback, e.g. [`LifecycleNode::on_configure`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CallbackReturn {
+    /// The transition succeeds and the node moves to the goal state
+    Success,
+    /// The transition is rejected and the node goes back to the start state
+    Failure,
+    /// The node moves to `ErrorProcessing` and [`LifecycleNode::on_error`] is invoked
+    Error,
+}
+
+/// A managed node, driven through the standard `rcl_lifecycle` state machine
+///
+/// The node starts out unconfigured. Transitions can be triggered either locally, e.g. with
+/// [`configure`](Self::configure), or remotely via the `~/change_state` service. The current
+/// state is available via the `~/get_state` service, and every transition is announced on
+/// `~/transition_event`.
+///
+/// A `LifecycleNode` dereferences to [`Node`], so it can be passed to [`spin`](crate::spin)
+/// and used to create regular publishers and subscriptions.
+pub struct LifecycleNode {
+    node: Node,
+    state_machine: Arc<LifecycleStateMachine>,
+}
+
+impl LifecycleNode {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(node_name: &str, context: &Context) -> Result<LifecycleNode, RclrsError> {
+        Self::new_with_namespace(node_name, "", context)
+    }
+
+    pub fn new_with_namespace(
+        node_name: &str,
+        node_ns: &str,
+        context: &Context,
+    ) -> Result<LifecycleNode, RclrsError> {
+        let node = Node::builder(context, node_name)
+            .namespace(node_ns)
+            .build()?;
+        let state_machine = Arc::new(LifecycleStateMachine::new(&node)?);
+        node.add_waitable(state_machine.clone());
+
+        Ok(LifecycleNode {
+            node,
+            state_machine,
+        })
+    }
+
+    pub fn current_state(&self) -> LifecycleState {
+        self.state_machine.current_state()
+    }
+
+    /// Triggers the transition with the given label, e.g. `"configure"`, and returns the new state
+    ///
+    /// If the transition callback fails, the node stays in (or returns to) its previous state
+    /// and that state is returned. An error is only returned if the transition is not valid
+    /// from the current state.
+    pub fn trigger_transition(&self, label: &str) -> Result<LifecycleState, RclrsError> {
+        self.state_machine
+            .trigger_transition(label)
+            .map(|(state, _)| state)
+    }
+
+    pub fn configure(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition("configure")
+    }
+
+    pub fn cleanup(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition("cleanup")
+    }
+
+    pub fn activate(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition("activate")
+    }
+
+    pub fn deactivate(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition("deactivate")
+    }
+
+    pub fn shutdown(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition("shutdown")
+    }
+
+    /// Sets the callback run when transitioning from unconfigured to inactive
+    ///
+    /// Like all transition callbacks, it receives the state the node was in before the
+    /// transition started. Unset callbacks always return [`CallbackReturn::Success`].
+    /// Callbacks may replace callbacks, but transitions triggered from a callback fail, as
+    /// do transitions triggered on other threads while one is in progress.
+    pub fn on_configure<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> CallbackReturn + Send + 'static,
+    {
+        *self.state_machine.callbacks.on_configure.lock() = Some(Box::new(callback));
+    }
+
+    /// Sets the callback run when transitioning from inactive to unconfigured
+    pub fn on_cleanup<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> CallbackReturn + Send + 'static,
+    {
+        *self.state_machine.callbacks.on_cleanup.lock() = Some(Box::new(callback));
+    }
+
+    /// Sets the callback run when transitioning from inactive to active
+    pub fn on_activate<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> CallbackReturn + Send + 'static,
+    {
+        *self.state_machine.callbacks.on_activate.lock() = Some(Box::new(callback));
+    }
+
+    /// Sets the callback run when transitioning from active to inactive
+    pub fn on_deactivate<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> CallbackReturn + Send + 'static,
+    {
+        *self.state_machine.callbacks.on_deactivate.lock() = Some(Box::new(callback));
+    }
+
+    /// Sets the callback run when shutting down from any primary state
+    pub fn on_shutdown<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> CallbackReturn + Send + 'static,
+    {
+        *self.state_machine.callbacks.on_shutdown.lock() = Some(Box::new(callback));
+    }
+
+    /// Sets the callback run after another callback returned [`CallbackReturn::Error`]
+    ///
+    /// On success the node goes back to unconfigured, otherwise it is finalized.
+    pub fn on_error<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> CallbackReturn + Send + 'static,
+    {
+        *self.state_machine.callbacks.on_error.lock() = Some(Box::new(callback));
+    }
+
+    /// Creates a publisher that only publishes while the node is active
+    pub fn create_lifecycle_publisher<T>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<LifecyclePublisher<T>, RclrsError>
+    where
+        T: rclrs_common::traits::MessageDefinition,
+    {
+        LifecyclePublisher::<T>::new(self, topic, qos)
+    }
+}
+
+impl Deref for LifecycleNode {
+    type Target = Node;
+
+    fn deref(&self) -> &Node {
+        &self.node
+    }
+}
+
+impl DerefMut for LifecycleNode {
+    fn deref_mut(&mut self) -> &mut Node {
+        &mut self.node
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::rcl_bindings::*;
+    use crate::{spin_once, ContextBuilder, QoSDurabilityPolicy, QOS_PROFILE_DEFAULT};
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use parking_lot::Mutex;
+    use rclrs_common::error::RclReturnCode;
+
+    fn context() -> Context {
+        ContextBuilder::new(Vec::new())
+            .install_signal_handlers(false)
+            .build()
+            .unwrap()
+    }
+
+    /// `lifecycle_msgs/msg/TransitionEvent`, which has type support in the `rcl` bindings,
+    /// reduced to its timestamp
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct TestMessage {
+        timestamp: u64,
+    }
+
+    struct RmwTestMessage(lifecycle_msgs__msg__TransitionEvent);
+
+    impl Default for RmwTestMessage {
+        fn default() -> Self {
+            let mut message = unsafe { core::mem::zeroed() };
+            unsafe { lifecycle_msgs__msg__TransitionEvent__init(&mut message as *mut _) };
+            Self(message)
+        }
+    }
+
+    impl Drop for RmwTestMessage {
+        fn drop(&mut self) {
+            unsafe { lifecycle_msgs__msg__TransitionEvent__fini(&mut self.0 as *mut _) };
+        }
+    }
+
+    impl rclrs_common::traits::MessageDefinition for TestMessage {
+        type RmwMsg = RmwTestMessage;
+
+        fn type_support() -> *const rclrs_common::rmw::rosidl_message_type_support_t {
+            unsafe {
+                rosidl_typesupport_c__get_message_type_support_handle__lifecycle_msgs__msg__TransitionEvent()
+                    as *const _
+            }
+        }
+
+        fn into_rmw(message: &Self) -> Self::RmwMsg {
+            let mut rmw_message = RmwTestMessage::default();
+            rmw_message.0.timestamp = message.timestamp;
+            rmw_message
+        }
+
+        fn from_rmw(message: &Self::RmwMsg) -> Self {
+            Self {
+                timestamp: message.0.timestamp,
+            }
+        }
+    }
+
+    #[test]
+    fn only_valid_transitions_change_the_state() {
+        let context = context();
+        let node = LifecycleNode::new("lifecycle_transitions_test", &context).unwrap();
+        assert_eq!(node.current_state().id, state_id::UNCONFIGURED);
+
+        assert!(node.activate().is_err());
+        assert!(node.trigger_transition("no_such_transition").is_err());
+        assert_eq!(node.current_state().id, state_id::UNCONFIGURED);
+
+        assert_eq!(node.configure().unwrap().id, state_id::INACTIVE);
+        assert_eq!(node.activate().unwrap().id, state_id::ACTIVE);
+        assert!(node.configure().is_err());
+        assert_eq!(node.deactivate().unwrap().id, state_id::INACTIVE);
+        assert_eq!(node.cleanup().unwrap().id, state_id::UNCONFIGURED);
+        assert_eq!(node.shutdown().unwrap().id, state_id::FINALIZED);
+        assert!(node.configure().is_err());
+        assert_eq!(node.current_state().id, state_id::FINALIZED);
+    }
+
+    #[test]
+    fn callback_results_decide_the_resulting_state() {
+        let context = context();
+        let node = LifecycleNode::new("lifecycle_callbacks_test", &context).unwrap();
+
+        node.on_configure(|_| CallbackReturn::Failure);
+        assert_eq!(node.configure().unwrap().id, state_id::UNCONFIGURED);
+        node.on_configure(|previous_state| {
+            assert_eq!(previous_state.id, state_id::UNCONFIGURED);
+            CallbackReturn::Success
+        });
+        assert_eq!(node.configure().unwrap().id, state_id::INACTIVE);
+
+        // Errors are handled in ErrorProcessing, which goes back to unconfigured on success
+        let errors = Arc::new(AtomicUsize::new(0));
+        let counted_errors = errors.clone();
+        node.on_activate(|_| CallbackReturn::Error);
+        node.on_error(move |previous_state| {
+            assert_eq!(previous_state.id, state_id::INACTIVE);
+            counted_errors.fetch_add(1, Ordering::SeqCst);
+            CallbackReturn::Success
+        });
+        assert_eq!(node.activate().unwrap().id, state_id::UNCONFIGURED);
+        assert_eq!(errors.load(Ordering::SeqCst), 1);
+
+        // and finalizes the node otherwise
+        assert_eq!(node.configure().unwrap().id, state_id::INACTIVE);
+        node.on_error(|_| CallbackReturn::Failure);
+        assert_eq!(node.activate().unwrap().id, state_id::FINALIZED);
+    }
+
+    #[test]
+    fn callbacks_may_set_callbacks_but_not_trigger_transitions() {
+        let context = context();
+        let node = Arc::new(LifecycleNode::new("lifecycle_reentrancy_test", &context).unwrap());
+        let callback_node = Arc::downgrade(&node);
+        node.on_configure(move |_| {
+            let node = callback_node.upgrade().unwrap();
+            assert!(node.activate().is_err());
+            node.on_configure(|_| CallbackReturn::Failure);
+            CallbackReturn::Success
+        });
+        assert_eq!(node.configure().unwrap().id, state_id::INACTIVE);
+
+        // The callback replaced itself
+        assert_eq!(node.cleanup().unwrap().id, state_id::UNCONFIGURED);
+        assert_eq!(node.configure().unwrap().id, state_id::UNCONFIGURED);
+    }
+
+    #[test]
+    fn inactive_publishers_drop_messages() {
+        let context = context();
+        let node = LifecycleNode::new("lifecycle_publisher_test", &context).unwrap();
+        // The subscription gets the kept messages however late it is matched
+        let qos = QoSProfile {
+            durability: QoSDurabilityPolicy::TransientLocal,
+            ..QOS_PROFILE_DEFAULT
+        };
+        let publisher = node
+            .create_lifecycle_publisher::<TestMessage>("lifecycle_publisher_test", qos)
+            .unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let subscription_received = received.clone();
+        let _subscription = node
+            .create_subscription::<TestMessage, _>(
+                "lifecycle_publisher_test",
+                qos,
+                move |message: &TestMessage| subscription_received.lock().push(message.timestamp),
+            )
+            .unwrap();
+
+        assert!(!publisher.is_activated());
+        publisher.publish(&TestMessage { timestamp: 1 }).unwrap();
+        node.configure().unwrap();
+        node.activate().unwrap();
+        assert!(publisher.is_activated());
+        publisher.publish(&TestMessage { timestamp: 2 }).unwrap();
+
+        for _ in 0..50 {
+            if !received.lock().is_empty() {
+                break;
+            }
+            if let Err(error) = spin_once(&node, 100_000_000) {
+                assert_eq!(error.code(), &RclReturnCode::Timeout);
+            }
+        }
+        assert_eq!(*received.lock(), [2]);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::error::RclrsError;
+use crate::qos::QoSProfile;
+use crate::Publisher;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::LifecycleNode;
+
+/// Publisher that drops messages unless its [`LifecycleNode`] is in the active state
+pub struct LifecyclePublisher<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    publisher: Publisher<T>,
+    active: Arc<AtomicBool>,
+}
+
+impl<T> LifecyclePublisher<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    pub fn new(node: &LifecycleNode, topic: &str, qos: QoSProfile) -> Result<Self, RclrsError> {
+        Ok(Self {
+            publisher: Publisher::new(node, topic, qos)?,
+            active: node.state_machine.active.clone(),
+        })
+    }
+
+    pub fn is_activated(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Publishes `message` if the node is active, otherwise silently drops it
+    pub fn publish(&self, message: &T) -> Result<(), RclrsError> {
+        if !self.is_activated() {
+            return Ok(());
+        }
+        self.publisher.publish(message)
+    }
+}
@@ -0,0 +1,516 @@
+use crate::error::{ErrorContext, RclrsError, ToResult};
+use crate::node::graph::string_from_ptr;
+use crate::rcl_bindings::*;
+use crate::wait::{WaitSet, Waitable, WaitableCount};
+use crate::{Node, NodeHandle};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cstr_core::CString;
+use rclrs_common::error::{to_rcl_result, RclReturnCode, ServiceErrorCode};
+
+use super::{state_id, CallbackReturn, LifecycleState};
+
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+#[cfg(feature = "std")]
+use parking_lot::Mutex;
+
+// Labels of the transitions leaving the intermediate states, see `rcl_lifecycle/transition_map.h`
+const TRANSITION_SUCCESS_LABEL: &str = "transition_success";
+const TRANSITION_FAILURE_LABEL: &str = "transition_failure";
+const TRANSITION_ERROR_LABEL: &str = "transition_error";
+
+pub(crate) type LifecycleCallback =
+    Box<dyn FnMut(&LifecycleState) -> CallbackReturn + Send + 'static>;
+
+#[derive(Default)]
+pub(crate) struct LifecycleCallbacks {
+    pub on_configure: Mutex<Option<LifecycleCallback>>,
+    pub on_cleanup: Mutex<Option<LifecycleCallback>>,
+    pub on_activate: Mutex<Option<LifecycleCallback>>,
+    pub on_deactivate: Mutex<Option<LifecycleCallback>>,
+    pub on_shutdown: Mutex<Option<LifecycleCallback>>,
+    pub on_error: Mutex<Option<LifecycleCallback>>,
+}
+
+/// Owns the `rcl_lifecycle_state_machine_t` of a [`LifecycleNode`](super::LifecycleNode)
+/// along with the services of its com interface
+pub(crate) struct LifecycleStateMachine {
+    handle: Mutex<rcl_lifecycle_state_machine_t>,
+    node_handle: Arc<NodeHandle>,
+    pub(crate) callbacks: LifecycleCallbacks,
+    /// Held for the whole of a transition, from checking that it is valid until the node is
+    /// in the resulting state
+    transition_lock: Mutex<()>,
+    /// Shared with the lifecycle publishers, `true` iff the node is in the active state
+    pub(crate) active: Arc<AtomicBool>,
+    /// Where each of the [`COM_SERVICES`] went in the last wait set
+    service_indices: Mutex<[usize; COM_SERVICES.len()]>,
+}
+
+// SAFETY: the state machine and its services may be used from any thread as long as calls
+// on them are not concurrent, which the mutex ensures.
+unsafe impl Send for LifecycleStateMachine {}
+unsafe impl Sync for LifecycleStateMachine {}
+
+impl LifecycleStateMachine {
+    pub(crate) fn new(node: &Node) -> Result<Self, RclrsError> {
+        let mut state_machine = unsafe { rcl_lifecycle_get_zero_initialized_state_machine() };
+        let node_handle = &mut *node.handle.lock();
+
+        unsafe {
+            let ts_pub_notify =
+                rosidl_typesupport_c__get_message_type_support_handle__lifecycle_msgs__msg__TransitionEvent();
+            let ts_srv_change_state =
+                rosidl_typesupport_c__get_service_type_support_handle__lifecycle_msgs__srv__ChangeState();
+            let ts_srv_get_state =
+                rosidl_typesupport_c__get_service_type_support_handle__lifecycle_msgs__srv__GetState();
+            let ts_srv_get_available_states =
+                rosidl_typesupport_c__get_service_type_support_handle__lifecycle_msgs__srv__GetAvailableStates();
+            let ts_srv_get_available_transitions =
+                rosidl_typesupport_c__get_service_type_support_handle__lifecycle_msgs__srv__GetAvailableTransitions();
+
+            #[cfg(ros_distro = "foxy")]
+            {
+                let allocator = rcutils_get_default_allocator();
+                rcl_lifecycle_state_machine_init(
+                    &mut state_machine as *mut _,
+                    node_handle as *mut _,
+                    ts_pub_notify,
+                    ts_srv_change_state,
+                    ts_srv_get_state,
+                    ts_srv_get_available_states,
+                    ts_srv_get_available_transitions,
+                    ts_srv_get_available_transitions,
+                    true,
+                    &allocator as *const _,
+                )
+                .ok()?;
+            }
+
+            #[cfg(not(ros_distro = "foxy"))]
+            {
+                let mut state_machine_options = rcl_lifecycle_get_default_state_machine_options();
+                state_machine_options.enable_com_interface = true;
+                state_machine_options.initialize_default_states = true;
+                rcl_lifecycle_state_machine_init(
+                    &mut state_machine as *mut _,
+                    node_handle as *mut _,
+                    ts_pub_notify,
+                    ts_srv_change_state,
+                    ts_srv_get_state,
+                    ts_srv_get_available_states,
+                    ts_srv_get_available_transitions,
+                    ts_srv_get_available_transitions,
+                    &state_machine_options as *const _,
+                )
+                .ok()?;
+            }
+        }
+
+        Ok(Self {
+            handle: Mutex::new(state_machine),
+            node_handle: node.handle.clone(),
+            callbacks: LifecycleCallbacks::default(),
+            transition_lock: Mutex::new(()),
+            active: Arc::new(AtomicBool::new(false)),
+            service_indices: Mutex::new([usize::MAX; COM_SERVICES.len()]),
+        })
+    }
+
+    pub(crate) fn current_state(&self) -> LifecycleState {
+        let handle = &*self.handle.lock();
+        unsafe { state_from_ptr(handle.current_state) }
+    }
+
+    /// Runs a full transition: the primary transition, the user callback of the intermediate
+    /// state and, if that callback errored, the error handling
+    ///
+    /// Returns the resulting state along with what the transition callback returned. Fails
+    /// if another transition is in progress, e.g. when called from a transition callback,
+    /// like `rcl_lifecycle` does for transitions from an intermediate state.
+    pub(crate) fn trigger_transition(
+        &self,
+        label: &str,
+    ) -> Result<(LifecycleState, CallbackReturn), RclrsError> {
+        let _transition = self.transition_lock.try_lock().ok_or_else(|| {
+            RclrsError::from(RclReturnCode::Error).context(format!(
+                "can't trigger transition '{}' while another transition is in progress",
+                label
+            ))
+        })?;
+        let previous_state = self.current_state();
+        self.trigger_transition_by_label(label)?;
+
+        let intermediate_state = self.current_state();
+        let result = self.run_callback(intermediate_state.id, &previous_state);
+        self.trigger_transition_by_label(match result {
+            CallbackReturn::Success => TRANSITION_SUCCESS_LABEL,
+            CallbackReturn::Failure => TRANSITION_FAILURE_LABEL,
+            CallbackReturn::Error => TRANSITION_ERROR_LABEL,
+        })?;
+
+        if result == CallbackReturn::Error {
+            let error_result = self.run_callback(state_id::ERROR_PROCESSING, &previous_state);
+            self.trigger_transition_by_label(match error_result {
+                CallbackReturn::Success => TRANSITION_SUCCESS_LABEL,
+                _ => TRANSITION_FAILURE_LABEL,
+            })?;
+        }
+
+        let state = self.current_state();
+        self.active
+            .store(state.id == state_id::ACTIVE, Ordering::SeqCst);
+        Ok((state, result))
+    }
+
+    fn trigger_transition_by_label(&self, label: &str) -> Result<(), RclrsError> {
+        let label_c_string = CString::new(label).map_err(|_| {
+            RclrsError::from(RclReturnCode::InvalidArgument)
+                .context(format!("transition label {:?} contains a NUL byte", label))
+        })?;
+        let handle = &mut *self.handle.lock();
+        unsafe {
+            rcl_lifecycle_trigger_transition_by_label(
+                handle as *mut _,
+                label_c_string.as_ptr(),
+                true,
+            )
+            .ok()
+        }
+    }
+
+    fn run_callback(
+        &self,
+        intermediate_state_id: u8,
+        previous_state: &LifecycleState,
+    ) -> CallbackReturn {
+        let slot = match intermediate_state_id {
+            state_id::CONFIGURING => &self.callbacks.on_configure,
+            state_id::CLEANING_UP => &self.callbacks.on_cleanup,
+            state_id::ACTIVATING => &self.callbacks.on_activate,
+            state_id::DEACTIVATING => &self.callbacks.on_deactivate,
+            state_id::SHUTTING_DOWN => &self.callbacks.on_shutdown,
+            state_id::ERROR_PROCESSING => &self.callbacks.on_error,
+            _ => return CallbackReturn::Success,
+        };
+        // Taken out of its slot while it runs, so that it may set callbacks itself
+        let mut callback = match slot.lock().take() {
+            Some(callback) => callback,
+            None => return CallbackReturn::Success,
+        };
+        let result = callback(previous_state);
+        let mut slot = slot.lock();
+        if slot.is_none() {
+            *slot = Some(callback);
+        }
+        result
+    }
+
+    /// Looks up the label of a transition that is valid from the current state
+    fn transition_label_by_id(&self, transition_id: u8) -> Option<String> {
+        let handle = &*self.handle.lock();
+        unsafe {
+            let transition =
+                rcl_lifecycle_get_transition_by_id(handle.current_state, transition_id);
+            if transition.is_null() {
+                rcutils_reset_error();
+                return None;
+            }
+            Some(string_from_ptr((*transition).label))
+        }
+    }
+
+    /// Takes and answers every pending request of one of the [`COM_SERVICES`]
+    ///
+    /// `respond` fills in the response to a request and is called without the state machine
+    /// locked, so that it may trigger transitions.
+    fn serve<Request, Response>(
+        &self,
+        service: usize,
+        request_init: unsafe extern "C" fn(*mut Request) -> bool,
+        request_fini: unsafe extern "C" fn(*mut Request),
+        response_init: unsafe extern "C" fn(*mut Response) -> bool,
+        response_fini: unsafe extern "C" fn(*mut Response),
+        mut respond: impl FnMut(&Request, &mut Response),
+    ) -> Result<(), RclrsError> {
+        loop {
+            let mut request_header: rmw_request_id_t = unsafe { core::mem::zeroed() };
+            let mut request: Request = unsafe { core::mem::zeroed() };
+            unsafe { request_init(&mut request as *mut _) };
+            let take_result = {
+                let handle = &*self.handle.lock();
+                unsafe {
+                    rcl_take_request(
+                        COM_SERVICES[service](&handle.com_interface) as *const _,
+                        &mut request_header as *mut _,
+                        &mut request as *mut _ as *mut _,
+                    )
+                }
+            };
+            if let Err(error) = to_rcl_result(take_result) {
+                unsafe { request_fini(&mut request as *mut _) };
+                return match error {
+                    RclReturnCode::ServiceError(ServiceErrorCode::ServiceTakeFailed) => Ok(()),
+                    error => Err(RclrsError::from_rcl(error)
+                        .context("failed to take a request on a lifecycle service")),
+                };
+            }
+
+            let mut response: Response = unsafe { core::mem::zeroed() };
+            unsafe { response_init(&mut response as *mut _) };
+            respond(&request, &mut response);
+            unsafe { request_fini(&mut request as *mut _) };
+
+            let send_result = {
+                let handle = &*self.handle.lock();
+                unsafe {
+                    rcl_send_response(
+                        COM_SERVICES[service](&handle.com_interface) as *const _,
+                        &mut request_header as *mut _,
+                        &mut response as *mut _ as *mut _,
+                    )
+                }
+            };
+            unsafe { response_fini(&mut response as *mut _) };
+            send_result
+                .ok()
+                .with_context(|| "failed to answer a request on a lifecycle service".into())?;
+        }
+    }
+
+    fn respond_change_state(
+        &self,
+        request: &lifecycle_msgs__srv__ChangeState_Request,
+        response: &mut lifecycle_msgs__srv__ChangeState_Response,
+    ) {
+        let label = if request.transition.label.size > 0 {
+            Some(string_from_ptr(request.transition.label.data))
+        } else {
+            self.transition_label_by_id(request.transition.id)
+        };
+        response.success = match label {
+            Some(label) => matches!(
+                self.trigger_transition(&label),
+                Ok((_, CallbackReturn::Success))
+            ),
+            None => false,
+        };
+    }
+
+    fn respond_get_state(&self, response: &mut lifecycle_msgs__srv__GetState_Response) {
+        let handle = &*self.handle.lock();
+        unsafe { set_state_msg(&mut response.current_state, handle.current_state) };
+    }
+
+    fn respond_get_available_states(
+        &self,
+        response: &mut lifecycle_msgs__srv__GetAvailableStates_Response,
+    ) {
+        let handle = &*self.handle.lock();
+        let map = &handle.transition_map;
+        unsafe {
+            let states = slice_from_raw(map.states, map.states_size);
+            let sequence = &mut response.available_states;
+            if !lifecycle_msgs__msg__State__Sequence__init(sequence as *mut _, states.len()) {
+                return;
+            }
+            for (msg, state) in slice_from_raw_mut(sequence.data, sequence.size)
+                .iter_mut()
+                .zip(states)
+            {
+                set_state_msg(msg, state as *const _);
+            }
+        }
+    }
+
+    /// Answers both `~/get_available_transitions`, with the transitions valid from the current
+    /// state, and `~/get_transition_graph`, with every transition
+    fn respond_get_transitions(
+        &self,
+        response: &mut lifecycle_msgs__srv__GetAvailableTransitions_Response,
+        all_transitions: bool,
+    ) {
+        let handle = &*self.handle.lock();
+        unsafe {
+            let transitions = if all_transitions {
+                let map = &handle.transition_map;
+                slice_from_raw(map.transitions, map.transitions_size)
+            } else if handle.current_state.is_null() {
+                &[]
+            } else {
+                let state = &*handle.current_state;
+                slice_from_raw(state.valid_transitions, state.valid_transition_size)
+            };
+            let sequence = &mut response.available_transitions;
+            if !lifecycle_msgs__msg__TransitionDescription__Sequence__init(
+                sequence as *mut _,
+                transitions.len(),
+            ) {
+                return;
+            }
+            for (msg, transition) in slice_from_raw_mut(sequence.data, sequence.size)
+                .iter_mut()
+                .zip(transitions)
+            {
+                msg.transition.id = transition.id as u8;
+                rosidl_runtime_c__String__assign(
+                    &mut msg.transition.label as *mut _,
+                    transition.label,
+                );
+                set_state_msg(&mut msg.start_state, transition.start);
+                set_state_msg(&mut msg.goal_state, transition.goal);
+            }
+        }
+    }
+}
+
+// Indices into `COM_SERVICES` and `LifecycleStateMachine::service_indices`
+const CHANGE_STATE: usize = 0;
+const GET_STATE: usize = 1;
+const GET_AVAILABLE_STATES: usize = 2;
+const GET_AVAILABLE_TRANSITIONS: usize = 3;
+const GET_TRANSITION_GRAPH: usize = 4;
+
+/// The services created by `enable_com_interface`
+const COM_SERVICES: [fn(&rcl_lifecycle_com_interface_t) -> &rcl_service_t; 5] = [
+    |com_interface| &com_interface.srv_change_state,
+    |com_interface| &com_interface.srv_get_state,
+    |com_interface| &com_interface.srv_get_available_states,
+    |com_interface| &com_interface.srv_get_available_transitions,
+    |com_interface| &com_interface.srv_get_transition_graph,
+];
+
+impl Waitable for LifecycleStateMachine {
+    fn count(&self) -> WaitableCount {
+        WaitableCount {
+            services: COM_SERVICES.len(),
+            ..WaitableCount::default()
+        }
+    }
+
+    fn add_to_wait_set(&self, wait_set: &mut WaitSet) -> Result<(), RclrsError> {
+        let handle = &*self.handle.lock();
+        let service_indices = &mut *self.service_indices.lock();
+        for (index, service) in service_indices.iter_mut().zip(COM_SERVICES) {
+            *index = wait_set.add_service(service(&handle.com_interface))?;
+        }
+        Ok(())
+    }
+
+    fn execute(&self, wait_set: &WaitSet) -> Result<(), RclrsError> {
+        let service_indices = *self.service_indices.lock();
+        let is_ready = |service: usize| wait_set.is_service_ready(service_indices[service]);
+
+        if is_ready(CHANGE_STATE) {
+            self.serve(
+                CHANGE_STATE,
+                lifecycle_msgs__srv__ChangeState_Request__init,
+                lifecycle_msgs__srv__ChangeState_Request__fini,
+                lifecycle_msgs__srv__ChangeState_Response__init,
+                lifecycle_msgs__srv__ChangeState_Response__fini,
+                |request, response| self.respond_change_state(request, response),
+            )?;
+        }
+        if is_ready(GET_STATE) {
+            self.serve(
+                GET_STATE,
+                lifecycle_msgs__srv__GetState_Request__init,
+                lifecycle_msgs__srv__GetState_Request__fini,
+                lifecycle_msgs__srv__GetState_Response__init,
+                lifecycle_msgs__srv__GetState_Response__fini,
+                |_, response| self.respond_get_state(response),
+            )?;
+        }
+        if is_ready(GET_AVAILABLE_STATES) {
+            self.serve(
+                GET_AVAILABLE_STATES,
+                lifecycle_msgs__srv__GetAvailableStates_Request__init,
+                lifecycle_msgs__srv__GetAvailableStates_Request__fini,
+                lifecycle_msgs__srv__GetAvailableStates_Response__init,
+                lifecycle_msgs__srv__GetAvailableStates_Response__fini,
+                |_, response| self.respond_get_available_states(response),
+            )?;
+        }
+        for (service, all_transitions) in [
+            (GET_AVAILABLE_TRANSITIONS, false),
+            (GET_TRANSITION_GRAPH, true),
+        ] {
+            if is_ready(service) {
+                self.serve(
+                    service,
+                    lifecycle_msgs__srv__GetAvailableTransitions_Request__init,
+                    lifecycle_msgs__srv__GetAvailableTransitions_Request__fini,
+                    lifecycle_msgs__srv__GetAvailableTransitions_Response__init,
+                    lifecycle_msgs__srv__GetAvailableTransitions_Response__fini,
+                    |_, response| self.respond_get_transitions(response, all_transitions),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LifecycleStateMachine {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        let node_handle = &mut *self.node_handle.lock();
+        unsafe {
+            #[cfg(ros_distro = "foxy")]
+            {
+                let allocator = rcutils_get_default_allocator();
+                rcl_lifecycle_state_machine_fini(
+                    handle as *mut _,
+                    node_handle as *mut _,
+                    &allocator as *const _,
+                )
+                .log_on_error("failed to finalize a lifecycle state machine");
+            }
+
+            #[cfg(not(ros_distro = "foxy"))]
+            rcl_lifecycle_state_machine_fini(handle as *mut _, node_handle as *mut _)
+                .log_on_error("failed to finalize a lifecycle state machine");
+        }
+    }
+}
+
+unsafe fn state_from_ptr(state: *const rcl_lifecycle_state_t) -> LifecycleState {
+    if state.is_null() {
+        return LifecycleState {
+            id: state_id::UNKNOWN,
+            label: String::from("unknown"),
+        };
+    }
+    LifecycleState {
+        id: (*state).id as u8,
+        label: string_from_ptr((*state).label),
+    }
+}
+
+/// Copies an rcl state into a `lifecycle_msgs/State`, leaving it as is if there's no state
+unsafe fn set_state_msg(msg: &mut lifecycle_msgs__msg__State, state: *const rcl_lifecycle_state_t) {
+    if state.is_null() {
+        return;
+    }
+    msg.id = (*state).id as u8;
+    rosidl_runtime_c__String__assign(&mut msg.label as *mut _, (*state).label);
+}
+
+/// The array of `len` elements at `data`, which may be null if the array is empty
+unsafe fn slice_from_raw<'a, T>(data: *const T, len: cty::c_uint) -> &'a [T] {
+    if data.is_null() {
+        return &[];
+    }
+    core::slice::from_raw_parts(data, len as usize)
+}
+
+unsafe fn slice_from_raw_mut<'a, T>(data: *mut T, len: usize) -> &'a mut [T] {
+    if data.is_null() {
+        return &mut [];
+    }
+    core::slice::from_raw_parts_mut(data, len)
+}
@@ -0,0 +1,89 @@
+use crate::error::RclrsError;
+use crate::{Context, Node, NodeOptions};
+use alloc::string::String;
+
+/// Builder for a [`Node`]
+///
+/// ```ignore
+/// let node = rclrs::Node::builder(&context, "my_node")
+///     .namespace("/robot")
+///     .arguments(["--ros-args", "-r", "chatter:=talk"])
+///     .parameter_override("rate", "10")
+///     .build()?;
+/// ```
+pub struct NodeBuilder<'ctxt> {
+    context: &'ctxt Context,
+    name: String,
+    namespace: String,
+    options: NodeOptions,
+}
+
+impl<'ctxt> NodeBuilder<'ctxt> {
+    pub fn new(context: &'ctxt Context, name: &str) -> Self {
+        Self {
+            context,
+            name: String::from(name),
+            namespace: String::new(),
+            options: NodeOptions::default(),
+        }
+    }
+
+    /// Sets the namespace, which is relative to `/` unless it starts with `/`
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = String::from(namespace);
+        self
+    }
+
+    /// See [`NodeOptions::enable_rosout`]
+    pub fn enable_rosout(mut self, enable_rosout: bool) -> Self {
+        self.options.enable_rosout = enable_rosout;
+        self
+    }
+
+    /// See [`NodeOptions::use_global_arguments`]
+    pub fn use_global_arguments(mut self, use_global_arguments: bool) -> Self {
+        self.options.use_global_arguments = use_global_arguments;
+        self
+    }
+
+    /// See [`NodeOptions::arguments`]
+    pub fn arguments<I>(mut self, arguments: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.options.arguments = arguments.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds a parameter override, see [`NodeOptions::parameter_overrides`]
+    pub fn parameter_override(mut self, name: &str, value: &str) -> Self {
+        self.options
+            .parameter_overrides
+            .push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// See [`NodeOptions::use_intra_process_comms`]
+    pub fn use_intra_process_comms(mut self, use_intra_process_comms: bool) -> Self {
+        self.options.use_intra_process_comms = use_intra_process_comms;
+        self
+    }
+
+    /// Replaces all options at once
+    pub fn options(mut self, options: NodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Creates the node
+    ///
+    /// # Errors
+    /// - [`NodeErrorCode::NodeInvalidName`](rclrs_common::error::NodeErrorCode::NodeInvalidName)
+    ///   or [`NodeErrorCode::NodeInvalidNamespace`](rclrs_common::error::NodeErrorCode::NodeInvalidNamespace)
+    ///   if the name or namespace is not valid, e.g. contains a NUL byte
+    /// - [`RclReturnCode::ParsingError`] if the arguments or parameter overrides are invalid
+    pub fn build(self) -> Result<Node, RclrsError> {
+        Node::new_with_options(&self.name, &self.namespace, self.context, self.options)
+    }
+}
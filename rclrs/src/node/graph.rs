@@ -0,0 +1,168 @@
+use crate::error::{RclrsError, ToResult};
+use crate::qos::QoSProfile;
+use crate::rcl_bindings::*;
+use crate::{Node, NodeHandle};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use cstr_core::{c_char, CStr, CString};
+use rclrs_common::error::{NodeErrorCode, RclReturnCode};
+
+/// Information about a single publisher or subscription on a topic, as reported by the ROS graph
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopicEndpointInfo {
+    pub node_name: String,
+    pub node_namespace: String,
+    pub topic_type: String,
+    pub endpoint_gid: Vec<u8>,
+    pub qos_profile: QoSProfile,
+}
+
+impl From<&rmw_topic_endpoint_info_t> for TopicEndpointInfo {
+    fn from(info: &rmw_topic_endpoint_info_t) -> Self {
+        Self {
+            node_name: string_from_ptr(info.node_name),
+            node_namespace: string_from_ptr(info.node_namespace),
+            topic_type: string_from_ptr(info.topic_type),
+            endpoint_gid: info.endpoint_gid.to_vec(),
+            qos_profile: unsafe { core::ptr::read(&info.qos_profile) }.into(),
+        }
+    }
+}
+
+/// Copies a string owned by `rcl` or `rmw`, empty if it is null
+pub(crate) fn string_from_ptr(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr).to_string_lossy().into_owned() }
+}
+
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+#[cfg(feature = "std")]
+use parking_lot::Mutex;
+
+/// Subscription-like handle that is notified whenever the ROS graph changes
+///
+/// The callback is invoked from [`spin_once`](crate::spin_once) when nodes, topics or services
+/// appear or disappear. It does not say what changed; use the graph queries on [`Node`]
+/// to find out.
+///
+/// `GraphEvents` is `Send` and `Sync`, and so the callback has to be `Send`.
+pub struct GraphEvents {
+    node_handle: Arc<NodeHandle>,
+    pub callback: Mutex<Box<dyn FnMut() + Send + 'static>>,
+}
+
+impl GraphEvents {
+    /// Returns the node's graph guard condition, which is valid for as long as the node
+    pub(crate) fn guard_condition(&self) -> *const rcl_guard_condition_t {
+        let node_handle = &*self.node_handle.lock();
+        unsafe { rcl_node_get_graph_guard_condition(node_handle as *const _) }
+    }
+
+    pub(crate) fn callback_fn(&self) {
+        (&mut *self.callback.lock())();
+    }
+}
+
+type EndpointInfoGetter = unsafe extern "C" fn(
+    *const rcl_node_t,
+    *mut rcutils_allocator_t,
+    *const c_char,
+    bool,
+    *mut rmw_topic_endpoint_info_array_t,
+) -> rcl_ret_t;
+
+impl Node {
+    /// Registers a callback that fires whenever the ROS graph changes
+    ///
+    /// Like subscriptions, the callback is owned by the node and fires until it is removed
+    /// with [`remove_graph_events`](Self::remove_graph_events).
+    pub fn graph_events<F>(&self, callback: F) -> Result<Arc<GraphEvents>, RclrsError>
+    where
+        F: FnMut() + Send + Sized + 'static,
+    {
+        let graph_events = Arc::new(GraphEvents {
+            node_handle: self.handle.clone(),
+            callback: Mutex::new(Box::new(callback)),
+        });
+        if graph_events.guard_condition().is_null() {
+            return Err(NodeErrorCode::NodeInvalid.into());
+        }
+        self.graph_events.lock().push(graph_events.clone());
+        Ok(graph_events)
+    }
+
+    /// Removes a graph event callback from the node
+    ///
+    /// Returns `false` if it doesn't belong to this node or was already removed.
+    pub fn remove_graph_events(&self, graph_events: &Arc<GraphEvents>) -> bool {
+        let entities = &mut *self.graph_events.lock();
+        let len = entities.len();
+        entities.retain(|entity| !Arc::ptr_eq(entity, graph_events));
+        entities.len() != len
+    }
+
+    /// Returns the publishers on `topic`, including their node and QoS settings
+    pub fn get_publishers_info_by_topic(
+        &self,
+        topic: &str,
+    ) -> Result<Vec<TopicEndpointInfo>, RclrsError> {
+        self.get_endpoint_info_by_topic(topic, rcl_get_publishers_info_by_topic)
+    }
+
+    /// Returns the subscriptions on `topic`, including their node and QoS settings
+    pub fn get_subscriptions_info_by_topic(
+        &self,
+        topic: &str,
+    ) -> Result<Vec<TopicEndpointInfo>, RclrsError> {
+        self.get_endpoint_info_by_topic(topic, rcl_get_subscriptions_info_by_topic)
+    }
+
+    fn get_endpoint_info_by_topic(
+        &self,
+        topic: &str,
+        getter: EndpointInfoGetter,
+    ) -> Result<Vec<TopicEndpointInfo>, RclrsError> {
+        let topic_c_string = CString::new(topic).map_err(|_| {
+            RclrsError::from(RclReturnCode::InvalidArgument)
+                .context(format!("topic name {:?} contains a NUL byte", topic))
+        })?;
+        let node_handle = &*self.handle.lock();
+
+        let mut allocator = unsafe { rcutils_get_default_allocator() };
+        let mut info_array = unsafe { rmw_get_zero_initialized_topic_endpoint_info_array() };
+
+        unsafe {
+            getter(
+                node_handle as *const _,
+                &mut allocator as *mut _,
+                topic_c_string.as_ptr(),
+                false,
+                &mut info_array as *mut _,
+            )
+            .ok()?;
+        }
+
+        let endpoints = if info_array.info_array.is_null() {
+            Vec::new()
+        } else {
+            unsafe { core::slice::from_raw_parts(info_array.info_array, info_array.size) }
+                .iter()
+                .map(TopicEndpointInfo::from)
+                .collect()
+        };
+
+        unsafe {
+            rmw_topic_endpoint_info_array_fini(&mut info_array as *mut _, &mut allocator as *mut _)
+                .ok()?;
+        }
+
+        Ok(endpoints)
+    }
+}
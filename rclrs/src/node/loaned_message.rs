@@ -0,0 +1,138 @@
+use crate::error::{RclrsError, ToResult};
+use crate::rcl_bindings::*;
+use crate::Publisher;
+use alloc::sync::Arc;
+use core::ops::{Deref, DerefMut};
+use rclrs_common::traits::MessageDefinition;
+
+/// A message to be published through memory loaned from the middleware
+///
+/// Shared-memory middlewares, e.g. iceoryx, can hand out the memory a message is published
+/// from, so that subscribers in other processes read it without a copy. The message is
+/// filled in right there, as the `#[repr(C)]` [`MessageDefinition::RmwMsg`] that the
+/// middleware publishes, which `LoanedMessage` dereferences to. If the middleware can't loan
+/// messages for this publisher, e.g. because the type is not of fixed size, the message is
+/// kept in the `LoanedMessage` itself and published normally instead.
+///
+/// See [`Publisher::borrow_loaned_message`]. A loaned message that is dropped without being
+/// published is returned to the middleware.
+pub struct LoanedMessage<'a, T>
+where
+    T: MessageDefinition,
+{
+    message: MessageStorage<T>,
+    publisher: &'a Publisher<T>,
+}
+
+enum MessageStorage<T>
+where
+    T: MessageDefinition,
+{
+    /// Initialized message in memory loaned from the middleware, null once published
+    Loaned(*mut T::RmwMsg),
+    /// The middleware doesn't loan messages for the publisher
+    Owned(T::RmwMsg),
+}
+
+impl<'a, T> LoanedMessage<'a, T>
+where
+    T: MessageDefinition,
+{
+    pub(crate) fn new(publisher: &'a Publisher<T>) -> Result<Self, RclrsError> {
+        let handle = &*publisher.handle.lock();
+        let message = if unsafe { rcl_publisher_can_loan_messages(handle as *const _) } {
+            let mut loan: *mut cty::c_void = core::ptr::null_mut();
+            unsafe {
+                rcl_borrow_loaned_message(
+                    handle as *const _,
+                    T::type_support() as *const rosidl_message_type_support_t,
+                    &mut loan as *mut _,
+                )
+                .ok()?;
+            }
+            // The loan is uninitialized memory for a message of the publisher's type
+            let loan = loan as *mut T::RmwMsg;
+            unsafe { core::ptr::write(loan, T::RmwMsg::default()) };
+            MessageStorage::Loaned(loan)
+        } else {
+            MessageStorage::Owned(T::RmwMsg::default())
+        };
+        Ok(Self { message, publisher })
+    }
+
+    /// Whether the message will be published through loaned memory
+    pub fn is_loaned(&self) -> bool {
+        matches!(self.message, MessageStorage::Loaned(_))
+    }
+
+    /// Publishes the message, through the loaned memory if there is any
+    ///
+    /// Intra-process subscriptions receive a copy of the message.
+    pub fn publish(mut self) -> Result<(), RclrsError> {
+        let publisher = self.publisher;
+        if !publisher.publish_intra_process(|| Arc::new(T::from_rmw(&self)))? {
+            return Ok(());
+        }
+        match &mut self.message {
+            MessageStorage::Owned(message) => publisher.publish_rmw(message),
+            MessageStorage::Loaned(loan) => {
+                // The loan belongs to the middleware from here on, even if publishing fails
+                let loan = core::mem::replace(loan, core::ptr::null_mut());
+                let handle = &*publisher.handle.lock();
+                unsafe {
+                    rcl_publish_loaned_message(
+                        handle as *const _,
+                        loan as *mut _,
+                        core::ptr::null_mut(),
+                    )
+                    .ok()
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> Deref for LoanedMessage<'a, T>
+where
+    T: MessageDefinition,
+{
+    type Target = T::RmwMsg;
+
+    fn deref(&self) -> &T::RmwMsg {
+        match &self.message {
+            MessageStorage::Loaned(loan) => unsafe { &**loan },
+            MessageStorage::Owned(message) => message,
+        }
+    }
+}
+
+impl<'a, T> DerefMut for LoanedMessage<'a, T>
+where
+    T: MessageDefinition,
+{
+    fn deref_mut(&mut self) -> &mut T::RmwMsg {
+        match &mut self.message {
+            MessageStorage::Loaned(loan) => unsafe { &mut **loan },
+            MessageStorage::Owned(message) => message,
+        }
+    }
+}
+
+impl<'a, T> Drop for LoanedMessage<'a, T>
+where
+    T: MessageDefinition,
+{
+    fn drop(&mut self) {
+        if let MessageStorage::Loaned(loan) = self.message {
+            if loan.is_null() {
+                return;
+            }
+            let handle = &*self.publisher.handle.lock();
+            unsafe {
+                core::ptr::drop_in_place(loan);
+                rcl_return_loaned_message_from_publisher(handle as *const _, loan as *mut _)
+                    .log_on_error("failed to return a loaned message to the middleware");
+            }
+        }
+    }
+}
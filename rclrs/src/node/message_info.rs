@@ -0,0 +1,45 @@
+use crate::rcl_bindings::*;
+
+/// Globally unique ID of a publisher, as in `rmw_gid_t`
+pub type Gid = [u8; RMW_GID_STORAGE_SIZE as usize];
+
+/// Metadata of a received message, as reported by the middleware
+///
+/// Timestamps are in nanoseconds since the epoch, and are zero if the middleware does not
+/// support them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageInfo {
+    /// When the message was published
+    pub source_timestamp: i64,
+    /// When the message was received
+    pub received_timestamp: i64,
+    /// Sequence number of the message at the publisher, not available before Humble
+    pub publication_sequence_number: Option<u64>,
+    /// Sequence number of the message at the subscription, not available before Humble
+    pub reception_sequence_number: Option<u64>,
+    /// ID of the publisher that sent the message, see [`Publisher::gid`](crate::Publisher::gid)
+    pub publisher_gid: Gid,
+    /// Whether the message was delivered within the process, without the middleware
+    pub from_intra_process: bool,
+}
+
+impl From<&rmw_message_info_t> for MessageInfo {
+    fn from(info: &rmw_message_info_t) -> Self {
+        #[cfg(any(ros_distro = "foxy", ros_distro = "galactic"))]
+        let (publication_sequence_number, reception_sequence_number) = (None, None);
+        #[cfg(not(any(ros_distro = "foxy", ros_distro = "galactic")))]
+        let (publication_sequence_number, reception_sequence_number) = (
+            Some(info.publication_sequence_number),
+            Some(info.reception_sequence_number),
+        );
+
+        Self {
+            source_timestamp: info.source_timestamp,
+            received_timestamp: info.received_timestamp,
+            publication_sequence_number,
+            reception_sequence_number,
+            publisher_gid: info.publisher_gid.data,
+            from_intra_process: info.from_intra_process,
+        }
+    }
+}
@@ -1,16 +1,33 @@
+use alloc::format;
 use alloc::{
     sync::{Arc, Weak},
     vec::Vec,
 };
 
-use crate::error::ToResult;
+use crate::arguments::{parse_arguments, take_allocated_string};
+use crate::error::{ErrorContext, RclrsError, ToResult};
+use crate::intra_process::IntraProcessSubscription;
 use crate::qos::QoSProfile;
 use crate::rcl_bindings::*;
+use crate::wait::Waitable;
 
 use crate::{Context, ContextHandle};
-use cstr_core::CString;
-use rclrs_common::error::RclReturnCode;
+use alloc::string::String;
+use cstr_core::{c_char, CString};
+use libc::uintptr_t;
+use rclrs_common::error::{NodeErrorCode, RclErrorCode, RclReturnCode};
 
+pub mod builder;
+pub use self::builder::*;
+pub mod graph;
+use self::graph::string_from_ptr;
+pub use self::graph::*;
+pub mod loaned_message;
+pub use self::loaned_message::*;
+pub mod message_info;
+pub use self::message_info::*;
+pub mod options;
+pub use self::options::*;
 pub mod publisher;
 pub use self::publisher::*;
 pub mod subscription;
@@ -22,99 +39,386 @@ use spin::{Mutex, MutexGuard};
 #[cfg(feature = "std")]
 use parking_lot::{Mutex, MutexGuard};
 
-pub struct NodeHandle(Mutex<rcl_node_t>);
+/// Owns the `rcl` node
+///
+/// Publishers, subscriptions and other entities hold on to it, and it holds on to the
+/// context, so that everything is finalized in the order `rcl` requires, no matter in
+/// which order the Rust values are dropped.
+pub struct NodeHandle {
+    handle: Mutex<rcl_node_t>,
+    // The node has to be finalized before its context
+    _context_handle: Arc<ContextHandle>,
+}
+
+// SAFETY: `rcl` nodes may be used from any thread as long as calls on the same node are
+// not concurrent, which the mutex ensures. The raw pointers inside `rcl_node_t` only
+// point to memory owned by the node itself or by its context, which outlives it.
+unsafe impl Send for NodeHandle {}
+unsafe impl Sync for NodeHandle {}
 
 impl NodeHandle {
     pub fn get_mut(&mut self) -> &mut rcl_node_t {
-        self.0.get_mut()
+        self.handle.get_mut()
     }
 
     pub fn lock(&self) -> MutexGuard<rcl_node_t> {
-        self.0.lock()
+        self.handle.lock()
     }
 
     pub fn try_lock(&self) -> Option<MutexGuard<rcl_node_t>> {
-        self.0.try_lock()
+        self.handle.try_lock()
     }
 }
 
 impl Drop for NodeHandle {
     fn drop(&mut self) {
         let handle = &mut *self.get_mut();
-        unsafe { rcl_node_fini(handle as *mut _).unwrap() };
+        unsafe { rcl_node_fini(handle as *mut _).log_on_error("failed to finalize a node") };
     }
 }
 
+/// A ROS node, which owns the subscriptions and other callbacks that are executed when
+/// it is spun
+///
+/// Callbacks are registered in the node's entity tables and stay active until they are
+/// removed, e.g. with [`remove_subscription`](Self::remove_subscription), or the node is
+/// dropped. The handles returned when creating them do not need to be kept alive.
+/// Publishers are not registered, since they have nothing to execute; like all other
+/// entities they keep the underlying `rcl` node alive for as long as they exist.
+///
+/// `Node` is `Send` and `Sync`, and all of its methods take `&self`, so it can be shared
+/// as `Arc<Node>`, e.g. to create publishers or subscriptions from worker threads while
+/// another thread spins it. Entities created while the node is waiting are picked up by
+/// the next call to [`spin_once`](crate::spin_once). Callbacks are owned by the node, so a
+/// callback that captures an `Arc<Node>` keeps the node alive until it is removed.
 pub struct Node {
-    handle: Arc<NodeHandle>,
+    pub(crate) handle: Arc<NodeHandle>,
     pub(crate) context: Arc<ContextHandle>,
-    pub(crate) subscriptions: Vec<Weak<dyn SubscriptionBase>>,
+    pub(crate) subscriptions: Mutex<Vec<Arc<dyn SubscriptionBase>>>,
+    pub(crate) graph_events: Mutex<Vec<Arc<GraphEvents>>>,
+    pub(crate) waitables: Mutex<Vec<Arc<dyn Waitable>>>,
+    options: NodeOptions,
 }
 
 impl Node {
+    /// Creates a node with default options in the root namespace
+    ///
+    /// Use [`Node::builder`] to set a namespace, arguments or other options.
     #[allow(clippy::new_ret_no_self)]
-    pub fn new<'ctxt>(node_name: &str, context: &Context) -> Result<Node, RclReturnCode> {
-        Self::new_with_namespace(node_name, "", context)
+    pub fn new(node_name: &str, context: &Context) -> Result<Node, RclrsError> {
+        Self::builder(context, node_name).build()
+    }
+
+    /// Returns a [`NodeBuilder`] for a node with the given name
+    pub fn builder<'ctxt>(context: &'ctxt Context, node_name: &str) -> NodeBuilder<'ctxt> {
+        NodeBuilder::new(context, node_name)
     }
 
-    pub fn new_with_namespace<'ctxt>(
+    pub(crate) fn new_with_options(
         node_name: &str,
         node_ns: &str,
         context: &Context,
-    ) -> Result<Node, RclReturnCode> {
-        let raw_node_name = CString::new(node_name).unwrap();
-        let raw_node_ns = CString::new(node_ns).unwrap();
+        options: NodeOptions,
+    ) -> Result<Node, RclrsError> {
+        let raw_node_name = CString::new(node_name).map_err(|_| NodeErrorCode::NodeInvalidName)?;
+        let raw_node_ns = CString::new(node_ns).map_err(|_| NodeErrorCode::NodeInvalidNamespace)?;
+        let arguments = options.rcl_arguments();
 
         let mut node_handle = unsafe { rcl_get_zero_initialized_node() };
         let context_handle = &mut *context.handle.lock();
 
         unsafe {
-            let node_options = rcl_node_get_default_options();
-            rcl_node_init(
+            let mut node_options = rcl_node_get_default_options();
+            node_options.use_global_arguments = options.use_global_arguments;
+            node_options.enable_rosout = options.enable_rosout;
+            #[cfg(ros_distro = "foxy")]
+            if let Some(domain_id) = context.handle.domain_id {
+                node_options.domain_id = domain_id;
+            }
+            if !arguments.is_empty() {
+                node_options.arguments = parse_arguments(&arguments)?;
+            }
+            // `rcl_node_init` copies the options, including the parsed arguments
+            let init_result = rcl_node_init(
                 &mut node_handle as *mut _,
                 raw_node_name.as_ptr(),
                 raw_node_ns.as_ptr(),
                 context_handle as *mut _,
                 &node_options as *const _,
-            )
-            .ok()?;
+            );
+            rcl_node_options_fini(&mut node_options as *mut _);
+            init_result.ok().with_context(|| {
+                format!(
+                    "failed to create node '{}' in namespace '{}'",
+                    node_name, node_ns
+                )
+            })?;
         }
 
-        let handle = Arc::new(NodeHandle(Mutex::new(node_handle)));
+        let handle = Arc::new(NodeHandle {
+            handle: Mutex::new(node_handle),
+            _context_handle: context.handle.clone(),
+        });
 
         Ok(Node {
             handle,
             context: context.handle.clone(),
-            subscriptions: alloc::vec![],
+            subscriptions: Mutex::new(Vec::new()),
+            graph_events: Mutex::new(Vec::new()),
+            waitables: Mutex::new(Vec::new()),
+            options,
         })
     }
 
-    // TODO: make publisher's lifetime depend on node's lifetime
+    /// Returns the options the node was created with
+    pub fn options(&self) -> &NodeOptions {
+        &self.options
+    }
+
+    /// Returns the name of the node, after remapping
+    pub fn name(&self) -> String {
+        let node_handle = &*self.handle.lock();
+        string_from_ptr(unsafe { rcl_node_get_name(node_handle as *const _) })
+    }
+
+    /// Returns the namespace of the node, after remapping, e.g. `/` or `/robot`
+    pub fn namespace(&self) -> String {
+        let node_handle = &*self.handle.lock();
+        string_from_ptr(unsafe { rcl_node_get_namespace(node_handle as *const _) })
+    }
+
+    /// Returns the namespace and name of the node, e.g. `/robot/my_node`
+    pub fn fully_qualified_name(&self) -> String {
+        let node_handle = &*self.handle.lock();
+        string_from_ptr(unsafe { rcl_node_get_fully_qualified_name(node_handle as *const _) })
+    }
+
+    /// Resolves a topic name the way publishers and subscriptions of this node do
+    ///
+    /// Relative names are expanded with the node's namespace, `~` with its fully qualified
+    /// name and substitutions like `{node}` are replaced. Unless `only_expand` is set, the
+    /// remap rules are applied afterwards, see [`remap_topic_name`](Self::remap_topic_name).
+    ///
+    /// Invalid names are reported as [`RclErrorCode::TopicNameInvalid`].
+    pub fn resolve_topic_name(
+        &self,
+        topic_name: &str,
+        only_expand: bool,
+    ) -> Result<String, RclrsError> {
+        let topic_name_c_string =
+            CString::new(topic_name).map_err(|_| RclErrorCode::TopicNameInvalid)?;
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        let mut output_name: *mut c_char = core::ptr::null_mut();
+
+        {
+            let node_handle = &*self.handle.lock();
+            with_topic_name_substitutions(|substitutions| unsafe {
+                rcl_expand_topic_name(
+                    topic_name_c_string.as_ptr(),
+                    rcl_node_get_name(node_handle as *const _),
+                    rcl_node_get_namespace(node_handle as *const _),
+                    substitutions as *const _,
+                    rcutils_get_default_allocator(),
+                    &mut output_name as *mut _,
+                )
+                .ok()
+            })?;
+        }
+        let expanded_name = unsafe { take_allocated_string(output_name, &allocator) }
+            .ok_or(RclReturnCode::from(RclErrorCode::TopicNameInvalid))?;
+
+        if only_expand {
+            Ok(expanded_name)
+        } else {
+            self.remap_topic_name(&expanded_name)
+        }
+    }
+
+    /// Applies the topic remap rules of the node's and, unless disabled in [`NodeOptions`],
+    /// the global arguments to a fully qualified topic name, e.g. `/chatter`
+    ///
+    /// Returns the topic name unchanged if no rule applies.
+    pub fn remap_topic_name(&self, topic_name: &str) -> Result<String, RclrsError> {
+        let topic_name_c_string = CString::new(topic_name).map_err(|_| {
+            RclrsError::from(RclReturnCode::InvalidArgument)
+                .context(format!("topic name {:?} contains a NUL byte", topic_name))
+        })?;
+        let node_handle = &*self.handle.lock();
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        let mut output_name: *mut c_char = core::ptr::null_mut();
+
+        unsafe {
+            let node_options = rcl_node_get_options(node_handle as *const _);
+            if node_options.is_null() {
+                return Err(RclrsError::from_rcl(RclReturnCode::Error));
+            }
+            let global_arguments = if (*node_options).use_global_arguments {
+                &(*node_handle.context).global_arguments as *const _
+            } else {
+                core::ptr::null()
+            };
+
+            with_topic_name_substitutions(|substitutions| {
+                rcl_remap_topic_name(
+                    &(*node_options).arguments as *const _,
+                    global_arguments,
+                    topic_name_c_string.as_ptr(),
+                    rcl_node_get_name(node_handle as *const _),
+                    rcl_node_get_namespace(node_handle as *const _),
+                    substitutions as *const _,
+                    rcutils_get_default_allocator(),
+                    &mut output_name as *mut _,
+                )
+                .ok()
+            })?;
+
+            Ok(take_allocated_string(output_name, &allocator)
+                .unwrap_or_else(|| String::from(topic_name)))
+        }
+    }
+
+    /// Creates a publisher, which can be used independently of the node
     pub fn create_publisher<T>(
         &self,
         topic: &str,
         qos: QoSProfile,
-    ) -> Result<Publisher<T>, RclReturnCode>
+    ) -> Result<Publisher<T>, RclrsError>
     where
-        T: rclrs_common::traits::MessageDefinition<T>,
+        T: rclrs_common::traits::MessageDefinition,
     {
         Publisher::<T>::new(self, topic, qos)
     }
 
-    // TODO: make subscription's lifetime depend on node's lifetime
-    pub fn create_subscription<T, F>(
-        &mut self,
+    /// Creates a publisher of messages that are serialized in Rust, see [`SerializedPublisher`]
+    pub fn create_serialized_publisher<T>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<SerializedPublisher<T>, RclrsError>
+    where
+        T: rclrs_common::traits::SerializedMessage,
+    {
+        SerializedPublisher::<T>::new(self, topic, qos)
+    }
+
+    /// Creates a subscription whose callback is executed while the node is spinning
+    ///
+    /// The callback may take the message by reference, by value or as an `Arc`, optionally
+    /// along with its [`MessageInfo`], see [`SubscriptionCallback`].
+    ///
+    /// The node owns the subscription, so it stays active when the returned handle is
+    /// dropped. Use [`remove_subscription`](Self::remove_subscription) to stop it.
+    pub fn create_subscription<T, Args>(
+        &self,
         topic: &str,
         qos: QoSProfile,
-        callback: F,
-    ) -> Result<Arc<Subscription<T>>, RclReturnCode>
+        callback: impl SubscriptionCallback<T, Args>,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
     where
-        T: rclrs_common::traits::MessageDefinition<T> + Default,
-        F: FnMut(&T) + Sized + 'static,
+        T: rclrs_common::traits::MessageDefinition,
     {
         let subscription = Arc::new(Subscription::<T>::new(self, topic, qos, callback)?);
         self.subscriptions
-            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+            .lock()
+            .push(subscription.clone() as Arc<dyn SubscriptionBase>);
+        if subscription.uses_intra_process_comms() {
+            let weak = Arc::downgrade(&subscription) as Weak<dyn IntraProcessSubscription>;
+            self.context.intra_process_manager.add_subscription(
+                subscription.topic_name(),
+                T::type_support() as uintptr_t,
+                qos,
+                weak,
+            );
+            self.add_waitable(subscription.clone());
+        }
+        Ok(subscription)
+    }
+
+    /// Creates a subscription to messages that are deserialized in Rust, see
+    /// [`SerializedSubscription`]
+    ///
+    /// Like [`create_subscription`](Self::create_subscription), the node owns the subscription
+    /// and executes its callback while spinning.
+    pub fn create_serialized_subscription<T, Args>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: impl SubscriptionCallback<T, Args>,
+    ) -> Result<Arc<SerializedSubscription<T>>, RclrsError>
+    where
+        T: rclrs_common::traits::SerializedMessage,
+    {
+        let subscription = Arc::new(SerializedSubscription::<T>::new(
+            self, topic, qos, callback,
+        )?);
+        self.subscriptions
+            .lock()
+            .push(subscription.clone() as Arc<dyn SubscriptionBase>);
         Ok(subscription)
     }
+
+    /// Creates a subscription without a callback, whose messages are taken with
+    /// [`try_recv`](PollingSubscription::try_recv) instead of by spinning the node
+    pub fn create_polling_subscription<T>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<PollingSubscription<T>, RclrsError>
+    where
+        T: rclrs_common::traits::MessageDefinition,
+    {
+        PollingSubscription::<T>::new(self, topic, qos)
+    }
+
+    /// Removes a subscription from the node, so that its callback is no longer executed
+    ///
+    /// Returns `false` if the subscription doesn't belong to this node or was already removed.
+    /// The `rcl` subscription is destroyed once the last handle to it is dropped.
+    pub fn remove_subscription<S>(&self, subscription: &Arc<S>) -> bool
+    where
+        S: SubscriptionBase + 'static,
+    {
+        let ptr = Arc::as_ptr(subscription) as *const ();
+        // The intra-process manager only holds a weak reference
+        self.remove_waitable(ptr);
+        remove_entity(&mut self.subscriptions.lock(), ptr)
+    }
+
+    /// Adds a waitable, e.g. an action server, to the node
+    pub(crate) fn add_waitable(&self, waitable: Arc<dyn Waitable>) {
+        self.waitables.lock().push(waitable);
+    }
+
+    /// Removes a waitable, e.g. an action server, from the node
+    pub(crate) fn remove_waitable(&self, waitable: *const ()) -> bool {
+        remove_entity(&mut self.waitables.lock(), waitable)
+    }
+}
+
+/// Runs `f` with the default topic name substitutions of `rcl`, e.g. `{node}`
+fn with_topic_name_substitutions<F>(f: F) -> Result<(), RclrsError>
+where
+    F: FnOnce(&rcutils_string_map_t) -> Result<(), RclrsError>,
+{
+    let mut substitutions = unsafe { rcutils_get_zero_initialized_string_map() };
+    unsafe {
+        rcutils_string_map_init(
+            &mut substitutions as *mut _,
+            0,
+            rcutils_get_default_allocator(),
+        )
+        .ok()?;
+    }
+    let result = unsafe { rcl_get_default_topic_name_substitutions(&mut substitutions as *mut _) }
+        .ok()
+        .and_then(|()| f(&substitutions));
+    unsafe { rcutils_string_map_fini(&mut substitutions as *mut _) };
+    result
+}
+
+/// Removes the entity at `ptr` from one of the node's entity tables
+fn remove_entity<E: ?Sized>(entities: &mut Vec<Arc<E>>, ptr: *const ()) -> bool {
+    let len = entities.len();
+    entities.retain(|entity| Arc::as_ptr(entity) as *const () != ptr);
+    entities.len() != len
 }
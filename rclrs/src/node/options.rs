@@ -0,0 +1,55 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Options for creating a [`Node`](crate::Node), usually set through a
+/// [`NodeBuilder`](crate::NodeBuilder)
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeOptions {
+    /// Arguments that only apply to this node, e.g. `["--ros-args", "-r", "chatter:=talk"]`
+    ///
+    /// This is how two nodes in one process get different remappings.
+    pub arguments: Vec<String>,
+    /// Whether the arguments the [`Context`](crate::Context) was initialized with apply to
+    /// this node, in addition to its own [`arguments`](Self::arguments)
+    pub use_global_arguments: bool,
+    /// Whether the node logs to `/rosout`
+    pub enable_rosout: bool,
+    /// Initial parameter values as `(name, value)`, with the value in YAML syntax
+    ///
+    /// They are handed to `rcl` as `-p name:=value` rules, which take precedence over the
+    /// parameters from the global arguments.
+    pub parameter_overrides: Vec<(String, String)>,
+    /// Whether publishers and subscriptions of this node exchange messages with those of
+    /// other intra-process nodes in the same context directly, as `Arc<T>`
+    ///
+    /// Messages from nodes without it, and transient local endpoints, still go through the
+    /// middleware.
+    pub use_intra_process_comms: bool,
+}
+
+impl NodeOptions {
+    /// The node's arguments, with the parameter overrides appended as ROS arguments
+    pub(crate) fn rcl_arguments(&self) -> Vec<String> {
+        let mut arguments = self.arguments.clone();
+        if !self.parameter_overrides.is_empty() {
+            arguments.push(String::from("--ros-args"));
+            for (name, value) in &self.parameter_overrides {
+                arguments.push(String::from("-p"));
+                arguments.push(alloc::format!("{}:={}", name, value));
+            }
+        }
+        arguments
+    }
+}
+
+impl Default for NodeOptions {
+    fn default() -> Self {
+        Self {
+            arguments: Vec::new(),
+            use_global_arguments: true,
+            enable_rosout: true,
+            parameter_overrides: Vec::new(),
+            use_intra_process_comms: false,
+        }
+    }
+}
@@ -1,12 +1,18 @@
-use crate::error::ToResult;
+use crate::error::{ErrorContext, RclrsError, ToResult};
+use crate::intra_process::{uses_intra_process, IntraProcessPublisher, SharedMessage};
+use crate::node::graph::string_from_ptr;
 use crate::qos::QoSProfile;
 use crate::rcl_bindings::*;
-use crate::{Node, NodeHandle};
+use crate::{Gid, LoanedMessage, Node, NodeHandle};
+use alloc::format;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::marker::PhantomData;
 use cstr_core::CString;
-use rclrs_common::error::RclReturnCode;
+use libc::uintptr_t;
+use rclrs_common::error::{RclErrorCode, RclReturnCode};
 
 #[cfg(not(feature = "std"))]
 use spin::{Mutex, MutexGuard};
@@ -19,7 +25,52 @@ pub struct PublisherHandle {
     node_handle: Arc<NodeHandle>,
 }
 
+// SAFETY: `rcl` publishers may be used from any thread as long as calls on the same
+// publisher are not concurrent, which the mutex ensures.
+unsafe impl Send for PublisherHandle {}
+unsafe impl Sync for PublisherHandle {}
+
 impl PublisherHandle {
+    fn new(
+        node: &Node,
+        topic: &str,
+        qos: QoSProfile,
+        type_support: *const rosidl_message_type_support_t,
+    ) -> Result<Self, RclrsError> {
+        let mut publisher_handle = unsafe { rcl_get_zero_initialized_publisher() };
+        let topic_c_string = CString::new(topic).map_err(|_| {
+            RclrsError::from(RclErrorCode::TopicNameInvalid)
+                .context(format!("topic name {:?} contains a NUL byte", topic))
+        })?;
+        let node_handle = &mut *node.handle.lock();
+
+        unsafe {
+            let mut publisher_options = rcl_publisher_get_default_options();
+            publisher_options.qos = qos.into();
+
+            rcl_publisher_init(
+                &mut publisher_handle as *mut _,
+                node_handle as *mut _,
+                type_support,
+                topic_c_string.as_ptr(),
+                &publisher_options as *const _,
+            )
+            .ok()
+            .with_context(|| format!("failed to create a publisher on topic '{}'", topic))?;
+        }
+
+        Ok(Self {
+            handle: Mutex::new(publisher_handle),
+            node_handle: node.handle.clone(),
+        })
+    }
+
+    /// Returns the fully qualified topic name, after expansion and remapping
+    pub(crate) fn topic_name(&self) -> String {
+        let handle = &*self.lock();
+        string_from_ptr(unsafe { rcl_publisher_get_topic_name(handle as *const _) })
+    }
+
     fn node_handle(&self) -> &NodeHandle {
         self.node_handle.borrow()
     }
@@ -28,7 +79,7 @@ impl PublisherHandle {
         self.handle.get_mut()
     }
 
-    fn lock(&self) -> MutexGuard<rcl_publisher_t> {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_publisher_t> {
         self.handle.lock()
     }
 
@@ -42,69 +93,257 @@ impl Drop for PublisherHandle {
         let handle = self.handle.get_mut();
         let node_handle = &mut *self.node_handle.lock();
         unsafe {
-            rcl_publisher_fini(handle as *mut _, node_handle as *mut _);
+            rcl_publisher_fini(handle as *mut _, node_handle as *mut _)
+                .log_on_error("failed to finalize a publisher");
         }
     }
 }
 
 /// Main class responsible for publishing data to ROS topics
+///
+/// Publishers are `Send` and `Sync`, so they can be created on and moved to worker threads.
+///
+/// If the node uses intra-process communication, messages are handed to the subscriptions
+/// of the same context directly, and only go through the middleware if there are
+/// subscriptions elsewhere. Transient local publishers always publish through the
+/// middleware, which keeps the last messages for subscriptions that join late.
 pub struct Publisher<T>
 where
-    T: rclrs_common::traits::MessageDefinition<T>,
+    T: rclrs_common::traits::MessageDefinition,
 {
     pub handle: Arc<PublisherHandle>,
+    intra_process: Option<IntraProcessPublisher>,
     message: PhantomData<T>,
 }
 
 impl<T> Publisher<T>
 where
-    T: rclrs_common::traits::MessageDefinition<T>,
+    T: rclrs_common::traits::MessageDefinition,
 {
-    pub fn new(node: &Node, topic: &str, qos: QoSProfile) -> Result<Self, RclReturnCode>
+    pub fn new(node: &Node, topic: &str, qos: QoSProfile) -> Result<Self, RclrsError> {
+        let type_support = T::type_support() as *const rosidl_message_type_support_t;
+        let handle = Arc::new(PublisherHandle::new(node, topic, qos, type_support)?);
+
+        let mut publisher = Self {
+            handle,
+            intra_process: None,
+            message: PhantomData,
+        };
+        if node.options().use_intra_process_comms && uses_intra_process(&qos) {
+            publisher.intra_process = Some(IntraProcessPublisher::new(
+                &node.context,
+                publisher.topic_name(),
+                T::type_support() as uintptr_t,
+                qos,
+                publisher.gid()?,
+            ));
+        }
+        Ok(publisher)
+    }
+
+    /// Returns the fully qualified topic name, after expansion and remapping
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Returns the globally unique ID of the publisher, which subscribers receive in the
+    /// [`MessageInfo`](crate::MessageInfo) of its messages
+    pub fn gid(&self) -> Result<Gid, RclrsError> {
+        let handle = &*self.handle.lock();
+        let mut gid = unsafe { core::mem::zeroed::<rmw_gid_t>() };
+        unsafe {
+            let rmw_handle = rcl_publisher_get_rmw_handle(handle as *const _);
+            if rmw_handle.is_null() {
+                return Err(RclrsError::from_rcl(RclReturnCode::PublisherInvalid));
+            }
+            rmw_get_gid_for_publisher(rmw_handle as *const _, &mut gid as *mut _).ok()?;
+        }
+        Ok(gid.data)
+    }
+
+    /// Whether the middleware can loan messages for this publisher, see [`LoanedMessage`]
+    pub fn can_loan_messages(&self) -> bool {
+        let handle = &*self.handle.lock();
+        unsafe { rcl_publisher_can_loan_messages(handle as *const _) }
+    }
+
+    /// Returns a default message to be filled in place and published through memory loaned
+    /// from the middleware, falling back to a regular publish if it can't loan messages
+    pub fn borrow_loaned_message(&self) -> Result<LoanedMessage<'_, T>, RclrsError> {
+        LoanedMessage::new(self)
+    }
+
+    /// Publishes a message
+    ///
+    /// Intra-process subscriptions receive a copy of the message, which is shared between
+    /// all of them.
+    pub fn publish(&self, message: &T) -> Result<(), RclrsError> {
+        if !self.publish_intra_process(|| Arc::new(message.clone()))? {
+            return Ok(());
+        }
+        self.publish_rmw(&T::into_rmw(message))
+    }
+
+    /// Publishes a message, sharing it with the intra-process subscriptions without a copy
+    ///
+    /// Without intra-process communication, this is the same as [`publish`](Self::publish).
+    pub fn publish_shared(&self, message: impl Into<Arc<T>>) -> Result<(), RclrsError> {
+        let message = message.into();
+        if !self.publish_intra_process(|| message.clone())? {
+            return Ok(());
+        }
+        self.publish_rmw(&T::into_rmw(&message))
+    }
+
+    /// Hands the message to the intra-process subscriptions, if the node uses intra-process
+    /// communication, and returns whether it still has to go through the middleware
+    ///
+    /// `message` is only called if there are intra-process subscriptions.
+    pub(crate) fn publish_intra_process<F>(&self, message: F) -> Result<bool, RclrsError>
     where
-        T: rclrs_common::traits::MessageDefinition<T>,
+        F: FnOnce() -> Arc<T>,
     {
-        let mut publisher_handle = unsafe { rcl_get_zero_initialized_publisher() };
-        let type_support = T::get_type_support() as *const rosidl_message_type_support_t;
-        let topic_c_string = CString::new(topic).unwrap();
-        let node_handle = &mut *node.handle.lock();
+        let intra_process = match &self.intra_process {
+            Some(intra_process) => intra_process,
+            None => return Ok(true),
+        };
+        let subscriptions = intra_process.matching_subscriptions();
+        if !subscriptions.is_empty() {
+            let shared: SharedMessage = message();
+            intra_process.deliver(&subscriptions, shared);
+        }
+        self.has_inter_process_subscriptions(subscriptions.len())
+    }
 
+    /// Whether there are more matched subscriptions than the intra-process ones, which the
+    /// middleware doesn't deliver to
+    fn has_inter_process_subscriptions(
+        &self,
+        intra_process_count: usize,
+    ) -> Result<bool, RclrsError> {
+        let handle = &*self.handle.lock();
+        let mut subscription_count = 0;
         unsafe {
-            let mut publisher_options = rcl_publisher_get_default_options();
-            publisher_options.qos = qos.into();
-
-            rcl_publisher_init(
-                &mut publisher_handle as *mut _,
-                node_handle as *mut _,
-                type_support,
-                topic_c_string.as_ptr(),
-                &publisher_options as *const _,
+            rcl_publisher_get_subscription_count(
+                handle as *const _,
+                &mut subscription_count as *mut _,
             )
             .ok()?;
         }
+        Ok(subscription_count > intra_process_count)
+    }
 
-        let handle = Arc::new(PublisherHandle {
-            handle: Mutex::new(publisher_handle),
-            node_handle: node.handle.clone(),
-        });
+    /// Publishes a message through the middleware only
+    pub(crate) fn publish_rmw(&self, message: &T::RmwMsg) -> Result<(), RclrsError> {
+        unsafe {
+            rcl_publish(
+                &*self.handle.lock() as *const _,
+                message as *const T::RmwMsg as *const _,
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()
+        .with_context(|| format!("failed to publish on topic '{}'", self.topic_name()))
+    }
+}
 
+/// Publisher of messages that are serialized to CDR in Rust, instead of by the type support
+/// of the middleware
+///
+/// The messages always go through the middleware, even to subscriptions in the same
+/// process.
+///
+/// See [`Node::create_serialized_publisher`].
+pub struct SerializedPublisher<T>
+where
+    T: rclrs_common::traits::SerializedMessage,
+{
+    pub handle: Arc<PublisherHandle>,
+    message: PhantomData<T>,
+}
+
+impl<T> SerializedPublisher<T>
+where
+    T: rclrs_common::traits::SerializedMessage,
+{
+    pub fn new(node: &Node, topic: &str, qos: QoSProfile) -> Result<Self, RclrsError> {
+        let type_support = T::type_support() as *const rosidl_message_type_support_t;
         Ok(Self {
-            handle,
+            handle: Arc::new(PublisherHandle::new(node, topic, qos, type_support)?),
             message: PhantomData,
         })
     }
 
-    pub fn publish(&self, message: &T) -> Result<(), RclReturnCode> {
-        let native_message_ptr = message.get_native_message();
-        let handle = &mut *self.handle.lock();
-        let ret = unsafe {
-            rcl_publish(
-                handle as *mut _,
-                native_message_ptr as *mut _,
+    /// Returns the fully qualified topic name, after expansion and remapping
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Serializes and publishes a message
+    pub fn publish(&self, message: &T) -> Result<(), RclrsError> {
+        let mut data = rclrs_common::cdr::to_vec(message);
+        // rcl only reads the buffer, which stays owned by `data`
+        let serialized_message = rcl_serialized_message_t {
+            buffer: data.as_mut_ptr(),
+            buffer_length: data.len(),
+            buffer_capacity: data.capacity(),
+            allocator: unsafe { rcutils_get_default_allocator() },
+        };
+        unsafe {
+            rcl_publish_serialized_message(
+                &*self.handle.lock() as *const _,
+                &serialized_message as *const _,
                 core::ptr::null_mut(),
             )
-        };
-        message.destroy_native_message(native_message_ptr);
-        ret.ok()
+        }
+        .ok()
+        .with_context(|| format!("failed to publish on topic '{}'", self.topic_name()))
+    }
+}
+
+/// Serializes a message with the type support of the middleware, as a [`Publisher`] does
+/// before sending it
+///
+/// The result starts with the same encapsulation header as [`rclrs_common::cdr::to_vec`],
+/// but may have padding at the end.
+pub fn serialize_message<T>(message: &T) -> Result<Vec<u8>, RclrsError>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    let rmw_message = T::into_rmw(message);
+    let mut serialized_message = unsafe { rcutils_get_zero_initialized_uint8_array() };
+    unsafe {
+        // The middleware grows the buffer to the size of the message
+        let allocator = rcutils_get_default_allocator();
+        rcutils_uint8_array_init(&mut serialized_message as *mut _, 0, &allocator as *const _)
+            .ok()?;
+    }
+
+    let result = unsafe {
+        rmw_serialize(
+            &rmw_message as *const T::RmwMsg as *const _,
+            T::type_support() as *const rosidl_message_type_support_t,
+            &mut serialized_message as *mut _,
+        )
     }
+    .ok()
+    .map_err(|error| error.context("failed to serialize a message"))
+    .map(|()| {
+        if serialized_message.buffer_length == 0 {
+            Vec::new()
+        } else {
+            unsafe {
+                core::slice::from_raw_parts(
+                    serialized_message.buffer,
+                    serialized_message.buffer_length,
+                )
+            }
+            .to_vec()
+        }
+    });
+
+    unsafe { rcutils_uint8_array_fini(&mut serialized_message as *mut _) }
+        .log_on_error("failed to finalize a serialized message");
+
+    result
 }
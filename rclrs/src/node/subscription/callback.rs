@@ -0,0 +1,123 @@
+use crate::MessageInfo;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+/// A subscription callback of any of the supported signatures
+///
+/// Callbacks taking `T` own the message and can e.g. move it into a queue, while callbacks
+/// taking `Arc<T>` can share it with other threads, in both cases without a copy.
+pub enum AnySubscriptionCallback<T> {
+    Regular(Box<dyn FnMut(&T) + Send + 'static>),
+    RegularWithMessageInfo(Box<dyn FnMut(&T, MessageInfo) + Send + 'static>),
+    Owned(Box<dyn FnMut(T) + Send + 'static>),
+    OwnedWithMessageInfo(Box<dyn FnMut(T, MessageInfo) + Send + 'static>),
+    Shared(Box<dyn FnMut(Arc<T>) + Send + 'static>),
+    SharedWithMessageInfo(Box<dyn FnMut(Arc<T>, MessageInfo) + Send + 'static>),
+}
+
+impl<T> AnySubscriptionCallback<T> {
+    pub(crate) fn call(&mut self, message: T, info: MessageInfo) {
+        match self {
+            Self::Regular(callback) => callback(&message),
+            Self::RegularWithMessageInfo(callback) => callback(&message, info),
+            Self::Owned(callback) => callback(message),
+            Self::OwnedWithMessageInfo(callback) => callback(message, info),
+            Self::Shared(callback) => callback(Arc::new(message)),
+            Self::SharedWithMessageInfo(callback) => callback(Arc::new(message), info),
+        }
+    }
+}
+
+impl<T> AnySubscriptionCallback<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    /// Calls the callback with a message shared with other intra-process subscriptions
+    ///
+    /// Callbacks taking `T` only get the message without a copy if no other subscription
+    /// holds on to it.
+    pub(crate) fn call_shared(&mut self, message: Arc<T>, info: MessageInfo) {
+        match self {
+            Self::Regular(callback) => callback(&message),
+            Self::RegularWithMessageInfo(callback) => callback(&message, info),
+            Self::Owned(callback) => {
+                callback(Arc::try_unwrap(message).unwrap_or_else(|message| (*message).clone()))
+            }
+            Self::OwnedWithMessageInfo(callback) => callback(
+                Arc::try_unwrap(message).unwrap_or_else(|message| (*message).clone()),
+                info,
+            ),
+            Self::Shared(callback) => callback(message),
+            Self::SharedWithMessageInfo(callback) => callback(message, info),
+        }
+    }
+}
+
+/// Implemented by closures that can be used as subscription callbacks for messages of type `T`
+///
+/// `Args` is the signature of the callback, e.g. `fn(&T)`, `fn(T)`, `fn(Arc<T>)` or any of
+/// these with an additional [`MessageInfo`] argument. It is inferred from the closure, so
+/// closure arguments need type annotations:
+///
+/// ```ignore
+/// node.create_subscription::<Msg, _>("topic", QOS_PROFILE_DEFAULT, |msg: Msg| {
+///     sender.send(msg).unwrap();
+/// })?;
+/// ```
+pub trait SubscriptionCallback<T, Args>: Send + 'static {
+    fn into_callback(self) -> AnySubscriptionCallback<T>;
+}
+
+impl<T, F> SubscriptionCallback<T, fn(&T)> for F
+where
+    F: FnMut(&T) + Send + 'static,
+{
+    fn into_callback(self) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::Regular(Box::new(self))
+    }
+}
+
+impl<T, F> SubscriptionCallback<T, fn(&T, MessageInfo)> for F
+where
+    F: FnMut(&T, MessageInfo) + Send + 'static,
+{
+    fn into_callback(self) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::RegularWithMessageInfo(Box::new(self))
+    }
+}
+
+impl<T, F> SubscriptionCallback<T, fn(T)> for F
+where
+    F: FnMut(T) + Send + 'static,
+{
+    fn into_callback(self) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::Owned(Box::new(self))
+    }
+}
+
+impl<T, F> SubscriptionCallback<T, fn(T, MessageInfo)> for F
+where
+    F: FnMut(T, MessageInfo) + Send + 'static,
+{
+    fn into_callback(self) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::OwnedWithMessageInfo(Box::new(self))
+    }
+}
+
+impl<T, F> SubscriptionCallback<T, fn(Arc<T>)> for F
+where
+    F: FnMut(Arc<T>) + Send + 'static,
+{
+    fn into_callback(self) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::Shared(Box::new(self))
+    }
+}
+
+impl<T, F> SubscriptionCallback<T, fn(Arc<T>, MessageInfo)> for F
+where
+    F: FnMut(Arc<T>, MessageInfo) + Send + 'static,
+{
+    fn into_callback(self) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::SharedWithMessageInfo(Box::new(self))
+    }
+}
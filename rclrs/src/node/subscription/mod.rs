@@ -0,0 +1,340 @@
+use crate::error::{ErrorContext, RclrsError, ToResult};
+use crate::intra_process::{
+    uses_intra_process, IntraProcessBuffer, IntraProcessSubscription, SharedMessage,
+};
+use crate::node::graph::string_from_ptr;
+use crate::qos::QoSProfile;
+use crate::rcl_bindings::*;
+use crate::wait::{WaitSet, Waitable, WaitableCount};
+use crate::{MessageInfo, Node, NodeHandle};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+use cstr_core::CString;
+use libc::uintptr_t;
+use rclrs_common::error::{to_rcl_result, RclErrorCode, RclReturnCode, SubscriberErrorCode};
+
+mod callback;
+pub use self::callback::*;
+mod polling;
+pub use self::polling::*;
+mod serialized;
+pub use self::serialized::*;
+
+#[cfg(not(feature = "std"))]
+use spin::{Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+use parking_lot::{Mutex, MutexGuard};
+
+pub struct SubscriptionHandle {
+    handle: Mutex<rcl_subscription_t>,
+    node_handle: Arc<NodeHandle>,
+}
+
+// SAFETY: `rcl` subscriptions may be used from any thread as long as calls on the same
+// subscription are not concurrent, which the mutex ensures.
+unsafe impl Send for SubscriptionHandle {}
+unsafe impl Sync for SubscriptionHandle {}
+
+impl SubscriptionHandle {
+    pub(crate) fn new(
+        node: &Node,
+        topic: &str,
+        qos: QoSProfile,
+        type_support: *const rosidl_message_type_support_t,
+    ) -> Result<Self, RclrsError> {
+        let mut subscription_handle = unsafe { rcl_get_zero_initialized_subscription() };
+        let topic_c_string = CString::new(topic).map_err(|_| {
+            RclrsError::from(RclErrorCode::TopicNameInvalid)
+                .context(format!("topic name {:?} contains a NUL byte", topic))
+        })?;
+        let node_handle = &mut *node.handle.lock();
+
+        unsafe {
+            let mut subscription_options = rcl_subscription_get_default_options();
+            subscription_options.qos = qos.into();
+            rcl_subscription_init(
+                &mut subscription_handle as *mut _,
+                node_handle as *mut _,
+                type_support,
+                topic_c_string.as_ptr(),
+                &subscription_options as *const _,
+            )
+            .ok()
+            .with_context(|| format!("failed to create a subscription on topic '{}'", topic))?;
+        }
+
+        Ok(Self {
+            handle: Mutex::new(subscription_handle),
+            node_handle: node.handle.clone(),
+        })
+    }
+
+    /// Ask RMW for the data
+    ///
+    /// +-------------+
+    /// | rclrs::take |
+    /// +------+------+
+    ///        |
+    ///        |
+    /// +------v------+
+    /// |  rcl_take   |
+    /// +------+------+
+    ///        |
+    ///        |
+    /// +------v------+
+    /// |  rmw_take   |
+    /// +-------------+
+    ///
+    /// Returns the message and its info if a message was taken, `None` if there was none.
+    pub(crate) fn take<T>(&self) -> Result<Option<(T, MessageInfo)>, RclrsError>
+    where
+        T: rclrs_common::traits::MessageDefinition,
+    {
+        let handle = &mut *self.lock();
+        let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
+        if unsafe { rcl_subscription_can_loan_messages(handle as *const _) } {
+            return Self::take_loaned(handle, message_info);
+        }
+        let mut message = T::RmwMsg::default();
+
+        let result = unsafe {
+            rcl_take(
+                handle as *const _,
+                &mut message as *mut T::RmwMsg as *mut _,
+                &mut message_info as *mut _,
+                core::ptr::null_mut(),
+            )
+        };
+
+        match to_rcl_result(result) {
+            Ok(()) => Ok(Some((
+                T::from_rmw(&message),
+                MessageInfo::from(&message_info),
+            ))),
+            Err(RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed)) => {
+                Ok(None)
+            }
+            Err(error) => Err(RclrsError::from_rcl(error).context("failed to take a message")),
+        }
+    }
+
+    /// Takes a message through memory loaned from the middleware, which is returned as
+    /// soon as the message has been converted
+    fn take_loaned<T>(
+        handle: &rcl_subscription_t,
+        mut message_info: rmw_message_info_t,
+    ) -> Result<Option<(T, MessageInfo)>, RclrsError>
+    where
+        T: rclrs_common::traits::MessageDefinition,
+    {
+        let mut loan: *mut cty::c_void = core::ptr::null_mut();
+        let result = unsafe {
+            rcl_take_loaned_message(
+                handle as *const _,
+                &mut loan as *mut _,
+                &mut message_info as *mut _,
+                core::ptr::null_mut(),
+            )
+        };
+        match to_rcl_result(result) {
+            Ok(()) => {
+                // The loan holds a message of the subscription's type
+                let message = T::from_rmw(unsafe { &*(loan as *const T::RmwMsg) });
+                unsafe { rcl_return_loaned_message_from_subscription(handle as *const _, loan) }
+                    .ok()?;
+                Ok(Some((message, MessageInfo::from(&message_info))))
+            }
+            Err(RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed)) => {
+                Ok(None)
+            }
+            Err(error) => Err(RclrsError::from_rcl(error).context("failed to take a message")),
+        }
+    }
+
+    /// Returns the fully qualified topic name, after expansion and remapping
+    pub(crate) fn topic_name(&self) -> String {
+        let handle = &*self.lock();
+        string_from_ptr(unsafe { rcl_subscription_get_topic_name(handle as *const _) })
+    }
+
+    fn node_handle(&self) -> &NodeHandle {
+        self.node_handle.borrow()
+    }
+
+    pub fn get_mut(&mut self) -> &mut rcl_subscription_t {
+        self.handle.get_mut()
+    }
+
+    pub fn lock(&self) -> MutexGuard<rcl_subscription_t> {
+        self.handle.lock()
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<rcl_subscription_t>> {
+        self.handle.try_lock()
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        let node_handle = &mut *self.node_handle.lock();
+        unsafe {
+            rcl_subscription_fini(handle as *mut _, node_handle as *mut _)
+                .log_on_error("failed to finalize a subscription");
+        }
+    }
+}
+
+/// Trait to be implemented by concrete Subscriber structs
+/// See [`Subscription<T>`] for an example
+pub trait SubscriptionBase: Send + Sync {
+    fn handle(&self) -> &SubscriptionHandle;
+
+    /// Takes a message, if one is available, and passes it to the callback
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+/// Main class responsible for subscribing to topics and receiving data over IPC in ROS
+///
+/// Subscriptions are `Send` and `Sync`. The callback is called from whichever thread spins
+/// the node, so it has to be `Send`.
+///
+/// If the node uses intra-process communication, messages of the intra-process publishers
+/// in the same context arrive through an intra-process queue, and their copies from the
+/// middleware are skipped. Messages of all other publishers, including those of nodes in
+/// the same process without intra-process communication, come from the middleware.
+///
+/// Transient local subscriptions always receive through the middleware, which replays the
+/// last messages to subscriptions that join late.
+pub struct Subscription<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    pub handle: Arc<SubscriptionHandle>,
+    callback: Mutex<AnySubscriptionCallback<T>>,
+    intra_process: Option<IntraProcessBuffer<T>>,
+    message: PhantomData<T>,
+}
+
+impl<T> Subscription<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    /// Creates a subscription, see [`SubscriptionCallback`] for the supported callbacks
+    pub fn new<Args>(
+        node: &Node,
+        topic: &str,
+        qos: QoSProfile,
+        callback: impl SubscriptionCallback<T, Args>,
+    ) -> Result<Self, RclrsError> {
+        let type_support = T::type_support() as *const rosidl_message_type_support_t;
+        let handle = Arc::new(SubscriptionHandle::new(node, topic, qos, type_support)?);
+        let intra_process = if node.options().use_intra_process_comms && uses_intra_process(&qos) {
+            Some(IntraProcessBuffer::new(
+                &node.context,
+                handle.topic_name(),
+                type_support as uintptr_t,
+                qos,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            handle,
+            callback: Mutex::new(callback.into_callback()),
+            intra_process,
+            message: PhantomData,
+        })
+    }
+
+    /// Returns the fully qualified topic name, after expansion and remapping
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    pub(crate) fn uses_intra_process_comms(&self) -> bool {
+        self.intra_process.is_some()
+    }
+
+    /// Takes a message from the middleware without calling the callback, `None` if there
+    /// is none
+    ///
+    /// Messages published intra-process are only delivered to the callback.
+    pub fn take(&self) -> Result<Option<(T, MessageInfo)>, RclrsError> {
+        loop {
+            match self.handle.take()? {
+                Some((_, info)) if self.is_duplicate(&info) => continue,
+                taken => return Ok(taken),
+            }
+        }
+    }
+
+    /// Whether a message from the middleware was already delivered intra-process
+    fn is_duplicate(&self, info: &MessageInfo) -> bool {
+        match &self.intra_process {
+            Some(intra_process) => intra_process.is_duplicate(info),
+            None => false,
+        }
+    }
+}
+
+impl<T> SubscriptionBase for Subscription<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    fn handle(&self) -> &SubscriptionHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        if let Some((message, info)) = self.take()? {
+            self.callback.lock().call(message, info);
+        }
+        Ok(())
+    }
+}
+
+impl<T> IntraProcessSubscription for Subscription<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    fn deliver(&self, message: SharedMessage, info: &MessageInfo) {
+        if let (Some(intra_process), Ok(message)) = (&self.intra_process, message.downcast()) {
+            intra_process.push(message, info.clone());
+        }
+    }
+}
+
+/// The intra-process queue of a subscription is waited on through its guard condition
+impl<T> Waitable for Subscription<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    fn count(&self) -> WaitableCount {
+        WaitableCount {
+            guard_conditions: if self.intra_process.is_some() { 1 } else { 0 },
+            ..Default::default()
+        }
+    }
+
+    fn add_to_wait_set(&self, wait_set: &mut WaitSet) -> Result<(), RclrsError> {
+        if let Some(intra_process) = &self.intra_process {
+            wait_set.add_guard_condition(&*intra_process.guard_condition())?;
+        }
+        Ok(())
+    }
+
+    fn execute(&self, _wait_set: &WaitSet) -> Result<(), RclrsError> {
+        if let Some(intra_process) = &self.intra_process {
+            while let Some((message, info)) = intra_process.pop() {
+                self.callback.lock().call_shared(message, info);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error::RclrsError;
+use crate::qos::QoSProfile;
+use crate::rcl_bindings::*;
+use crate::{MessageInfo, Node, SubscriptionHandle};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// Subscription without a callback, from which messages are taken on demand
+///
+/// It is not registered with its node, so it works without spinning, e.g. in a
+/// fixed-rate control loop. Like other subscriptions, it is `Send` and `Sync`.
+///
+/// See [`Node::create_polling_subscription`].
+pub struct PollingSubscription<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    pub handle: Arc<SubscriptionHandle>,
+    message: PhantomData<T>,
+}
+
+impl<T> PollingSubscription<T>
+where
+    T: rclrs_common::traits::MessageDefinition,
+{
+    pub fn new(node: &Node, topic: &str, qos: QoSProfile) -> Result<Self, RclrsError> {
+        let type_support = T::type_support() as *const rosidl_message_type_support_t;
+        Ok(Self {
+            handle: Arc::new(SubscriptionHandle::new(node, topic, qos, type_support)?),
+            message: PhantomData,
+        })
+    }
+
+    /// Returns the fully qualified topic name, after expansion and remapping
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Takes the oldest message that has been received, `None` if there is none
+    pub fn try_recv(&self) -> Result<Option<T>, RclrsError> {
+        Ok(self
+            .try_recv_with_message_info()?
+            .map(|(message, _)| message))
+    }
+
+    /// Like [`try_recv`](Self::try_recv), but also returns the [`MessageInfo`]
+    pub fn try_recv_with_message_info(&self) -> Result<Option<(T, MessageInfo)>, RclrsError> {
+        self.handle.take()
+    }
+
+    /// Takes up to `max` messages, oldest first, without waiting for more
+    ///
+    /// How many messages are queued depends on the QoS depth; to only read the latest
+    /// sample, use a depth of 1 or take the last element.
+    pub fn take_batch(&self, max: usize) -> Result<Vec<T>, RclrsError> {
+        let mut messages = Vec::new();
+        while messages.len() < max {
+            match self.try_recv()? {
+                Some(message) => messages.push(message),
+                None => break,
+            }
+        }
+        Ok(messages)
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::{log_error, RclrsError, ToResult};
+use crate::qos::QoSProfile;
+use crate::rcl_bindings::*;
+use crate::{
+    AnySubscriptionCallback, MessageInfo, Node, SubscriptionBase, SubscriptionCallback,
+    SubscriptionHandle,
+};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use rclrs_common::cdr::CdrError;
+use rclrs_common::error::{to_rcl_result, RclReturnCode, SubscriberErrorCode};
+
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+#[cfg(feature = "std")]
+use parking_lot::Mutex;
+
+impl SubscriptionHandle {
+    /// Takes a serialized message and deserializes it, see [`take`](Self::take)
+    ///
+    /// Returns `None` if there was no message, and the deserialization error separately from
+    /// the errors of `rcl`, since it's the publisher's fault.
+    pub(crate) fn take_serialized<T>(
+        &self,
+    ) -> Result<Option<(Result<T, CdrError>, MessageInfo)>, RclrsError>
+    where
+        T: rclrs_common::traits::SerializedMessage,
+    {
+        let handle = &*self.lock();
+        let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
+        let mut serialized_message = unsafe { rcutils_get_zero_initialized_uint8_array() };
+        unsafe {
+            // The middleware grows the buffer to the size of the message
+            let allocator = rcutils_get_default_allocator();
+            rcutils_uint8_array_init(&mut serialized_message as *mut _, 0, &allocator as *const _)
+                .ok()?;
+        }
+
+        let result = unsafe {
+            rcl_take_serialized_message(
+                handle as *const _,
+                &mut serialized_message as *mut _,
+                &mut message_info as *mut _,
+                core::ptr::null_mut(),
+            )
+        };
+
+        let result = match to_rcl_result(result) {
+            Ok(()) => {
+                let data = if serialized_message.buffer_length == 0 {
+                    &[][..]
+                } else {
+                    unsafe {
+                        core::slice::from_raw_parts(
+                            serialized_message.buffer,
+                            serialized_message.buffer_length,
+                        )
+                    }
+                };
+                Ok(Some((
+                    rclrs_common::cdr::from_slice(data),
+                    MessageInfo::from(&message_info),
+                )))
+            }
+            Err(RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed)) => {
+                Ok(None)
+            }
+            Err(error) => Err(RclrsError::from_rcl(error).context("failed to take a message")),
+        };
+
+        unsafe { rcutils_uint8_array_fini(&mut serialized_message as *mut _) }
+            .log_on_error("failed to finalize a serialized message");
+
+        result
+    }
+}
+
+/// Subscription to messages that are deserialized from CDR in Rust, instead of by the type
+/// support of the middleware
+///
+/// Like [`SerializedPublisher`](crate::SerializedPublisher), it always receives through
+/// the middleware, also from intra-process publishers. Messages that can't be
+/// deserialized, e.g. because they exceed the bounds of the type, are logged and dropped.
+///
+/// See [`Node::create_serialized_subscription`].
+pub struct SerializedSubscription<T>
+where
+    T: rclrs_common::traits::SerializedMessage,
+{
+    pub handle: Arc<SubscriptionHandle>,
+    callback: Mutex<AnySubscriptionCallback<T>>,
+}
+
+impl<T> SerializedSubscription<T>
+where
+    T: rclrs_common::traits::SerializedMessage,
+{
+    /// Creates a subscription, see [`SubscriptionCallback`] for the supported callbacks
+    pub fn new<Args>(
+        node: &Node,
+        topic: &str,
+        qos: QoSProfile,
+        callback: impl SubscriptionCallback<T, Args>,
+    ) -> Result<Self, RclrsError> {
+        let type_support = T::type_support() as *const rosidl_message_type_support_t;
+        Ok(Self {
+            handle: Arc::new(SubscriptionHandle::new(node, topic, qos, type_support)?),
+            callback: Mutex::new(callback.into_callback()),
+        })
+    }
+
+    /// Returns the fully qualified topic name, after expansion and remapping
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Takes a message without calling the callback, `None` if there is none
+    pub fn take(&self) -> Result<Option<(T, MessageInfo)>, RclrsError> {
+        match self.handle.take_serialized()? {
+            Some((Ok(message), info)) => Ok(Some((message, info))),
+            Some((Err(error), _)) => Err(self.deserialization_error(error)),
+            None => Ok(None),
+        }
+    }
+
+    fn deserialization_error(&self, error: CdrError) -> RclrsError {
+        RclrsError::from(RclReturnCode::Error).context(format!(
+            "failed to deserialize a message on topic '{}': {}",
+            self.topic_name(),
+            error
+        ))
+    }
+}
+
+impl<T> SubscriptionBase for SerializedSubscription<T>
+where
+    T: rclrs_common::traits::SerializedMessage,
+{
+    fn handle(&self) -> &SubscriptionHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        match self.handle.take_serialized()? {
+            Some((Ok(message), info)) => self.callback.lock().call(message, info),
+            // A malformed message from another process shouldn't stop the executor
+            Some((Err(error), _)) => log_error(&self.deserialization_error(error)),
+            None => {}
+        }
+        Ok(())
+    }
+}
@@ -1,36 +1,78 @@
 use crate::rcl_bindings::*;
+use core::time::Duration;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum QoSReliabilityPolicy {
     SystemDefault = 0,
     Reliable = 1,
     BestEffort = 2,
+    /// Only reported back by the middleware, e.g. in graph queries
+    Unknown = 3,
+    /// Let the middleware pick the policy matching the endpoints already on the topic
+    #[cfg(ros_distro = "rolling")]
+    BestAvailable = 4,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum QoSHistoryPolicy {
     SystemDefault = 0,
     KeepLast = 1,
     KeepAll = 2,
+    /// Only reported back by the middleware, e.g. in graph queries
+    Unknown = 3,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum QoSDurabilityPolicy {
     SystemDefault = 0,
     TransientLocal = 1,
     Volatile = 2,
+    /// Only reported back by the middleware, e.g. in graph queries
+    Unknown = 3,
+    /// Let the middleware pick the policy matching the endpoints already on the topic
+    #[cfg(ros_distro = "rolling")]
+    BestAvailable = 4,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QoSLivelinessPolicy {
+    SystemDefault = 0,
+    Automatic = 1,
+    ManualByNode = 2,
+    ManualByTopic = 3,
+    /// Only reported back by the middleware, e.g. in graph queries
+    Unknown = 4,
+    /// Let the middleware pick the policy matching the endpoints already on the topic
+    #[cfg(ros_distro = "rolling")]
+    BestAvailable = 5,
+}
+
+/// A duration of zero means "use the middleware default" for the
+/// `deadline`, `lifespan` and `liveliness_lease_duration` policies.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct QoSProfile {
     pub history: QoSHistoryPolicy,
     pub depth: isize,
     pub reliability: QoSReliabilityPolicy,
     pub durability: QoSDurabilityPolicy,
+    pub deadline: Duration,
+    pub lifespan: Duration,
+    pub liveliness: QoSLivelinessPolicy,
+    pub liveliness_lease_duration: Duration,
     pub avoid_ros_namespace_conventions: bool,
 }
 
+pub const QOS_DURATION_DEFAULT: Duration = Duration::from_secs(0);
+
 pub const QOS_PROFILE_SENSOR_DATA: QoSProfile = QoSProfile {
     history: QoSHistoryPolicy::KeepLast,
     depth: 5,
     reliability: QoSReliabilityPolicy::BestEffort,
     durability: QoSDurabilityPolicy::Volatile,
+    deadline: QOS_DURATION_DEFAULT,
+    lifespan: QOS_DURATION_DEFAULT,
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QOS_DURATION_DEFAULT,
     avoid_ros_namespace_conventions: false,
 };
 
@@ -39,6 +81,10 @@ pub const QOS_PROFILE_PARAMETERS: QoSProfile = QoSProfile {
     depth: 1000,
     reliability: QoSReliabilityPolicy::Reliable,
     durability: QoSDurabilityPolicy::Volatile,
+    deadline: QOS_DURATION_DEFAULT,
+    lifespan: QOS_DURATION_DEFAULT,
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QOS_DURATION_DEFAULT,
     avoid_ros_namespace_conventions: false,
 };
 
@@ -47,6 +93,10 @@ pub const QOS_PROFILE_DEFAULT: QoSProfile = QoSProfile {
     depth: 10,
     reliability: QoSReliabilityPolicy::Reliable,
     durability: QoSDurabilityPolicy::Volatile,
+    deadline: QOS_DURATION_DEFAULT,
+    lifespan: QOS_DURATION_DEFAULT,
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QOS_DURATION_DEFAULT,
     avoid_ros_namespace_conventions: false,
 };
 
@@ -55,6 +105,10 @@ pub const QOS_PROFILE_SERVICES_DEFAULT: QoSProfile = QoSProfile {
     depth: 10,
     reliability: QoSReliabilityPolicy::Reliable,
     durability: QoSDurabilityPolicy::Volatile,
+    deadline: QOS_DURATION_DEFAULT,
+    lifespan: QOS_DURATION_DEFAULT,
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QOS_DURATION_DEFAULT,
     avoid_ros_namespace_conventions: false,
 };
 
@@ -63,6 +117,10 @@ pub const QOS_PROFILE_PARAMETER_EVENTS: QoSProfile = QoSProfile {
     depth: 1000,
     reliability: QoSReliabilityPolicy::Reliable,
     durability: QoSDurabilityPolicy::Volatile,
+    deadline: QOS_DURATION_DEFAULT,
+    lifespan: QOS_DURATION_DEFAULT,
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QOS_DURATION_DEFAULT,
     avoid_ros_namespace_conventions: false,
 };
 
@@ -73,6 +131,10 @@ pub const QOS_PROFILE_SYSTEM_DEFAULT: QoSProfile = QoSProfile {
     depth: SYSTEM_DEFAULT,
     reliability: QoSReliabilityPolicy::SystemDefault,
     durability: QoSDurabilityPolicy::SystemDefault,
+    deadline: QOS_DURATION_DEFAULT,
+    lifespan: QOS_DURATION_DEFAULT,
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QOS_DURATION_DEFAULT,
     avoid_ros_namespace_conventions: false,
 };
 
@@ -84,14 +146,49 @@ impl From<QoSProfile> for rmw_qos_profile_t {
             reliability: qos.reliability.into(),
             durability: qos.durability.into(),
             avoid_ros_namespace_conventions: qos.avoid_ros_namespace_conventions,
-            deadline: rmw_time_t { sec: 0, nsec: 0 },
-            lifespan: rmw_time_t { sec: 0, nsec: 0 },
-            liveliness_lease_duration: rmw_time_t { sec: 0, nsec: 0 },
-            liveliness: rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_SYSTEM_DEFAULT,
+            deadline: qos.deadline.into(),
+            lifespan: qos.lifespan.into(),
+            liveliness_lease_duration: qos.liveliness_lease_duration.into(),
+            liveliness: qos.liveliness.into(),
         }
     }
 }
 
+impl From<rmw_qos_profile_t> for QoSProfile {
+    fn from(qos: rmw_qos_profile_t) -> Self {
+        Self {
+            history: qos.history.into(),
+            depth: qos.depth as isize,
+            reliability: qos.reliability.into(),
+            durability: qos.durability.into(),
+            deadline: qos.deadline.into(),
+            lifespan: qos.lifespan.into(),
+            liveliness: qos.liveliness.into(),
+            liveliness_lease_duration: qos.liveliness_lease_duration.into(),
+            avoid_ros_namespace_conventions: qos.avoid_ros_namespace_conventions,
+        }
+    }
+}
+
+impl From<Duration> for rmw_time_t {
+    fn from(duration: Duration) -> Self {
+        Self {
+            sec: duration.as_secs(),
+            nsec: duration.subsec_nanos() as u64,
+        }
+    }
+}
+
+/// Saturates at [`Duration::MAX`], e.g. for durations that the middleware reports as
+/// infinite
+impl From<rmw_time_t> for Duration {
+    fn from(time: rmw_time_t) -> Self {
+        Duration::from_secs(time.sec)
+            .checked_add(Duration::from_nanos(time.nsec))
+            .unwrap_or(Duration::MAX)
+    }
+}
+
 impl From<QoSHistoryPolicy> for rmw_qos_history_policy_t {
     fn from(policy: QoSHistoryPolicy) -> Self {
         match policy {
@@ -102,6 +199,7 @@ impl From<QoSHistoryPolicy> for rmw_qos_history_policy_t {
                 rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_LAST
             }
             QoSHistoryPolicy::KeepAll => rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_ALL,
+            QoSHistoryPolicy::Unknown => rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_UNKNOWN,
         }
     }
 }
@@ -118,6 +216,13 @@ impl From<QoSReliabilityPolicy> for rmw_qos_reliability_policy_t {
             QoSReliabilityPolicy::BestEffort => {
                 rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_BEST_EFFORT
             }
+            QoSReliabilityPolicy::Unknown => {
+                rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_UNKNOWN
+            }
+            #[cfg(ros_distro = "rolling")]
+            QoSReliabilityPolicy::BestAvailable => {
+                rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_BEST_AVAILABLE
+            }
         }
     }
 }
@@ -134,6 +239,216 @@ impl From<QoSDurabilityPolicy> for rmw_qos_durability_policy_t {
             QoSDurabilityPolicy::Volatile => {
                 rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_VOLATILE
             }
+            QoSDurabilityPolicy::Unknown => {
+                rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_UNKNOWN
+            }
+            #[cfg(ros_distro = "rolling")]
+            QoSDurabilityPolicy::BestAvailable => {
+                rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_BEST_AVAILABLE
+            }
+        }
+    }
+}
+
+impl From<QoSLivelinessPolicy> for rmw_qos_liveliness_policy_t {
+    fn from(policy: QoSLivelinessPolicy) -> Self {
+        match policy {
+            QoSLivelinessPolicy::SystemDefault => {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_SYSTEM_DEFAULT
+            }
+            QoSLivelinessPolicy::Automatic => {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_AUTOMATIC
+            }
+            QoSLivelinessPolicy::ManualByNode => {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_NODE
+            }
+            QoSLivelinessPolicy::ManualByTopic => {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_TOPIC
+            }
+            QoSLivelinessPolicy::Unknown => {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_UNKNOWN
+            }
+            #[cfg(ros_distro = "rolling")]
+            QoSLivelinessPolicy::BestAvailable => {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_BEST_AVAILABLE
+            }
+        }
+    }
+}
+
+impl From<rmw_qos_history_policy_t> for QoSHistoryPolicy {
+    fn from(policy: rmw_qos_history_policy_t) -> Self {
+        match policy {
+            rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_SYSTEM_DEFAULT => {
+                QoSHistoryPolicy::SystemDefault
+            }
+            rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_LAST => {
+                QoSHistoryPolicy::KeepLast
+            }
+            rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_ALL => QoSHistoryPolicy::KeepAll,
+            rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_UNKNOWN => QoSHistoryPolicy::Unknown,
+        }
+    }
+}
+
+impl From<rmw_qos_reliability_policy_t> for QoSReliabilityPolicy {
+    fn from(policy: rmw_qos_reliability_policy_t) -> Self {
+        match policy {
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_SYSTEM_DEFAULT => {
+                QoSReliabilityPolicy::SystemDefault
+            }
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_RELIABLE => {
+                QoSReliabilityPolicy::Reliable
+            }
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_BEST_EFFORT => {
+                QoSReliabilityPolicy::BestEffort
+            }
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_UNKNOWN => {
+                QoSReliabilityPolicy::Unknown
+            }
+            #[cfg(ros_distro = "rolling")]
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_BEST_AVAILABLE => {
+                QoSReliabilityPolicy::BestAvailable
+            }
+        }
+    }
+}
+
+impl From<rmw_qos_durability_policy_t> for QoSDurabilityPolicy {
+    fn from(policy: rmw_qos_durability_policy_t) -> Self {
+        match policy {
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_SYSTEM_DEFAULT => {
+                QoSDurabilityPolicy::SystemDefault
+            }
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_TRANSIENT_LOCAL => {
+                QoSDurabilityPolicy::TransientLocal
+            }
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_VOLATILE => {
+                QoSDurabilityPolicy::Volatile
+            }
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_UNKNOWN => {
+                QoSDurabilityPolicy::Unknown
+            }
+            #[cfg(ros_distro = "rolling")]
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_BEST_AVAILABLE => {
+                QoSDurabilityPolicy::BestAvailable
+            }
+        }
+    }
+}
+
+impl From<rmw_qos_liveliness_policy_t> for QoSLivelinessPolicy {
+    fn from(policy: rmw_qos_liveliness_policy_t) -> Self {
+        match policy {
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_SYSTEM_DEFAULT => {
+                QoSLivelinessPolicy::SystemDefault
+            }
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_AUTOMATIC => {
+                QoSLivelinessPolicy::Automatic
+            }
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_NODE => {
+                QoSLivelinessPolicy::ManualByNode
+            }
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_TOPIC => {
+                QoSLivelinessPolicy::ManualByTopic
+            }
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_UNKNOWN => {
+                QoSLivelinessPolicy::Unknown
+            }
+            #[cfg(ros_distro = "rolling")]
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_BEST_AVAILABLE => {
+                QoSLivelinessPolicy::BestAvailable
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_policy_round_trips() {
+        for policy in [
+            QoSHistoryPolicy::SystemDefault,
+            QoSHistoryPolicy::KeepLast,
+            QoSHistoryPolicy::KeepAll,
+            QoSHistoryPolicy::Unknown,
+        ] {
+            let rmw_policy = rmw_qos_history_policy_t::from(policy);
+            assert_eq!(rmw_policy as u32, policy as u32);
+            assert_eq!(QoSHistoryPolicy::from(rmw_policy), policy);
         }
     }
+
+    #[test]
+    fn reliability_policy_round_trips() {
+        for policy in [
+            QoSReliabilityPolicy::SystemDefault,
+            QoSReliabilityPolicy::Reliable,
+            QoSReliabilityPolicy::BestEffort,
+            QoSReliabilityPolicy::Unknown,
+            #[cfg(ros_distro = "rolling")]
+            QoSReliabilityPolicy::BestAvailable,
+        ] {
+            let rmw_policy = rmw_qos_reliability_policy_t::from(policy);
+            assert_eq!(rmw_policy as u32, policy as u32);
+            assert_eq!(QoSReliabilityPolicy::from(rmw_policy), policy);
+        }
+    }
+
+    #[test]
+    fn durability_policy_round_trips() {
+        for policy in [
+            QoSDurabilityPolicy::SystemDefault,
+            QoSDurabilityPolicy::TransientLocal,
+            QoSDurabilityPolicy::Volatile,
+            QoSDurabilityPolicy::Unknown,
+            #[cfg(ros_distro = "rolling")]
+            QoSDurabilityPolicy::BestAvailable,
+        ] {
+            let rmw_policy = rmw_qos_durability_policy_t::from(policy);
+            assert_eq!(rmw_policy as u32, policy as u32);
+            assert_eq!(QoSDurabilityPolicy::from(rmw_policy), policy);
+        }
+    }
+
+    #[test]
+    fn liveliness_policy_round_trips() {
+        for policy in [
+            QoSLivelinessPolicy::SystemDefault,
+            QoSLivelinessPolicy::Automatic,
+            QoSLivelinessPolicy::ManualByNode,
+            QoSLivelinessPolicy::ManualByTopic,
+            QoSLivelinessPolicy::Unknown,
+            #[cfg(ros_distro = "rolling")]
+            QoSLivelinessPolicy::BestAvailable,
+        ] {
+            let rmw_policy = rmw_qos_liveliness_policy_t::from(policy);
+            assert_eq!(rmw_policy as u32, policy as u32);
+            assert_eq!(QoSLivelinessPolicy::from(rmw_policy), policy);
+        }
+    }
+
+    #[test]
+    fn profile_round_trips() {
+        let profile = QoSProfile {
+            deadline: Duration::new(1, 500),
+            lifespan: Duration::from_millis(250),
+            liveliness: QoSLivelinessPolicy::ManualByTopic,
+            liveliness_lease_duration: Duration::from_secs(3),
+            avoid_ros_namespace_conventions: true,
+            ..QOS_PROFILE_SENSOR_DATA
+        };
+        assert_eq!(QoSProfile::from(rmw_qos_profile_t::from(profile)), profile);
+    }
+
+    #[test]
+    fn duration_saturates() {
+        let time = rmw_time_t {
+            sec: u64::MAX,
+            nsec: 999_999_999,
+        };
+        assert_eq!(Duration::from(time), Duration::MAX);
+    }
 }
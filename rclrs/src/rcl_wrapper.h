@@ -1,2 +1,16 @@
+#include <rcl/graph.h>
 #include <rcl/rcl.h>
-#include <rcutils/error_handling.h>
\ No newline at end of file
+#include <rcl_action/rcl_action.h>
+#include <rcutils/error_handling.h>
+#include <rcutils/logging.h>
+#include <rcl_lifecycle/rcl_lifecycle.h>
+#include <action_msgs/msg/goal_status_array.h>
+#include <action_msgs/srv/cancel_goal.h>
+#include <composition_interfaces/srv/list_nodes.h>
+#include <composition_interfaces/srv/load_node.h>
+#include <composition_interfaces/srv/unload_node.h>
+#include <lifecycle_msgs/msg/transition_event.h>
+#include <lifecycle_msgs/srv/change_state.h>
+#include <lifecycle_msgs/srv/get_available_states.h>
+#include <lifecycle_msgs/srv/get_available_transitions.h>
+#include <lifecycle_msgs/srv/get_state.h>
@@ -0,0 +1,260 @@
+use crate::error::RclrsError;
+use crate::ContextHandle;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicI32, Ordering};
+use parking_lot::{const_mutex, Mutex};
+use rclrs_common::error::RclReturnCode;
+use std::thread;
+
+/// Signals that shut down the registered contexts
+const SIGNALS: [libc::c_int; 2] = [libc::SIGINT, libc::SIGTERM];
+
+/// Write end of the pipe to the signal handling thread, or -1 if the handlers aren't installed
+static SIGNAL_PIPE_WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// The installed handlers, `None` while no context is registered
+static INSTALLATION: Mutex<Option<Installation>> = const_mutex(None);
+
+struct Installation {
+    /// Contexts to shut down on a signal, the handlers stay installed as long as there are any
+    contexts: Vec<Weak<ContextHandle>>,
+    /// Read end of the pipe, which identifies the signal handling thread of this installation
+    read_fd: libc::c_int,
+    write_fd: libc::c_int,
+    /// The actions that were in place before, restored on uninstalling
+    previous_actions: [libc::sigaction; SIGNALS.len()],
+}
+
+/// Registers a context to be shut down on SIGINT/SIGTERM, installing the handlers if needed
+///
+/// Hardly anything is allowed in a signal handler, so the handler merely writes the signal
+/// number to a pipe. The shutdown itself happens on a dedicated thread reading that pipe.
+///
+/// The handlers are uninstalled, restoring the previous ones, once a signal was handled or
+/// the last registered context is dropped, see [`unregister`].
+pub(crate) fn register(context: &Arc<ContextHandle>) -> Result<(), RclrsError> {
+    let installation = &mut *INSTALLATION.lock();
+    match installation {
+        Some(installation) => installation.contexts.push(Arc::downgrade(context)),
+        None => {
+            let mut new_installation = install()?;
+            new_installation.contexts.push(Arc::downgrade(context));
+            *installation = Some(new_installation);
+        }
+    }
+    Ok(())
+}
+
+/// Unregisters a context that is being dropped, uninstalling the handlers if it was the last one
+///
+/// Does nothing if the context wasn't registered.
+pub(crate) fn unregister(context: *const ContextHandle) {
+    let installation = &mut *INSTALLATION.lock();
+    let was_last = match installation {
+        Some(current) => {
+            current
+                .contexts
+                .retain(|registered| !core::ptr::eq(registered.as_ptr(), context));
+            current.contexts.is_empty()
+        }
+        None => false,
+    };
+    if was_last {
+        if let Some(current) = installation.take() {
+            uninstall(current);
+        }
+    }
+}
+
+/// Sets up the pipe, the signal handling thread and the handlers, undoing whatever was done
+/// if one of these steps fails
+fn install() -> Result<Installation, RclrsError> {
+    let mut fds: [libc::c_int; 2] = [-1; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(RclrsError::from(RclReturnCode::Error)
+            .context("failed to create the pipe for the signal handlers"));
+    }
+    let [read_fd, write_fd] = fds;
+    // The handler must never block, a full pipe already has a signal waiting in it
+    if unsafe { libc::fcntl(write_fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1 {
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+        return Err(RclrsError::from(RclReturnCode::Error)
+            .context("failed to make the pipe for the signal handlers non-blocking"));
+    }
+
+    let spawn_result = thread::Builder::new()
+        .name(String::from("rclrs_signal_handler"))
+        .spawn(move || handle_signals(read_fd));
+    if spawn_result.is_err() {
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+        return Err(RclrsError::from(RclReturnCode::Error)
+            .context("failed to spawn the signal handling thread"));
+    }
+    // From here on, closing the write end makes the thread close the read end and exit
+    SIGNAL_PIPE_WRITE_FD.store(write_fd, Ordering::SeqCst);
+
+    let mut previous_actions: [libc::sigaction; SIGNALS.len()] = unsafe { core::mem::zeroed() };
+    for (installed, signal) in SIGNALS.iter().enumerate() {
+        let result = unsafe {
+            let mut action: libc::sigaction = core::mem::zeroed();
+            action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask as *mut _);
+            libc::sigaction(
+                *signal,
+                &action as *const _,
+                &mut previous_actions[installed] as *mut _,
+            )
+        };
+        if result != 0 {
+            restore_actions(&SIGNALS[..installed], &previous_actions[..installed]);
+            close_pipe(write_fd);
+            return Err(RclrsError::from(RclReturnCode::Error)
+                .context("failed to install the signal handlers"));
+        }
+    }
+
+    Ok(Installation {
+        contexts: Vec::new(),
+        read_fd,
+        write_fd,
+        previous_actions,
+    })
+}
+
+/// Restores the previous handlers and stops the signal handling thread
+fn uninstall(installation: Installation) {
+    restore_actions(&SIGNALS, &installation.previous_actions);
+    close_pipe(installation.write_fd);
+}
+
+fn restore_actions(signals: &[libc::c_int], previous_actions: &[libc::sigaction]) {
+    for (signal, previous_action) in signals.iter().zip(previous_actions) {
+        unsafe { libc::sigaction(*signal, previous_action as *const _, core::ptr::null_mut()) };
+    }
+}
+
+fn close_pipe(write_fd: libc::c_int) {
+    SIGNAL_PIPE_WRITE_FD.store(-1, Ordering::SeqCst);
+    unsafe { libc::close(write_fd) };
+}
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    // The interrupted code may be about to check `errno`, which `write` could overwrite
+    let saved_errno = unsafe { *errno_location() };
+    let write_fd = SIGNAL_PIPE_WRITE_FD.load(Ordering::SeqCst);
+    if write_fd >= 0 {
+        let signal = signal as u8;
+        unsafe {
+            libc::write(write_fd, &signal as *const u8 as *const _, 1);
+        }
+    }
+    unsafe { *errno_location() = saved_errno };
+}
+
+#[cfg(any(target_os = "linux", target_os = "emscripten", target_os = "fuchsia"))]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__errno_location()
+}
+
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__errno()
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__error()
+}
+
+fn handle_signals(read_fd: libc::c_int) {
+    loop {
+        let mut signal: u8 = 0;
+        let bytes_read = unsafe { libc::read(read_fd, &mut signal as *mut u8 as *mut _, 1) };
+        if bytes_read < 0
+            && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted
+        {
+            continue;
+        }
+        if bytes_read <= 0 {
+            // The installation was undone and the write end closed
+            unsafe { libc::close(read_fd) };
+            return;
+        }
+
+        // Uninstalling first lets a second signal reach the previous handler, e.g. to
+        // terminate the process in case it doesn't exit by itself
+        let contexts: Vec<Arc<ContextHandle>> = {
+            let installation = &mut *INSTALLATION.lock();
+            match installation {
+                Some(current) if current.read_fd == read_fd => {
+                    let current = installation.take().unwrap();
+                    let contexts = current.contexts.iter().filter_map(Weak::upgrade).collect();
+                    uninstall(current);
+                    contexts
+                }
+                // A late signal for an installation that is already gone
+                _ => Vec::new(),
+            }
+        };
+        for context in contexts {
+            // The context may have been shut down explicitly already
+            let _ = context.shutdown();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spin, Context};
+    use core::time::Duration;
+
+    extern "C" fn previous_handler(_signal: libc::c_int) {}
+
+    fn sigint_handler() -> libc::sighandler_t {
+        unsafe {
+            let mut action: libc::sigaction = core::mem::zeroed();
+            libc::sigaction(libc::SIGINT, core::ptr::null(), &mut action as *mut _);
+            action.sa_sigaction
+        }
+    }
+
+    #[test]
+    fn signals_shut_down_the_registered_contexts() {
+        // Also keeps a stray SIGINT from terminating the test process
+        let previous_handler = previous_handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        unsafe { libc::signal(libc::SIGINT, previous_handler) };
+
+        // Dropping one of the contexts leaves the other one protected
+        let first = Context::new(Vec::new()).unwrap();
+        let second = Context::new(Vec::new()).unwrap();
+        drop(first);
+        assert_ne!(sigint_handler(), previous_handler);
+
+        let node = second.create_node("signal_handler_test").unwrap();
+        let signal_thread = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(100));
+            unsafe { libc::raise(libc::SIGINT) };
+        });
+        // Blocks until the signal shuts the context down
+        spin(&node).unwrap();
+        signal_thread.join().unwrap();
+        assert!(!second.ok().unwrap());
+        assert_eq!(sigint_handler(), previous_handler);
+
+        // The last context to be dropped restores the previous handler as well
+        let third = Context::new(Vec::new()).unwrap();
+        assert_ne!(sigint_handler(), previous_handler);
+        drop(third);
+        assert_eq!(sigint_handler(), previous_handler);
+    }
+}
@@ -15,39 +15,46 @@
 // DISTRIBUTION A. Approved for public release; distribution unlimited.
 // OPSEC #4584.
 
+use crate::error::{RclrsError, ToResult};
 use crate::rcl_bindings::*;
 use crate::SubscriptionBase;
 
-use alloc::sync::Weak;
+use alloc::sync::Arc;
 use core::borrow::BorrowMut;
-use core::fmt::Display;
-use core_error::Error;
-use rclrs_common::error::{to_rcl_result, RclReturnCode, WaitSetErrorCode};
-
-#[derive(Debug)]
-pub enum WaitSetErrorResponse {
-    DroppedSubscription,
-    ReturnCode(RclReturnCode),
-}
+use core::ops::AddAssign;
+use rclrs_common::error::WaitSetErrorCode;
 
-impl Display for WaitSetErrorResponse {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        match self {
-            Self::DroppedSubscription => {
-                write!(f, "WaitSet: Attempted to access dropped subscription!")
-            }
-            Self::ReturnCode(code) => write!(f, "WaitSet: Operation returned Rcl error - {}", code),
-        }
-    }
+/// Number of each kind of `rcl` entity that a [`Waitable`] adds to a [`WaitSet`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WaitableCount {
+    pub subscriptions: usize,
+    pub guard_conditions: usize,
+    pub timers: usize,
+    pub clients: usize,
+    pub services: usize,
+    pub events: usize,
 }
 
-impl From<RclReturnCode> for WaitSetErrorResponse {
-    fn from(code: RclReturnCode) -> Self {
-        Self::ReturnCode(code)
+impl AddAssign for WaitableCount {
+    fn add_assign(&mut self, other: Self) {
+        self.subscriptions += other.subscriptions;
+        self.guard_conditions += other.guard_conditions;
+        self.timers += other.timers;
+        self.clients += other.clients;
+        self.services += other.services;
+        self.events += other.events;
     }
 }
 
-impl Error for WaitSetErrorResponse {}
+/// Trait to be implemented by entities that bundle several `rcl` entities, such as the
+/// services of a lifecycle node, so that [`spin_once`](crate::spin_once) can wait on them
+pub trait Waitable: Send + Sync {
+    /// Number of entities [`add_to_wait_set`](Self::add_to_wait_set) will add
+    fn count(&self) -> WaitableCount;
+    fn add_to_wait_set(&self, wait_set: &mut WaitSet) -> Result<(), RclrsError>;
+    /// Handles whatever became ready during the last [`WaitSet::wait`]
+    fn execute(&self, wait_set: &WaitSet) -> Result<(), RclrsError>;
+}
 
 pub struct WaitSet {
     pub wait_set: rcl_wait_set_t,
@@ -67,13 +74,13 @@ impl WaitSet {
         number_of_services: usize,
         number_of_events: usize,
         context: &mut rcl_context_t,
-    ) -> Result<Self, WaitSetErrorResponse> {
+    ) -> Result<Self, RclrsError> {
         let mut waitset = Self {
             wait_set: unsafe { rcl_get_zero_initialized_wait_set() },
             initialized: false,
         };
         unsafe {
-            match to_rcl_result(rcl_wait_set_init(
+            match rcl_wait_set_init(
                 waitset.wait_set.borrow_mut() as *mut _,
                 number_of_subscriptions,
                 number_of_guard_conditions,
@@ -83,14 +90,16 @@ impl WaitSet {
                 number_of_events,
                 context,
                 rcutils_get_default_allocator(),
-            )) {
+            )
+            .ok()
+            {
                 Ok(()) => {
                     waitset.initialized = true;
                     Ok(waitset)
                 }
                 Err(err) => {
                     waitset.initialized = false;
-                    Err(WaitSetErrorResponse::ReturnCode(err))
+                    Err(err)
                 }
             }
         }
@@ -102,42 +111,87 @@ impl WaitSet {
     /// - `RclError::InvalidArgument` if any arguments are invalid.
     /// - `RclError::WaitSetInvalid` if the WaitSet is already zero-initialized.
     /// - `RclError::Error` for an unspecified error
-    pub fn clear(&mut self) -> Result<(), WaitSetErrorResponse> {
+    pub fn clear(&mut self) -> Result<(), RclrsError> {
         if !self.initialized {
-            return Err(WaitSetErrorResponse::ReturnCode(
-                RclReturnCode::WaitSetError(WaitSetErrorCode::WaitSetInvalid),
-            ));
+            return Err(WaitSetErrorCode::WaitSetInvalid.into());
         }
         unsafe {
             // Whether or not we successfully clear, this WaitSet will count as uninitialized
             self.initialized = false;
-            to_rcl_result(rcl_wait_set_clear(self.wait_set.borrow_mut() as *mut _))
-                .map_err(WaitSetErrorResponse::ReturnCode)
+            rcl_wait_set_clear(self.wait_set.borrow_mut() as *mut _).ok()
         }
     }
 
     /// Adds a subscription to the WaitSet
     ///
     /// # Errors
-    /// - `WaitSetError::DroppedSubscription` if the passed weak pointer refers to a dropped subscription
-    /// - `WaitSetError::RclError` for any `rcl` errors that occur during the process
+    /// - An `RclrsError` for any `rcl` errors that occur during the process
     pub fn add_subscription(
         &mut self,
-        subscription: &Weak<dyn SubscriptionBase>,
-    ) -> Result<(), WaitSetErrorResponse> {
-        if let Some(subscription) = subscription.upgrade() {
-            let subscription_handle = &mut *subscription.handle().lock();
-            unsafe {
-                return to_rcl_result(rcl_wait_set_add_subscription(
-                    self.wait_set.borrow_mut() as *mut _,
-                    subscription_handle as *const _,
-                    core::ptr::null_mut(),
-                ))
-                .map_err(WaitSetErrorResponse::ReturnCode);
-            }
-        } else {
-            Err(WaitSetErrorResponse::DroppedSubscription)
+        subscription: &Arc<dyn SubscriptionBase>,
+    ) -> Result<(), RclrsError> {
+        let subscription_handle = &mut *subscription.handle().lock();
+        unsafe {
+            rcl_wait_set_add_subscription(
+                self.wait_set.borrow_mut() as *mut _,
+                subscription_handle as *const _,
+                core::ptr::null_mut(),
+            )
+            .ok()
+        }
+    }
+
+    /// Adds a guard condition to the WaitSet, returning its index in the WaitSet
+    ///
+    /// # Errors
+    /// - An `RclrsError` for any `rcl` errors that occur during the process
+    pub fn add_guard_condition(
+        &mut self,
+        guard_condition: &rcl_guard_condition_t,
+    ) -> Result<usize, RclrsError> {
+        let mut index = 0;
+        unsafe {
+            rcl_wait_set_add_guard_condition(
+                self.wait_set.borrow_mut() as *mut _,
+                guard_condition as *const _,
+                &mut index as *mut _,
+            )
+            .ok()?;
         }
+        Ok(index)
+    }
+
+    /// Adds a service to the WaitSet, returning its index in the WaitSet
+    ///
+    /// # Errors
+    /// - An `RclrsError` for any `rcl` errors that occur during the process
+    pub fn add_service(&mut self, service: &rcl_service_t) -> Result<usize, RclrsError> {
+        let mut index = 0;
+        unsafe {
+            rcl_wait_set_add_service(
+                self.wait_set.borrow_mut() as *mut _,
+                service as *const _,
+                &mut index as *mut _,
+            )
+            .ok()?;
+        }
+        Ok(index)
+    }
+
+    /// Checks whether the guard condition at `index` was triggered during the last [`wait`](Self::wait)
+    pub fn is_guard_condition_ready(&self, index: usize) -> bool {
+        if index >= self.wait_set.size_of_guard_conditions {
+            return false;
+        }
+        unsafe { !(*self.wait_set.guard_conditions.add(index)).is_null() }
+    }
+
+    /// Checks whether the service at `index` has a pending request after the last [`wait`](Self::wait)
+    pub fn is_service_ready(&self, index: usize) -> bool {
+        if index >= self.wait_set.size_of_services {
+            return false;
+        }
+        unsafe { !(*self.wait_set.services.add(index)).is_null() }
     }
 
     /// Blocks until the WaitSet is ready, or until the timeout has been exceeded
@@ -188,25 +242,19 @@ impl WaitSet {
     /// - `RclError::WaitSetEmpty` if the wait set contains no items
     /// - `RclError::Timeout` if the timeout expired before something was ready
     /// - `RclError::Error` for an unspecified error
-    pub fn wait(&mut self, timeout: i64) -> Result<(), RclReturnCode> {
-        unsafe { to_rcl_result(rcl_wait(self.wait_set.borrow_mut() as *mut _, timeout)) }
+    pub fn wait(&mut self, timeout: i64) -> Result<(), RclrsError> {
+        unsafe { rcl_wait(self.wait_set.borrow_mut() as *mut _, timeout).ok() }
     }
 }
 
 impl Drop for WaitSet {
     /// Drops the WaitSet, and clears the memory
     ///
-    /// # Panics
-    /// A panic is raised if `rcl` is unable to release the waitset for any reason.
+    /// If `rcl` is unable to release the wait set, the error is logged.
     fn drop(&mut self) {
         let handle = &mut *self.wait_set.borrow_mut();
         unsafe {
-            match to_rcl_result(rcl_wait_set_fini(handle as *mut _)) {
-                Ok(()) => (),
-                Err(err) => {
-                    panic!("Unable to release WaitSet!! {:?}", err)
-                }
-            }
-        }
+            rcl_wait_set_fini(handle as *mut _).log_on_error("failed to finalize a wait set")
+        };
     }
 }
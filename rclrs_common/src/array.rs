@@ -0,0 +1,69 @@
+//! Helpers for the fixed-size array fields of messages, e.g. `float64[36]`, since `Default`
+//! and `serde` are only implemented for arrays of up to 32 elements
+
+/// An array of default values, of any size
+pub fn default<T: Default, const N: usize>() -> [T; N] {
+    [(); N].map(|_| T::default())
+}
+
+/// `#[serde(with = "rclrs_common::array::serde")]` (de)serializes an array of any size
+/// as a tuple, like `serde` does for small arrays
+#[cfg(feature = "serde")]
+pub mod serde {
+    use alloc::vec::Vec;
+    use core::convert::TryInto;
+    use core::fmt;
+    use core::marker::PhantomData;
+    use serde::de::{Deserialize, Deserializer, Error, SeqAccess, Visitor};
+    use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+    pub fn serialize<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for element in array {
+            tuple.serialize_element(element)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+            type Value = [T; N];
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "an array of {} elements", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[T; N], A::Error> {
+                let mut elements = Vec::with_capacity(N);
+                while let Some(element) = seq.next_element()? {
+                    elements.push(element);
+                }
+                let length = elements.len();
+                elements
+                    .try_into()
+                    .map_err(|_| A::Error::invalid_length(length, &self))
+            }
+        }
+
+        deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_default_array() {
+        let array: [f64; 36] = super::default();
+        assert!(array.iter().all(|value| *value == 0.0));
+    }
+}
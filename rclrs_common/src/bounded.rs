@@ -0,0 +1,420 @@
+//! Fixed-capacity types for the bounded strings and sequences of messages, e.g.
+//! `string<=10`, `wstring<=10` and `int32[<=5]`
+//!
+//! Their length can only be changed through checked operations, so a message can't hold a
+//! value that doesn't fit in its bounds, which `rcl` would fail to publish.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{self, Display};
+use core::ops::{Deref, DerefMut};
+use core_error::Error;
+
+use crate::wstring::WString;
+
+/// A value didn't fit in the capacity of a bounded type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError {
+    pub capacity: usize,
+    pub length: usize,
+}
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "length {} exceeds the capacity of {}",
+            self.length, self.capacity
+        )
+    }
+}
+
+impl Error for CapacityError {}
+
+fn check_capacity(capacity: usize, length: usize) -> Result<(), CapacityError> {
+    if length > capacity {
+        return Err(CapacityError { capacity, length });
+    }
+    Ok(())
+}
+
+/// A string of at most `N` bytes, for `string<=N` fields
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoundedString<const N: usize>(String);
+
+impl<const N: usize> BoundedString<N> {
+    pub fn new() -> Self {
+        Self(String::new())
+    }
+
+    /// Creates a string from data that the middleware already bounded, cutting it off
+    /// at the last character that fits in case it doesn't
+    pub fn from_truncated(mut string: String) -> Self {
+        if string.len() > N {
+            let mut length = N;
+            while !string.is_char_boundary(length) {
+                length -= 1;
+            }
+            string.truncate(length);
+        }
+        Self(string)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    pub fn push(&mut self, c: char) -> Result<(), CapacityError> {
+        check_capacity(N, self.0.len() + c.len_utf8())?;
+        self.0.push(c);
+        Ok(())
+    }
+
+    pub fn push_str(&mut self, string: &str) -> Result<(), CapacityError> {
+        check_capacity(N, self.0.len() + string.len())?;
+        self.0.push_str(string);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        self.0.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+impl<const N: usize> TryFrom<String> for BoundedString<N> {
+    type Error = CapacityError;
+
+    fn try_from(string: String) -> Result<Self, CapacityError> {
+        check_capacity(N, string.len())?;
+        Ok(Self(string))
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedString<N> {
+    type Error = CapacityError;
+
+    fn try_from(string: &str) -> Result<Self, CapacityError> {
+        check_capacity(N, string.len())?;
+        Ok(Self(string.into()))
+    }
+}
+
+impl<const N: usize> From<BoundedString<N>> for String {
+    fn from(string: BoundedString<N>) -> Self {
+        string.0
+    }
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> Display for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A UTF-16 string of at most `N` code units, for `wstring<=N` fields
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoundedWString<const N: usize>(WString);
+
+impl<const N: usize> BoundedWString<N> {
+    pub fn new() -> Self {
+        Self(WString::new())
+    }
+
+    /// Creates a string from data that the middleware already bounded, cutting it off
+    /// at the last code point that fits in case it doesn't
+    pub fn from_truncated(mut string: WString) -> Self {
+        if string.len() > N {
+            let mut length = N;
+            // Don't split a surrogate pair
+            if length > 0 && (0xD800..0xDC00).contains(&string[length - 1]) {
+                length -= 1;
+            }
+            string.truncate(length);
+        }
+        Self(string)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn as_wstring(&self) -> &WString {
+        &self.0
+    }
+
+    pub fn into_wstring(self) -> WString {
+        self.0
+    }
+
+    pub fn push_str(&mut self, string: &str) -> Result<(), CapacityError> {
+        check_capacity(N, self.0.len() + string.encode_utf16().count())?;
+        self.0.push_str(string);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+impl<const N: usize> TryFrom<WString> for BoundedWString<N> {
+    type Error = CapacityError;
+
+    fn try_from(string: WString) -> Result<Self, CapacityError> {
+        check_capacity(N, string.len())?;
+        Ok(Self(string))
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedWString<N> {
+    type Error = CapacityError;
+
+    fn try_from(string: &str) -> Result<Self, CapacityError> {
+        Self::try_from(WString::from(string))
+    }
+}
+
+impl<const N: usize> From<BoundedWString<N>> for WString {
+    fn from(string: BoundedWString<N>) -> Self {
+        string.0
+    }
+}
+
+impl<const N: usize> Deref for BoundedWString<N> {
+    type Target = WString;
+
+    fn deref(&self) -> &WString {
+        &self.0
+    }
+}
+
+impl<const N: usize> Display for BoundedWString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A sequence of at most `N` elements, for `T[<=N]` fields
+///
+/// The elements can be modified in place through [`DerefMut`], but only added through
+/// the checked [`push`](Self::push) and [`try_extend_from_slice`](Self::try_extend_from_slice).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoundedSequence<T, const N: usize>(Vec<T>);
+
+impl<T, const N: usize> BoundedSequence<T, N> {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Creates a sequence from data that the middleware already bounded, dropping the
+    /// elements that don't fit in case it doesn't
+    pub fn from_truncated(mut elements: Vec<T>) -> Self {
+        elements.truncate(N);
+        Self(elements)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.0
+    }
+
+    pub fn push(&mut self, element: T) -> Result<(), CapacityError> {
+        check_capacity(N, self.0.len() + 1)?;
+        self.0.push(element);
+        Ok(())
+    }
+
+    pub fn try_extend_from_slice(&mut self, elements: &[T]) -> Result<(), CapacityError>
+    where
+        T: Clone,
+    {
+        check_capacity(N, self.0.len() + elements.len())?;
+        self.0.extend_from_slice(elements);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    pub fn truncate(&mut self, length: usize) {
+        self.0.truncate(length)
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+impl<T, const N: usize> Default for BoundedSequence<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> TryFrom<Vec<T>> for BoundedSequence<T, N> {
+    type Error = CapacityError;
+
+    fn try_from(elements: Vec<T>) -> Result<Self, CapacityError> {
+        check_capacity(N, elements.len())?;
+        Ok(Self(elements))
+    }
+}
+
+impl<T, const N: usize> From<BoundedSequence<T, N>> for Vec<T> {
+    fn from(sequence: BoundedSequence<T, N>) -> Self {
+        sequence.0
+    }
+}
+
+impl<T, const N: usize> Deref for BoundedSequence<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> DerefMut for BoundedSequence<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{BoundedSequence, BoundedString, BoundedWString};
+    use crate::wstring::WString;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::{Serialize, Serializer};
+
+    impl<const N: usize> Serialize for BoundedString<N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(serializer)
+        }
+    }
+
+    impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Self::try_from(String::deserialize(deserializer)?).map_err(D::Error::custom)
+        }
+    }
+
+    impl<const N: usize> Serialize for BoundedWString<N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(serializer)
+        }
+    }
+
+    impl<'de, const N: usize> Deserialize<'de> for BoundedWString<N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Self::try_from(WString::deserialize(deserializer)?).map_err(D::Error::custom)
+        }
+    }
+
+    impl<T: Serialize, const N: usize> Serialize for BoundedSequence<T, N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for BoundedSequence<T, N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Self::try_from(Vec::deserialize(deserializer)?).map_err(D::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_bounded_string() {
+        let mut string = BoundedString::<5>::try_from("abc").unwrap();
+        assert_eq!(string.push_str("de"), Ok(()));
+        assert_eq!(
+            string.push('f'),
+            Err(CapacityError {
+                capacity: 5,
+                length: 6
+            })
+        );
+        assert_eq!(string.as_str(), "abcde");
+        assert!(BoundedString::<2>::try_from(String::from("abc")).is_err());
+    }
+
+    #[test]
+    fn test_bounded_string_counts_bytes() {
+        let mut string = BoundedString::<3>::new();
+        assert_eq!(string.push('é'), Ok(()));
+        assert!(string.push('é').is_err());
+        // Truncation doesn't split characters
+        let string = BoundedString::<3>::from_truncated(String::from("éé"));
+        assert_eq!(string.as_str(), "é");
+    }
+
+    #[test]
+    fn test_bounded_sequence() {
+        let mut sequence = BoundedSequence::<i32, 3>::try_from(vec![1, 2]).unwrap();
+        assert_eq!(sequence.push(3), Ok(()));
+        assert_eq!(
+            sequence.push(4),
+            Err(CapacityError {
+                capacity: 3,
+                length: 4
+            })
+        );
+        assert!(sequence.try_extend_from_slice(&[4]).is_err());
+        sequence[0] = 5;
+        assert_eq!(sequence.as_slice(), &[5, 2, 3]);
+        assert!(BoundedSequence::<i32, 1>::try_from(vec![1, 2]).is_err());
+        assert_eq!(
+            BoundedSequence::<i32, 1>::from_truncated(vec![1, 2]).as_slice(),
+            &[1]
+        );
+    }
+
+    #[test]
+    fn test_bounded_wstring_counts_code_units() {
+        let mut string = BoundedWString::<3>::try_from("a").unwrap();
+        assert!(string.push_str("😀😀").is_err());
+        assert_eq!(string.push_str("😀"), Ok(()));
+        assert_eq!(string.len(), 3);
+        // Truncation doesn't split surrogate pairs
+        let string = BoundedWString::<2>::from_truncated(WString::from("a😀"));
+        assert_eq!(string.as_slice(), &[0x61]);
+    }
+}
@@ -0,0 +1,448 @@
+//! Serialization of messages in the CDR format of the ROS middlewares
+//!
+//! Messages implementing [`Cdr`] can be published and taken through the
+//! serialized message APIs of `rcl`, without converting them to their C representation.
+//! The data starts with a 4-byte encapsulation header, followed by the fields of the
+//! message in order, each aligned to its size relative to the end of the header:
+//!
+//! - `bool`, `char`, `byte` and the numeric types are stored as they are
+//! - strings are a `u32` length that includes the terminating NUL, followed by the bytes
+//!   and the NUL
+//! - wstrings are a `u32` number of UTF-16 code units, followed by the code units, each
+//!   widened to a `u32`
+//! - sequences are a `u32` length followed by the elements, and arrays just the elements
+//!
+//! Messages are serialized in little-endian byte order, and deserialized in either order.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{self, Display};
+use core::mem::size_of;
+use core_error::Error;
+
+use crate::bounded::{BoundedSequence, BoundedString, BoundedWString, CapacityError};
+use crate::wstring::WString;
+
+const CDR_BE: [u8; 2] = [0x00, 0x00];
+const CDR_LE: [u8; 2] = [0x00, 0x01];
+const HEADER_LENGTH: usize = 4;
+
+/// Serialized data couldn't be deserialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CdrError {
+    /// The encapsulation header isn't plain CDR, e.g. it's XCDR2
+    UnsupportedEncapsulation([u8; 2]),
+    /// The data ended in the middle of the message
+    UnexpectedEnd,
+    /// A bounded string or sequence exceeded its capacity
+    Capacity(CapacityError),
+}
+
+impl Display for CdrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedEncapsulation(kind) => write!(
+                f,
+                "unsupported encapsulation 0x{:02x}{:02x}",
+                kind[0], kind[1]
+            ),
+            Self::UnexpectedEnd => write!(f, "unexpected end of the serialized data"),
+            Self::Capacity(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl Error for CdrError {}
+
+impl From<CapacityError> for CdrError {
+    fn from(error: CapacityError) -> Self {
+        Self::Capacity(error)
+    }
+}
+
+/// Implemented by the generated messages and the types of their fields
+pub trait Cdr: Sized {
+    fn serialize(&self, writer: &mut CdrWriter);
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError>;
+}
+
+/// Serializes a message, including the encapsulation header
+pub fn to_vec<T: Cdr>(message: &T) -> Vec<u8> {
+    let mut writer = CdrWriter::new();
+    message.serialize(&mut writer);
+    writer.into_vec()
+}
+
+/// Deserializes a message, ignoring any padding after it
+pub fn from_slice<T: Cdr>(data: &[u8]) -> Result<T, CdrError> {
+    T::deserialize(&mut CdrReader::new(data)?)
+}
+
+pub struct CdrWriter {
+    buffer: Vec<u8>,
+}
+
+impl CdrWriter {
+    pub fn new() -> Self {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&CDR_LE);
+        buffer.extend_from_slice(&[0, 0]);
+        Self { buffer }
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    fn align(&mut self, alignment: usize) {
+        let offset = self.buffer.len() - HEADER_LENGTH;
+        let padding = (alignment - offset % alignment) % alignment;
+        self.buffer.resize(self.buffer.len() + padding, 0);
+    }
+
+    /// Lengths are `u32`, which no message that the middleware can send exceeds
+    fn write_length(&mut self, length: usize) {
+        (length as u32).serialize(self)
+    }
+
+    fn write_str(&mut self, string: &str) {
+        self.write_length(string.len() + 1);
+        self.buffer.extend_from_slice(string.as_bytes());
+        self.buffer.push(0);
+    }
+
+    fn write_slice<T: Cdr>(&mut self, elements: &[T]) {
+        self.write_length(elements.len());
+        for element in elements {
+            element.serialize(self);
+        }
+    }
+}
+
+impl Default for CdrWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct CdrReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    little_endian: bool,
+}
+
+impl<'a> CdrReader<'a> {
+    /// Starts reading after the encapsulation header of `data`
+    pub fn new(data: &'a [u8]) -> Result<Self, CdrError> {
+        if data.len() < HEADER_LENGTH {
+            return Err(CdrError::UnexpectedEnd);
+        }
+        let little_endian = match [data[0], data[1]] {
+            CDR_LE => true,
+            CDR_BE => false,
+            kind => return Err(CdrError::UnsupportedEncapsulation(kind)),
+        };
+        Ok(Self {
+            data: &data[HEADER_LENGTH..],
+            position: 0,
+            little_endian,
+        })
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], CdrError> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(CdrError::UnexpectedEnd)?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_aligned(&mut self, size: usize) -> Result<&'a [u8], CdrError> {
+        let padding = (size - self.position % size) % size;
+        self.read_bytes(padding)?;
+        self.read_bytes(size)
+    }
+
+    fn read_length(&mut self) -> Result<usize, CdrError> {
+        Ok(u32::deserialize(self)? as usize)
+    }
+
+    /// Reads `length` elements, without trusting `length` for the allocation
+    fn read_vec<T: Cdr>(&mut self, length: usize) -> Result<Vec<T>, CdrError> {
+        let mut elements = Vec::with_capacity(length.min(self.data.len() - self.position));
+        for _ in 0..length {
+            elements.push(T::deserialize(self)?);
+        }
+        Ok(elements)
+    }
+}
+
+macro_rules! impl_cdr_for_numbers {
+    ($($type:ty),*) => {
+        $(
+            impl Cdr for $type {
+                fn serialize(&self, writer: &mut CdrWriter) {
+                    writer.align(size_of::<$type>());
+                    writer.buffer.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+                    let mut bytes = [0; size_of::<$type>()];
+                    bytes.copy_from_slice(reader.read_aligned(size_of::<$type>())?);
+                    Ok(if reader.little_endian {
+                        <$type>::from_le_bytes(bytes)
+                    } else {
+                        <$type>::from_be_bytes(bytes)
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_cdr_for_numbers!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+impl Cdr for bool {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        (*self as u8).serialize(writer)
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        Ok(u8::deserialize(reader)? != 0)
+    }
+}
+
+/// Like the C type support, invalid UTF-8 is replaced with U+FFFD
+impl Cdr for String {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        writer.write_str(self)
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        let length = reader.read_length()?;
+        let bytes = reader.read_bytes(length)?;
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// Like Fast-CDR, which the type supports use, each code unit is written as a 4-byte
+/// `wchar_t`, of which only the lower 16 bits are read back
+impl Cdr for WString {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        writer.write_length(self.len());
+        for code_unit in self.as_slice() {
+            u32::from(*code_unit).serialize(writer);
+        }
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        let length = reader.read_length()?;
+        let code_units = reader.read_vec::<u32>(length)?;
+        Ok(Self::from(
+            code_units
+                .into_iter()
+                .map(|code_unit| code_unit as u16)
+                .collect::<Vec<u16>>(),
+        ))
+    }
+}
+
+impl<T: Cdr> Cdr for Vec<T> {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        writer.write_slice(self)
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        let length = reader.read_length()?;
+        reader.read_vec(length)
+    }
+}
+
+impl<T: Cdr + Default, const N: usize> Cdr for [T; N] {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        for element in self {
+            element.serialize(writer);
+        }
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        let mut array: [T; N] = crate::array::default();
+        for element in array.iter_mut() {
+            *element = T::deserialize(reader)?;
+        }
+        Ok(array)
+    }
+}
+
+impl<const N: usize> Cdr for BoundedString<N> {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        writer.write_str(self)
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        Ok(Self::try_from(String::deserialize(reader)?)?)
+    }
+}
+
+impl<const N: usize> Cdr for BoundedWString<N> {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        self.as_wstring().serialize(writer)
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        Ok(Self::try_from(WString::deserialize(reader)?)?)
+    }
+}
+
+impl<T: Cdr, const N: usize> Cdr for BoundedSequence<T, N> {
+    fn serialize(&self, writer: &mut CdrWriter) {
+        writer.write_slice(self)
+    }
+
+    fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        let length = reader.read_length()?;
+        if length > N {
+            return Err(CapacityError {
+                capacity: N,
+                length,
+            }
+            .into());
+        }
+        Ok(Self::from_truncated(reader.read_vec(length)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounded::{BoundedSequence, BoundedString, BoundedWString, CapacityError};
+    use crate::cdr;
+    use crate::wstring::WString;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Implements `Cdr` like the generated messages do
+    #[derive(Debug, Default, PartialEq)]
+    struct Sample {
+        flag: bool,
+        value: f64,
+        name: String,
+        ids: BoundedSequence<u16, 2>,
+        matrix: [i8; 3],
+    }
+
+    impl Cdr for Sample {
+        fn serialize(&self, writer: &mut CdrWriter) {
+            self.flag.serialize(writer);
+            self.value.serialize(writer);
+            self.name.serialize(writer);
+            self.ids.serialize(writer);
+            self.matrix.serialize(writer);
+        }
+
+        fn deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+            Ok(Self {
+                flag: Cdr::deserialize(reader)?,
+                value: Cdr::deserialize(reader)?,
+                name: Cdr::deserialize(reader)?,
+                ids: Cdr::deserialize(reader)?,
+                matrix: Cdr::deserialize(reader)?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_cdr_string_message() {
+        // A std_msgs/String, as serialized by the middlewares
+        let data = [0, 1, 0, 0, 6, 0, 0, 0, b'h', b'e', b'l', b'l', b'o', 0];
+        assert_eq!(cdr::to_vec(&String::from("hello")), data);
+        assert_eq!(cdr::from_slice::<String>(&data).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_cdr_alignment() {
+        let sample = Sample {
+            flag: true,
+            value: 1.5,
+            name: String::from("a"),
+            ids: BoundedSequence::try_from(vec![7, 8]).unwrap(),
+            matrix: [-1, 0, 1],
+        };
+        let data = cdr::to_vec(&sample);
+        #[rustfmt::skip]
+        assert_eq!(data, [
+            0, 1, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0xF8, 0x3F,
+            2, 0, 0, 0, b'a', 0, 0, 0,
+            2, 0, 0, 0, 7, 0, 8, 0,
+            0xFF, 0, 1,
+        ]);
+        assert_eq!(cdr::from_slice::<Sample>(&data).unwrap(), sample);
+    }
+
+    #[test]
+    fn test_cdr_wstring() {
+        // Each code unit takes up 4 bytes, like a `wchar_t`
+        let string = WString::from("a😀");
+        #[rustfmt::skip]
+        let data = [
+            0, 1, 0, 0,
+            3, 0, 0, 0,
+            0x61, 0, 0, 0, 0x3D, 0xD8, 0, 0, 0x00, 0xDE, 0, 0,
+        ];
+        assert_eq!(cdr::to_vec(&string), data);
+        assert_eq!(cdr::from_slice::<WString>(&data).unwrap(), string);
+        let bounded = BoundedWString::<3>::try_from("a😀").unwrap();
+        assert_eq!(cdr::to_vec(&bounded), data);
+    }
+
+    #[test]
+    fn test_cdr_big_endian() {
+        #[rustfmt::skip]
+        let data = [
+            0, 0, 0, 0,
+            0, 0, 0, 2,
+            0, 0, 0, 0x61, 0, 0, 0xD8, 0x3D,
+        ];
+        let string: WString = cdr::from_slice(&data).unwrap();
+        assert_eq!(string.as_slice(), &[0x61, 0xD83D]);
+    }
+
+    #[test]
+    fn test_cdr_errors() {
+        assert_eq!(
+            cdr::from_slice::<u32>(&[0, 7, 0, 0, 1, 0, 0, 0]),
+            Err(CdrError::UnsupportedEncapsulation([0, 7]))
+        );
+        assert_eq!(
+            cdr::from_slice::<u32>(&[0, 1, 0, 0, 1, 0]),
+            Err(CdrError::UnexpectedEnd)
+        );
+        // A length that exceeds the data must not be allocated up front
+        assert_eq!(
+            cdr::from_slice::<Vec<u64>>(&[0, 1, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]),
+            Err(CdrError::UnexpectedEnd)
+        );
+        let data = cdr::to_vec(&vec![1u16, 2, 3]);
+        assert_eq!(
+            cdr::from_slice::<BoundedSequence<u16, 2>>(&data),
+            Err(CdrError::Capacity(CapacityError {
+                capacity: 2,
+                length: 3
+            }))
+        );
+        let data = cdr::to_vec(&String::from("abc"));
+        assert!(cdr::from_slice::<BoundedString<2>>(&data).is_err());
+        assert_eq!(
+            cdr::from_slice::<BoundedString<3>>(&data).unwrap().as_str(),
+            "abc"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 #![no_std]
 
+extern crate alloc;
+
 pub mod error {
     use core::{
         convert::TryFrom,
@@ -372,6 +374,74 @@ pub mod error {
 
     impl Error for EventErrorCode {}
 
+    /// Error codes indicating problems with RCL actions are in 21XX to 23XX
+    #[derive(Debug, PartialEq)]
+    pub enum ActionErrorCode {
+        /// Action goal accepted
+        ActionGoalAccepted = 2100,
+        /// Action goal rejected
+        ActionGoalRejected = 2101,
+        /// Invalid `rcl_action_client_t` given
+        ActionClientInvalid = 2102,
+        /// Failed to take a response from the action client
+        ActionClientTakeFailed = 2103,
+        /// Invalid `rcl_action_server_t` given
+        ActionServerInvalid = 2200,
+        /// Failed to take a request from the action server
+        ActionServerTakeFailed = 2201,
+        /// Invalid `rcl_action_goal_handle_t` given
+        ActionGoalHandleInvalid = 2300,
+        /// Invalid goal event given
+        ActionGoalEventInvalid = 2301,
+    }
+
+    impl TryFrom<i32> for ActionErrorCode {
+        type Error = i32;
+
+        fn try_from(value: i32) -> Result<Self, Self::Error> {
+            match value {
+                x if x == Self::ActionGoalAccepted as i32 => Ok(Self::ActionGoalAccepted),
+                x if x == Self::ActionGoalRejected as i32 => Ok(Self::ActionGoalRejected),
+                x if x == Self::ActionClientInvalid as i32 => Ok(Self::ActionClientInvalid),
+                x if x == Self::ActionClientTakeFailed as i32 => Ok(Self::ActionClientTakeFailed),
+                x if x == Self::ActionServerInvalid as i32 => Ok(Self::ActionServerInvalid),
+                x if x == Self::ActionServerTakeFailed as i32 => Ok(Self::ActionServerTakeFailed),
+                x if x == Self::ActionGoalHandleInvalid as i32 => Ok(Self::ActionGoalHandleInvalid),
+                x if x == Self::ActionGoalEventInvalid as i32 => Ok(Self::ActionGoalEventInvalid),
+                other => Err(other),
+            }
+        }
+    }
+
+    impl Display for ActionErrorCode {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::ActionGoalAccepted => write!(f, "ActionError: Action goal accepted!"),
+                Self::ActionGoalRejected => write!(f, "ActionError: Action goal rejected!"),
+                Self::ActionClientInvalid => {
+                    write!(f, "ActionError: Invalid `rcl_action_client_t` given!")
+                }
+                Self::ActionClientTakeFailed => write!(
+                    f,
+                    "ActionError: Failed to take a response from the action client!"
+                ),
+                Self::ActionServerInvalid => {
+                    write!(f, "ActionError: Invalid `rcl_action_server_t` given!")
+                }
+                Self::ActionServerTakeFailed => write!(
+                    f,
+                    "ActionError: Failed to take a request from the action server!"
+                ),
+                Self::ActionGoalHandleInvalid => {
+                    write!(f, "ActionError: Invalid `rcl_action_goal_handle_t` given!")
+                }
+                Self::ActionGoalEventInvalid => write!(f, "ActionError: Invalid goal event given!"),
+            }
+        }
+    }
+
+    impl Error for ActionErrorCode {}
+
     /// Error codes indicating problems with RCL lifecycle state register are in 30XX
     #[derive(Debug, PartialEq)]
     pub enum LifecycleErrorCode {
@@ -447,6 +517,8 @@ pub mod error {
         ParsingError(ParsingErrorCode),
         /// `rcl` event error occurred
         EventError(EventErrorCode),
+        /// `rcl` action error occurred
+        ActionError(ActionErrorCode),
         /// `rcl` lifecycle error occurred
         LifecycleError(LifecycleErrorCode),
         /// Unrecognized/unimplemented error code
@@ -499,6 +571,10 @@ pub mod error {
                     Ok(code) => Self::EventError(code),
                     Err(e) => Self::UnknownError(e),
                 },
+                action_err @ 2100..=2399 => match ActionErrorCode::try_from(action_err) {
+                    Ok(code) => Self::ActionError(code),
+                    Err(e) => Self::UnknownError(e),
+                },
                 lifecycle_err @ 3000..=3099 => match LifecycleErrorCode::try_from(lifecycle_err) {
                     Ok(code) => Self::LifecycleError(code),
                     Err(e) => Self::UnknownError(e),
@@ -562,6 +638,12 @@ pub mod error {
         }
     }
 
+    impl From<ActionErrorCode> for RclReturnCode {
+        fn from(err: ActionErrorCode) -> Self {
+            Self::ActionError(err)
+        }
+    }
+
     impl From<LifecycleErrorCode> for RclReturnCode {
         fn from(err: LifecycleErrorCode) -> Self {
             Self::LifecycleError(err)
@@ -593,6 +675,7 @@ pub mod error {
                 Self::WaitSetError(waitset_err) => write!(f, "RclReturnCode::{}", waitset_err),
                 Self::ParsingError(parse_err) => write!(f, "RclReturnCode::{}", parse_err),
                 Self::EventError(event_err) => write!(f, "RclReturnCode::{}", event_err),
+                Self::ActionError(action_err) => write!(f, "RclReturnCode::{}", action_err),
                 Self::LifecycleError(lifecycle_err) => {
                     write!(f, "RclReturnCode::{}", lifecycle_err)
                 }
@@ -614,31 +697,59 @@ pub mod error {
 }
 
 pub mod traits {
-    use downcast::{downcast, downcast_methods, downcast_methods_core, impl_downcast, Any};
-    use libc::uintptr_t;
-
-    pub trait Message: Any {
-        fn get_native_message(&self) -> uintptr_t;
-        fn destroy_native_message(&self, message_handle: uintptr_t);
-        fn read_handle(&mut self, message_handle: uintptr_t);
+    use crate::rmw::{rosidl_action_type_support_t, rosidl_message_type_support_t};
+
+    /// Implemented by the generated messages, which are plain Rust types that are converted
+    /// to and from their C representation to be passed to `rcl`
+    ///
+    /// Messages are plain data, so they can be shared with callbacks on other threads.
+    pub trait MessageDefinition: Clone + Default + Send + Sync + 'static {
+        /// The `#[repr(C)]` struct with the layout of the `rosidl_generator_c` message, e.g.
+        /// `std_msgs::msg::rmw::String` for `std_msgs::msg::String`
+        ///
+        /// Its default is an initialized message, which `rcl` can take a message into.
+        type RmwMsg: Default;
+
+        fn type_support() -> *const rosidl_message_type_support_t;
+        fn into_rmw(message: &Self) -> Self::RmwMsg;
+        fn from_rmw(message: &Self::RmwMsg) -> Self;
+    }
+
+    /// Messages that are serialized in Rust, see [`crate::cdr`], and published and taken
+    /// through the serialized message APIs of `rcl`
+    pub trait SerializedMessage: MessageDefinition + crate::cdr::Cdr {}
+
+    impl<T: MessageDefinition + crate::cdr::Cdr> SerializedMessage for T {}
+
+    /// Implemented by the generated action types, e.g. `example_interfaces::action::Fibonacci`
+    ///
+    /// Besides the goal, result and feedback messages, an action needs the wrapper messages
+    /// exchanged by `rcl_action`, which carry the goal ID. They are the same for every
+    /// action, apart from the [`RmwMsg`](MessageDefinition::RmwMsg) of the message that
+    /// they wrap, see e.g. [`SendGoalRequest`](crate::rmw::SendGoalRequest).
+    pub trait ActionDefinition: Send + Sync + 'static {
+        type Goal: MessageDefinition;
+        type Result: MessageDefinition;
+        type Feedback: MessageDefinition;
+
+        fn type_support() -> *const rosidl_action_type_support_t;
     }
+}
 
-    downcast!(dyn Message);
+pub mod array;
+pub mod bounded;
+pub mod cdr;
+pub mod rmw;
+pub mod wstring;
 
-    pub trait MessageDefinition<T>: Message {
-        fn get_type_support() -> uintptr_t;
-        fn static_get_native_message(message: &T) -> uintptr_t;
-        fn static_destroy_native_message(message_handle: uintptr_t);
-    }
-}
 #[cfg(test)]
 mod tests {
     use core::convert::TryFrom;
 
     use crate::error::{
-        ClientErrorCode, EventErrorCode, LifecycleErrorCode, NodeErrorCode, ParsingErrorCode,
-        RclErrorCode, RclReturnCode, ServiceErrorCode, SubscriberErrorCode, TimerErrorCode,
-        WaitSetErrorCode,
+        ActionErrorCode, ClientErrorCode, EventErrorCode, LifecycleErrorCode, NodeErrorCode,
+        ParsingErrorCode, RclErrorCode, RclReturnCode, ServiceErrorCode, SubscriberErrorCode,
+        TimerErrorCode, WaitSetErrorCode,
     };
 
     #[test]
@@ -1051,6 +1162,105 @@ mod tests {
         );
     }
 
+    ////////////////////////
+    // ActionError checks //
+    ////////////////////////
+    #[test]
+    fn test_action_goal_accepted() {
+        assert_eq!(
+            ActionErrorCode::try_from(2100).unwrap(),
+            ActionErrorCode::ActionGoalAccepted
+        );
+        assert_eq!(
+            RclReturnCode::from(2100),
+            RclReturnCode::ActionError(ActionErrorCode::ActionGoalAccepted)
+        );
+    }
+
+    #[test]
+    fn test_action_goal_rejected() {
+        assert_eq!(
+            ActionErrorCode::try_from(2101).unwrap(),
+            ActionErrorCode::ActionGoalRejected
+        );
+        assert_eq!(
+            RclReturnCode::from(2101),
+            RclReturnCode::ActionError(ActionErrorCode::ActionGoalRejected)
+        );
+    }
+
+    #[test]
+    fn test_action_client_invalid() {
+        assert_eq!(
+            ActionErrorCode::try_from(2102).unwrap(),
+            ActionErrorCode::ActionClientInvalid
+        );
+        assert_eq!(
+            RclReturnCode::from(2102),
+            RclReturnCode::ActionError(ActionErrorCode::ActionClientInvalid)
+        );
+    }
+
+    #[test]
+    fn test_action_client_take_failed() {
+        assert_eq!(
+            ActionErrorCode::try_from(2103).unwrap(),
+            ActionErrorCode::ActionClientTakeFailed
+        );
+        assert_eq!(
+            RclReturnCode::from(2103),
+            RclReturnCode::ActionError(ActionErrorCode::ActionClientTakeFailed)
+        );
+    }
+
+    #[test]
+    fn test_action_server_invalid() {
+        assert_eq!(
+            ActionErrorCode::try_from(2200).unwrap(),
+            ActionErrorCode::ActionServerInvalid
+        );
+        assert_eq!(
+            RclReturnCode::from(2200),
+            RclReturnCode::ActionError(ActionErrorCode::ActionServerInvalid)
+        );
+    }
+
+    #[test]
+    fn test_action_server_take_failed() {
+        assert_eq!(
+            ActionErrorCode::try_from(2201).unwrap(),
+            ActionErrorCode::ActionServerTakeFailed
+        );
+        assert_eq!(
+            RclReturnCode::from(2201),
+            RclReturnCode::ActionError(ActionErrorCode::ActionServerTakeFailed)
+        );
+    }
+
+    #[test]
+    fn test_action_goal_handle_invalid() {
+        assert_eq!(
+            ActionErrorCode::try_from(2300).unwrap(),
+            ActionErrorCode::ActionGoalHandleInvalid
+        );
+        assert_eq!(
+            RclReturnCode::from(2300),
+            RclReturnCode::ActionError(ActionErrorCode::ActionGoalHandleInvalid)
+        );
+    }
+
+    #[test]
+    fn test_action_goal_event_invalid() {
+        assert_eq!(
+            ActionErrorCode::try_from(2301).unwrap(),
+            ActionErrorCode::ActionGoalEventInvalid
+        );
+        assert_eq!(
+            RclReturnCode::from(2301),
+            RclReturnCode::ActionError(ActionErrorCode::ActionGoalEventInvalid)
+        );
+    }
+
     ///////////////////////////
     // LifecycleError checks //
     ///////////////////////////
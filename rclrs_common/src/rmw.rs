@@ -0,0 +1,424 @@
+//! The C representation of messages, see [`MessageDefinition`]
+//!
+//! These types have the layout of their `rosidl_runtime_c` counterparts, so that the
+//! generated `#[repr(C)]` messages have the layout of the `rosidl_generator_c` structs and
+//! can be passed to `rcl` directly. Their memory is allocated with `malloc`, like the
+//! default allocator of `rosidl_runtime_c` does, so messages filled in by the middleware are
+//! freed correctly when they are dropped.
+
+use alloc::vec::Vec;
+use core::ptr;
+
+use crate::bounded::{BoundedSequence, BoundedString, BoundedWString};
+use crate::traits::MessageDefinition;
+use crate::wstring::WString;
+
+/// `rosidl_message_type_support_t`, which is only handled through pointers
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct rosidl_message_type_support_t {
+    _private: [u8; 0],
+}
+
+/// `rosidl_action_type_support_t`, which is only handled through pointers
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct rosidl_action_type_support_t {
+    _private: [u8; 0],
+}
+
+/// Allocates `length` elements with `malloc`, or none if `length` is 0
+fn allocate<T>(length: usize) -> *mut T {
+    if length == 0 {
+        return ptr::null_mut();
+    }
+    // The elements come from a slice, so their size doesn't overflow
+    let data = unsafe { libc::malloc(length * core::mem::size_of::<T>()) } as *mut T;
+    if data.is_null() {
+        alloc::alloc::handle_alloc_error(core::alloc::Layout::new::<T>());
+    }
+    data
+}
+
+/// Copies code units into a NUL-terminated buffer, like `rosidl_runtime_c__String__assignn`
+fn allocate_nul_terminated<T: Copy + Default>(units: &[T]) -> *mut T {
+    let data = allocate::<T>(units.len() + 1);
+    unsafe {
+        ptr::copy_nonoverlapping(units.as_ptr(), data, units.len());
+        data.add(units.len()).write(T::default());
+    }
+    data
+}
+
+unsafe fn as_slice<'a, T>(data: *const T, size: usize) -> &'a [T] {
+    if data.is_null() {
+        &[]
+    } else {
+        core::slice::from_raw_parts(data, size)
+    }
+}
+
+/// `rosidl_runtime_c__String`, which the default message initializes to an empty string
+#[repr(C)]
+pub struct String {
+    data: *mut u8,
+    size: usize,
+    capacity: usize,
+}
+
+impl String {
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { as_slice(self.data, self.size) }
+    }
+}
+
+impl Default for String {
+    fn default() -> Self {
+        Self::from("")
+    }
+}
+
+impl From<&str> for String {
+    fn from(string: &str) -> Self {
+        Self {
+            data: allocate_nul_terminated(string.as_bytes()),
+            size: string.len(),
+            capacity: string.len() + 1,
+        }
+    }
+}
+
+impl Drop for String {
+    fn drop(&mut self) {
+        unsafe { libc::free(self.data as *mut libc::c_void) }
+    }
+}
+
+/// `rosidl_runtime_c__U16String`, which the default message initializes to an empty string
+#[repr(C)]
+pub struct U16String {
+    data: *mut u16,
+    size: usize,
+    capacity: usize,
+}
+
+impl U16String {
+    pub fn as_slice(&self) -> &[u16] {
+        unsafe { as_slice(self.data, self.size) }
+    }
+}
+
+impl Default for U16String {
+    fn default() -> Self {
+        Self::from(&[][..])
+    }
+}
+
+impl From<&[u16]> for U16String {
+    fn from(units: &[u16]) -> Self {
+        Self {
+            data: allocate_nul_terminated(units),
+            size: units.len(),
+            capacity: units.len() + 1,
+        }
+    }
+}
+
+impl Drop for U16String {
+    fn drop(&mut self) {
+        unsafe { libc::free(self.data as *mut libc::c_void) }
+    }
+}
+
+/// A sequence of primitives or messages, e.g. `rosidl_runtime_c__int32__Sequence`, which
+/// owns its elements
+#[repr(C)]
+pub struct Sequence<T> {
+    data: *mut T,
+    size: usize,
+    capacity: usize,
+}
+
+impl<T> Sequence<T> {
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { as_slice(self.data, self.size) }
+    }
+}
+
+impl<T> Default for Sequence<T> {
+    fn default() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            size: 0,
+            capacity: 0,
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for Sequence<T> {
+    fn from(elements: Vec<T>) -> Self {
+        let size = elements.len();
+        let data = allocate::<T>(size);
+        for (index, element) in elements.into_iter().enumerate() {
+            unsafe { data.add(index).write(element) };
+        }
+        Self {
+            data,
+            size,
+            capacity: size,
+        }
+    }
+}
+
+impl<T> Drop for Sequence<T> {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.data.is_null() {
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.data, self.size));
+            }
+            libc::free(self.data as *mut libc::c_void);
+        }
+    }
+}
+
+/// Implemented by the types of the fields of messages, which the generated messages
+/// convert one by one to and from the fields of their `RmwMsg`
+pub trait RmwField {
+    type Rmw;
+
+    fn to_rmw(&self) -> Self::Rmw;
+    fn from_rmw(rmw: &Self::Rmw) -> Self;
+}
+
+macro_rules! impl_rmw_field_for_primitives {
+    ($($type:ty),*) => {
+        $(
+            impl RmwField for $type {
+                type Rmw = $type;
+
+                fn to_rmw(&self) -> $type {
+                    *self
+                }
+
+                fn from_rmw(rmw: &$type) -> Self {
+                    *rmw
+                }
+            }
+        )*
+    };
+}
+
+impl_rmw_field_for_primitives!(bool, u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+/// Like the C type support, invalid UTF-8 is replaced with U+FFFD
+impl RmwField for alloc::string::String {
+    type Rmw = String;
+
+    fn to_rmw(&self) -> String {
+        String::from(self.as_str())
+    }
+
+    fn from_rmw(rmw: &String) -> Self {
+        alloc::string::String::from_utf8_lossy(rmw.as_bytes()).into_owned()
+    }
+}
+
+impl<const N: usize> RmwField for BoundedString<N> {
+    type Rmw = String;
+
+    fn to_rmw(&self) -> String {
+        String::from(self.as_str())
+    }
+
+    fn from_rmw(rmw: &String) -> Self {
+        Self::from_truncated(alloc::string::String::from_rmw(rmw))
+    }
+}
+
+impl RmwField for WString {
+    type Rmw = U16String;
+
+    fn to_rmw(&self) -> U16String {
+        U16String::from(self.as_slice())
+    }
+
+    fn from_rmw(rmw: &U16String) -> Self {
+        Self::from(rmw.as_slice().to_vec())
+    }
+}
+
+impl<const N: usize> RmwField for BoundedWString<N> {
+    type Rmw = U16String;
+
+    fn to_rmw(&self) -> U16String {
+        U16String::from(self.as_slice())
+    }
+
+    fn from_rmw(rmw: &U16String) -> Self {
+        Self::from_truncated(WString::from_rmw(rmw))
+    }
+}
+
+impl<T: RmwField> RmwField for Vec<T> {
+    type Rmw = Sequence<T::Rmw>;
+
+    fn to_rmw(&self) -> Sequence<T::Rmw> {
+        Sequence::from(self.iter().map(T::to_rmw).collect::<Vec<_>>())
+    }
+
+    fn from_rmw(rmw: &Sequence<T::Rmw>) -> Self {
+        rmw.as_slice().iter().map(T::from_rmw).collect()
+    }
+}
+
+impl<T: RmwField, const N: usize> RmwField for BoundedSequence<T, N> {
+    type Rmw = Sequence<T::Rmw>;
+
+    fn to_rmw(&self) -> Sequence<T::Rmw> {
+        Sequence::from(self.iter().map(T::to_rmw).collect::<Vec<_>>())
+    }
+
+    fn from_rmw(rmw: &Sequence<T::Rmw>) -> Self {
+        Self::from_truncated(Vec::from_rmw(rmw))
+    }
+}
+
+impl<T: RmwField, const N: usize> RmwField for [T; N] {
+    type Rmw = [T::Rmw; N];
+
+    fn to_rmw(&self) -> [T::Rmw; N] {
+        core::array::from_fn(|index| self[index].to_rmw())
+    }
+
+    fn from_rmw(rmw: &[T::Rmw; N]) -> Self {
+        core::array::from_fn(|index| T::from_rmw(&rmw[index]))
+    }
+}
+
+/// Nested messages
+impl<T: MessageDefinition> RmwField for T {
+    type Rmw = T::RmwMsg;
+
+    fn to_rmw(&self) -> T::RmwMsg {
+        T::into_rmw(self)
+    }
+
+    fn from_rmw(rmw: &T::RmwMsg) -> Self {
+        <T as MessageDefinition>::from_rmw(rmw)
+    }
+}
+
+/// `builtin_interfaces__msg__Time`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct Time {
+    pub sec: i32,
+    pub nanosec: u32,
+}
+
+/// The `SendGoal` request of an action, e.g. `example_interfaces__action__Fibonacci_SendGoal_Request`
+///
+/// Like the other wrappers of the goal, result and feedback messages that `rcl_action`
+/// exchanges, it's generic over the [`RmwMsg`](MessageDefinition::RmwMsg) of the wrapped
+/// message. Goal IDs are `unique_identifier_msgs__msg__UUID`s, which are just their bytes.
+#[derive(Default)]
+#[repr(C)]
+pub struct SendGoalRequest<Goal> {
+    pub goal_id: [u8; 16],
+    pub goal: Goal,
+}
+
+/// The `SendGoal` response of an action, which is the same for every action
+#[derive(Default)]
+#[repr(C)]
+pub struct SendGoalResponse {
+    pub accepted: bool,
+    pub stamp: Time,
+}
+
+/// The `GetResult` request of an action, which is the same for every action
+#[derive(Default)]
+#[repr(C)]
+pub struct GetResultRequest {
+    pub goal_id: [u8; 16],
+}
+
+/// The `GetResult` response of an action
+#[derive(Default)]
+#[repr(C)]
+pub struct GetResultResponse<Result> {
+    pub status: i8,
+    pub result: Result,
+}
+
+/// The feedback message of an action, which tells which goal the feedback is for
+#[derive(Default)]
+#[repr(C)]
+pub struct FeedbackMessage<Feedback> {
+    pub goal_id: [u8; 16],
+    pub feedback: Feedback,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rmw;
+    use alloc::string::String;
+    use alloc::vec;
+    use core::convert::TryFrom;
+    use core::mem::size_of;
+
+    #[test]
+    fn test_rmw_strings() {
+        let rmw = String::from("hello").to_rmw();
+        assert_eq!(rmw.as_bytes(), b"hello");
+        assert_eq!(String::from_rmw(&rmw), "hello");
+        assert_eq!(rmw::String::default().as_bytes(), b"");
+        assert_eq!(
+            String::from_rmw(&rmw::String::from("a\u{FFFD}")),
+            "a\u{FFFD}"
+        );
+        let rmw = BoundedString::<3>::try_from("abc").unwrap().to_rmw();
+        assert_eq!(BoundedString::<2>::from_rmw(&rmw).as_str(), "ab");
+
+        let wstring = WString::from(vec![0x61, 0xD83D, 0xDE00]);
+        let rmw = wstring.to_rmw();
+        assert_eq!(rmw.as_slice(), wstring.as_slice());
+        assert_eq!(WString::from_rmw(&rmw), wstring);
+        assert_eq!(BoundedWString::<1>::from_rmw(&rmw).as_slice(), &[0x61]);
+    }
+
+    #[test]
+    fn test_rmw_sequences() {
+        let strings = vec![String::from("a"), String::from("bc")];
+        let rmw = strings.to_rmw();
+        assert_eq!(rmw.as_slice().len(), 2);
+        assert_eq!(rmw.as_slice()[1].as_bytes(), b"bc");
+        assert_eq!(Vec::<String>::from_rmw(&rmw), strings);
+        assert!(Vec::<u8>::from_rmw(&rmw::Sequence::default()).is_empty());
+
+        let rmw = vec![1u16, 2, 3].to_rmw();
+        assert_eq!(
+            BoundedSequence::<u16, 2>::from_rmw(&rmw).as_slice(),
+            &[1, 2]
+        );
+
+        let array = [String::from("x"), String::new()];
+        assert_eq!(<[String; 2]>::from_rmw(&array.to_rmw()), array);
+    }
+
+    #[test]
+    fn test_rmw_action_wrappers() {
+        // The layouts of the C structs, e.g. the `bool` of a `SendGoal` response is padded
+        // to the alignment of the `int32_t` seconds of its stamp
+        assert_eq!(size_of::<rmw::SendGoalRequest<rmw::String>>(), 16 + 24);
+        assert_eq!(size_of::<rmw::SendGoalResponse>(), 12);
+        assert_eq!(size_of::<rmw::GetResultRequest>(), 16);
+        assert_eq!(size_of::<rmw::GetResultResponse<rmw::String>>(), 8 + 24);
+        assert_eq!(size_of::<rmw::FeedbackMessage<u8>>(), 17);
+
+        let request = rmw::SendGoalRequest::<rmw::String>::default();
+        assert_eq!(request.goal_id, [0; 16]);
+        assert_eq!(request.goal.as_bytes(), b"");
+    }
+}
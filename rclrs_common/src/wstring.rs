@@ -0,0 +1,140 @@
+//! UTF-16 strings, for `wstring` fields
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::char::{decode_utf16, DecodeUtf16Error, REPLACEMENT_CHARACTER};
+use core::convert::TryFrom;
+use core::fmt::{self, Display};
+use core::ops::Deref;
+
+/// A string of UTF-16 code units, like `rosidl_runtime_c__U16String`
+///
+/// Like the C type, it isn't required to be valid UTF-16, so the conversion to a Rust
+/// [`String`] can fail, see [`to_string_lossy`](Self::to_string_lossy) otherwise.
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WString(Vec<u16>);
+
+impl WString {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn as_slice(&self) -> &[u16] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<u16> {
+        self.0
+    }
+
+    pub fn push_str(&mut self, string: &str) {
+        self.0.extend(string.encode_utf16())
+    }
+
+    /// Converts the string, replacing invalid code units with U+FFFD
+    pub fn to_string_lossy(&self) -> String {
+        decode_utf16(self.0.iter().copied())
+            .map(|c| c.unwrap_or(REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    pub(crate) fn truncate(&mut self, length: usize) {
+        self.0.truncate(length)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+impl From<&str> for WString {
+    fn from(string: &str) -> Self {
+        Self(string.encode_utf16().collect())
+    }
+}
+
+impl From<String> for WString {
+    fn from(string: String) -> Self {
+        Self::from(string.as_str())
+    }
+}
+
+impl From<Vec<u16>> for WString {
+    fn from(code_units: Vec<u16>) -> Self {
+        Self(code_units)
+    }
+}
+
+impl TryFrom<&WString> for String {
+    type Error = DecodeUtf16Error;
+
+    fn try_from(string: &WString) -> Result<Self, DecodeUtf16Error> {
+        decode_utf16(string.0.iter().copied()).collect()
+    }
+}
+
+impl TryFrom<WString> for String {
+    type Error = DecodeUtf16Error;
+
+    fn try_from(string: WString) -> Result<Self, DecodeUtf16Error> {
+        Self::try_from(&string)
+    }
+}
+
+impl Deref for WString {
+    type Target = [u16];
+
+    fn deref(&self) -> &[u16] {
+        &self.0
+    }
+}
+
+impl Display for WString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl fmt::Debug for WString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+/// Serialized as a string, so invalid code units are replaced with U+FFFD
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::WString;
+    use alloc::string::String;
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+
+    impl Serialize for WString {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.to_string_lossy())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for WString {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            String::deserialize(deserializer).map(Self::from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_wstring() {
+        let string = WString::from("añ😀");
+        assert_eq!(string.as_slice(), &[0x61, 0xF1, 0xD83D, 0xDE00]);
+        assert_eq!(String::try_from(&string).unwrap(), "añ😀");
+        // Unpaired surrogates are valid in a WString, but not in a String
+        let string = WString::from(vec![0x61, 0xD83D]);
+        assert!(String::try_from(&string).is_err());
+        assert_eq!(string.to_string_lossy(), "a\u{FFFD}");
+    }
+}
@@ -0,0 +1,17 @@
+use std::env;
+
+use anyhow::{Error, Result};
+use cstr_core::CString;
+use rclrs;
+
+fn main() -> Result<(), Error> {
+    let args: Vec<CString> = env::args()
+        .filter_map(|arg| CString::new(arg).ok())
+        .collect();
+    let context = rclrs::Context::new(args)?;
+
+    // The name can be changed with `--ros-args -r __node:=<name>`
+    let manager = rclrs::ComponentManager::new(&context, "ComponentManager")?;
+
+    manager.spin().map_err(|err| err.into())
+}
@@ -0,0 +1,38 @@
+use rclrs;
+use std_msgs;
+
+/// The subscriber example as a component, which `rclrs_component_container` can load with
+/// `ros2 component load /ComponentManager rclrs_examples rclrs_examples::Listener`
+pub struct Listener {
+    node: rclrs::Node,
+}
+
+impl rclrs::Component for Listener {
+    fn new(
+        context: &rclrs::Context,
+        options: rclrs::ComponentOptions,
+    ) -> Result<Self, rclrs::RclrsError> {
+        let node = options.node_builder(context, "listener").build()?;
+
+        let mut num_messages: usize = 0;
+
+        // The node owns the subscription, so it doesn't have to be kept here
+        node.create_subscription::<std_msgs::msg::String, _>(
+            "topic",
+            rclrs::QOS_PROFILE_DEFAULT,
+            move |msg: &std_msgs::msg::String| {
+                num_messages += 1;
+                println!("I heard: '{}'", msg.data);
+                println!("(Got {} messages so far)", num_messages);
+            },
+        )?;
+
+        Ok(Self { node })
+    }
+
+    fn node(&self) -> &rclrs::Node {
+        &self.node
+    }
+}
+
+rclrs::register_components!(Listener);
@@ -8,7 +8,7 @@ fn main() -> Result<(), Error> {
     let args: Vec<CString> = env::args()
         .filter_map(|arg| CString::new(arg).ok())
         .collect();
-    let context = rclrs::Context::default(args);
+    let context = rclrs::Context::new(args)?;
 
     let node = context.create_node("minimal_publisher")?;
 
@@ -9,9 +9,9 @@ fn main() -> Result<(), Error> {
     let args: Vec<CString> = env::args()
         .filter_map(|arg| CString::new(arg).ok())
         .collect();
-    let context = rclrs::Context::default(args);
+    let context = rclrs::Context::new(args)?;
 
-    let mut node = context.create_node("minimal_subscriber")?;
+    let node = context.create_node("minimal_subscriber")?;
 
     let mut num_messages: usize = 0;
 
@@ -0,0 +1,186 @@
+use std::sync::{Arc, Mutex};
+
+use cstr_core::CString;
+use example_interfaces::action::{Fibonacci, Fibonacci_Feedback, Fibonacci_Goal, Fibonacci_Result};
+use rclrs::{
+    CancelGoalResponse, CancelResponse, CancelReturnCode, GoalResponse, GoalStatus, GoalUuid, Node,
+    ServerGoalHandle,
+};
+use rclrs_common::error::RclReturnCode;
+
+type Shared<T> = Arc<Mutex<T>>;
+
+/// Spins the node until `done` returns `true`, for at most ten seconds
+fn spin_until(node: &Node, mut done: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if done() {
+            return;
+        }
+        if let Err(error) = rclrs::spin_once(node, 100_000_000) {
+            assert_eq!(error.code(), &RclReturnCode::Timeout);
+        }
+    }
+    assert!(done(), "timed out");
+}
+
+struct Client {
+    goal_id: GoalUuid,
+    accepted: Shared<Option<bool>>,
+    feedback: Shared<Vec<Vec<i32>>>,
+    result: Shared<Option<(GoalStatus, Vec<i32>)>>,
+}
+
+fn send_goal(action_client: &rclrs::ActionClient<Fibonacci>, order: i32) -> Client {
+    let accepted: Shared<Option<bool>> = Shared::default();
+    let feedback: Shared<Vec<Vec<i32>>> = Shared::default();
+    let result: Shared<Option<(GoalStatus, Vec<i32>)>> = Shared::default();
+    let (goal_accepted, goal_feedback, goal_result) =
+        (accepted.clone(), feedback.clone(), result.clone());
+    let goal_id = action_client
+        .send_goal(
+            &Fibonacci_Goal { order },
+            move |_, accepted| *goal_accepted.lock().unwrap() = Some(accepted),
+            move |_, feedback: &Fibonacci_Feedback| {
+                goal_feedback
+                    .lock()
+                    .unwrap()
+                    .push(feedback.sequence.clone())
+            },
+            move |_, status, result: &Fibonacci_Result| {
+                *goal_result.lock().unwrap() = Some((status, result.sequence.clone()))
+            },
+        )
+        .unwrap();
+    Client {
+        goal_id,
+        accepted,
+        feedback,
+        result,
+    }
+}
+
+#[test]
+fn goals_feedback_results_and_cancellation_round_trip() {
+    let args: Vec<CString> = Vec::new();
+    let context = rclrs::ContextBuilder::new(args)
+        .install_signal_handlers(false)
+        .build()
+        .unwrap();
+    let node = context.create_node("action_round_trip").unwrap();
+
+    let goal_handles: Shared<Vec<Arc<ServerGoalHandle<Fibonacci>>>> = Shared::default();
+    let accepted_goal_handles = goal_handles.clone();
+    let _action_server = node
+        .create_action_server::<Fibonacci, _, _, _>(
+            "fibonacci",
+            |_, goal| {
+                if goal.order < 0 {
+                    GoalResponse::Reject
+                } else {
+                    GoalResponse::AcceptAndExecute
+                }
+            },
+            // Goals may also be finished from the cancel callback
+            |goal_handle| {
+                if goal_handle.goal().order == 0 {
+                    goal_handle.abort(&Fibonacci_Result::default()).unwrap();
+                    return CancelResponse::Reject;
+                }
+                CancelResponse::Accept
+            },
+            move |goal_handle| accepted_goal_handles.lock().unwrap().push(goal_handle),
+        )
+        .unwrap();
+    let action_client = node.create_action_client::<Fibonacci>("fibonacci").unwrap();
+    spin_until(&node, || action_client.server_is_ready().unwrap());
+
+    // Rejected goals never get feedback or a result
+    let rejected = send_goal(&action_client, -1);
+    spin_until(&node, || rejected.accepted.lock().unwrap().is_some());
+    assert_eq!(*rejected.accepted.lock().unwrap(), Some(false));
+    assert!(goal_handles.lock().unwrap().is_empty());
+
+    // Accepted goals get their feedback and their result
+    let succeeded = send_goal(&action_client, 3);
+    spin_until(&node, || goal_handles.lock().unwrap().len() == 1);
+    let goal_handle = goal_handles.lock().unwrap()[0].clone();
+    assert_eq!(goal_handle.goal_id(), &succeeded.goal_id);
+    assert_eq!(goal_handle.status(), GoalStatus::Executing);
+    goal_handle
+        .publish_feedback(&Fibonacci_Feedback {
+            sequence: vec![0, 1],
+        })
+        .unwrap();
+    goal_handle
+        .succeed(&Fibonacci_Result {
+            sequence: vec![0, 1, 1, 2],
+        })
+        .unwrap();
+    spin_until(&node, || {
+        succeeded.result.lock().unwrap().is_some() && !succeeded.feedback.lock().unwrap().is_empty()
+    });
+    assert_eq!(*succeeded.accepted.lock().unwrap(), Some(true));
+    assert_eq!(*succeeded.feedback.lock().unwrap(), [vec![0, 1]]);
+    assert_eq!(
+        *succeeded.result.lock().unwrap(),
+        Some((GoalStatus::Succeeded, vec![0, 1, 1, 2]))
+    );
+
+    // Canceled goals report their result once the server finished canceling them
+    let canceled = send_goal(&action_client, 5);
+    spin_until(&node, || goal_handles.lock().unwrap().len() == 2);
+    let goal_handle = goal_handles.lock().unwrap()[1].clone();
+    let cancel_response: Shared<Option<CancelGoalResponse>> = Shared::default();
+    let response = cancel_response.clone();
+    action_client
+        .cancel_goal(&canceled.goal_id, move |cancel_response| {
+            *response.lock().unwrap() = Some(cancel_response.clone())
+        })
+        .unwrap();
+    spin_until(&node, || cancel_response.lock().unwrap().is_some());
+    assert_eq!(
+        *cancel_response.lock().unwrap(),
+        Some(CancelGoalResponse {
+            return_code: CancelReturnCode::None,
+            goals_canceling: vec![canceled.goal_id],
+        })
+    );
+    assert!(goal_handle.is_canceling());
+    goal_handle
+        .canceled(&Fibonacci_Result {
+            sequence: vec![0, 1],
+        })
+        .unwrap();
+    spin_until(&node, || canceled.result.lock().unwrap().is_some());
+    assert_eq!(
+        *canceled.result.lock().unwrap(),
+        Some((GoalStatus::Canceled, vec![0, 1]))
+    );
+
+    // Goals finished by the cancel callback aren't canceled
+    let aborted = send_goal(&action_client, 0);
+    spin_until(&node, || goal_handles.lock().unwrap().len() == 3);
+    let cancel_response: Shared<Option<CancelGoalResponse>> = Shared::default();
+    let response = cancel_response.clone();
+    action_client
+        .cancel_goal(&aborted.goal_id, move |cancel_response| {
+            *response.lock().unwrap() = Some(cancel_response.clone())
+        })
+        .unwrap();
+    spin_until(&node, || {
+        cancel_response.lock().unwrap().is_some() && aborted.result.lock().unwrap().is_some()
+    });
+    assert_eq!(
+        cancel_response
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .return_code,
+        CancelReturnCode::Rejected
+    );
+    assert_eq!(
+        *aborted.result.lock().unwrap(),
+        Some((GoalStatus::Aborted, Vec::new()))
+    );
+}
@@ -0,0 +1,101 @@
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+use rclrs_common::bounded::{BoundedSequence, BoundedString};
+use rclrs_common::cdr;
+use rclrs_common::traits::SerializedMessage;
+use rclrs_common::wstring::WString;
+use test_msgs::msg::{Arrays, BasicTypes, BoundedSequences, MultiNested, Strings, WStrings};
+
+/// Checks that the message is serialized to the same bytes as by the type support of the
+/// middleware, and that those bytes are deserialized to the message again
+fn assert_matches_rmw<T: SerializedMessage + PartialEq + Debug>(message: &T) {
+    let serialized = cdr::to_vec(message);
+    let rmw_serialized = rclrs::serialize_message(message).unwrap();
+
+    // Newer versions of Fast-CDR store the padding at the end in the options of the header
+    assert_eq!(serialized[..2], rmw_serialized[..2]);
+    assert_eq!(serialized[4..], rmw_serialized[4..serialized.len()]);
+    let padding = &rmw_serialized[serialized.len()..];
+    assert!(padding.len() < 4 && padding.iter().all(|byte| *byte == 0));
+
+    assert_eq!(&cdr::from_slice::<T>(&rmw_serialized).unwrap(), message);
+}
+
+fn bounded_sequences() -> BoundedSequences {
+    BoundedSequences {
+        bool_values: BoundedSequence::try_from(vec![true]).unwrap(),
+        // Not aligned to 2 bytes after the bools
+        int16_values: BoundedSequence::try_from(vec![-1, 2]).unwrap(),
+        float64_values: BoundedSequence::try_from(vec![1.5]).unwrap(),
+        string_values: BoundedSequence::try_from(vec![String::from("a"), String::from("bc")])
+            .unwrap(),
+        basic_types_values: BoundedSequence::try_from(vec![BasicTypes {
+            byte_value: 3,
+            int64_value: -7,
+            ..Default::default()
+        }])
+        .unwrap(),
+        alignment_check: 42,
+        ..Default::default()
+    }
+}
+
+fn arrays() -> Arrays {
+    let mut message = Arrays::default();
+    message.char_values[0] = b'x';
+    message.uint64_values[2] = u64::MAX;
+    message.string_values[1] = String::from("not aligned");
+    message.basic_types_values[0].bool_value = true;
+    message.alignment_check = 42;
+    message
+}
+
+#[test]
+fn bounded_sequences_match_rmw() {
+    assert_matches_rmw(&BoundedSequences::default());
+    assert_matches_rmw(&bounded_sequences());
+}
+
+#[test]
+fn arrays_match_rmw() {
+    assert_matches_rmw(&Arrays::default());
+    assert_matches_rmw(&arrays());
+}
+
+#[test]
+fn strings_match_rmw() {
+    assert_matches_rmw(&Strings {
+        string_value: String::from("abc"),
+        bounded_string_value: BoundedString::try_from("de").unwrap(),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn wstrings_match_rmw() {
+    assert_matches_rmw(&WStrings::default());
+
+    let mut message = WStrings {
+        wstring_value: WString::from("a😀"),
+        bounded_sequence_of_wstrings: BoundedSequence::try_from(vec![WString::from("x")]).unwrap(),
+        unbounded_sequence_of_wstrings: vec![WString::new(), WString::from("ハロー")],
+        ..Default::default()
+    };
+    message.array_of_wstrings[1] = WString::from("ö");
+    assert_matches_rmw(&message);
+}
+
+#[test]
+fn nested_messages_match_rmw() {
+    assert_matches_rmw(&MultiNested::default());
+
+    let mut message = MultiNested {
+        bounded_sequence_of_arrays: BoundedSequence::try_from(vec![arrays(), Arrays::default()])
+            .unwrap(),
+        unbounded_sequence_of_bounded_sequences: vec![bounded_sequences()],
+        ..Default::default()
+    };
+    message.array_of_bounded_sequences[2] = bounded_sequences();
+    assert_matches_rmw(&message);
+}
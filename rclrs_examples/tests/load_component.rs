@@ -0,0 +1,78 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use cstr_core::CString;
+use rclrs;
+
+/// The `rclrs_examples` library next to the test executable, which is in `target/*/deps`
+fn component_library() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    let target_dir = exe.parent().unwrap().parent().unwrap();
+    target_dir.join(format!(
+        "{}rclrs_examples{}",
+        env::consts::DLL_PREFIX,
+        env::consts::DLL_SUFFIX
+    ))
+}
+
+/// Registers the library in an ament index under a temporary prefix, as
+/// `rclrs_register_components()` does on install
+fn register_component_library() {
+    let prefix = env::temp_dir().join(format!("rclrs_load_component_{}", std::process::id()));
+    let resource_dir = prefix.join("share/ament_index/resource_index/rclrs_components");
+    fs::create_dir_all(&resource_dir).unwrap();
+    fs::write(
+        resource_dir.join("rclrs_examples"),
+        format!(
+            "rclrs_examples::Listener;{}\n",
+            component_library().display()
+        ),
+    )
+    .unwrap();
+
+    // The rest of the path is still needed to find the middleware
+    let ament_prefix_path = env::var("AMENT_PREFIX_PATH").unwrap_or_default();
+    env::set_var(
+        "AMENT_PREFIX_PATH",
+        format!("{}:{}", prefix.display(), ament_prefix_path),
+    );
+}
+
+#[test]
+fn loads_and_unloads_the_example_component() {
+    register_component_library();
+    let args: Vec<CString> = Vec::new();
+    let context = rclrs::ContextBuilder::new(args)
+        .install_signal_handlers(false)
+        .build()
+        .unwrap();
+    let manager = rclrs::ComponentManager::new(&context, "test_container").unwrap();
+
+    let loaded = manager
+        .load(rclrs::LoadComponentRequest {
+            package_name: String::from("rclrs_examples"),
+            plugin_name: String::from("rclrs_examples::Listener"),
+            options: rclrs::ComponentOptions {
+                node_name: Some(String::from("loaded_listener")),
+                ..Default::default()
+            },
+        })
+        .unwrap();
+    assert_eq!(loaded.full_node_name, "/loaded_listener");
+    assert_eq!(manager.components().len(), 1);
+
+    let unknown = manager.load(rclrs::LoadComponentRequest {
+        package_name: String::from("rclrs_examples"),
+        plugin_name: String::from("rclrs_examples::Talker"),
+        options: rclrs::ComponentOptions::default(),
+    });
+    assert_eq!(
+        unknown.err().unwrap(),
+        "No class found with name 'rclrs_examples::Talker'"
+    );
+
+    assert!(manager.unload(loaded.unique_id));
+    assert!(!manager.unload(loaded.unique_id));
+    assert!(manager.components().is_empty());
+}
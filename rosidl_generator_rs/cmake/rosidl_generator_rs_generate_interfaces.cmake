@@ -45,9 +45,12 @@ set(_generated_msg_rs_files "")
 set(_generated_msg_c_files "")
 set(_generated_srv_rs_files "")
 set(_generated_srv_c_files "")
+set(_generated_action_rs_files "")
+set(_generated_action_c_files "")
 
 set(_has_msg FALSE)
 set(_has_srv FALSE)
+set(_has_action FALSE)
 
 foreach(_typesupport_impl ${_typesupport_impls})
   set(_generated_extension_${_typesupport_impl}_files "")
@@ -66,7 +69,7 @@ foreach(_idl_file ${rosidl_generate_interfaces_ABS_IDL_FILES})
     set(_idl_file_without_actions ${_idl_file_without_actions} ${_idl_file})
   elseif(_parent_folder STREQUAL "action")
     set(_has_action TRUE)
-    message(WARNING "Rust actions generation is not implemented")
+    set(_idl_file_without_actions ${_idl_file_without_actions} ${_idl_file})
   else()
     message(FATAL_ERROR "Interface file with unknown parent folder: ${_idl_file}")
   endif()
@@ -77,14 +80,10 @@ list(APPEND _generated_common_rs_files
 )
 
 if(${_has_msg})
+  # Messages are converted to their C representation in Rust, so they need no extension
   list(APPEND _generated_msg_rs_files
     "${_output_path}/rust/src/msg.rs"
   )
-
-  foreach(_typesupport_impl ${_typesupport_impls})
-    list_append_unique(_generated_extension_${_typesupport_impl}_files "${_output_path}/msg_rs.ep.${_typesupport_impl}.c")
-    list_append_unique(_generated_extension_files "${_generated_extension_${_typesupport_impl}_files}")
-  endforeach()
 endif()
 
 if(${_has_srv})
@@ -98,6 +97,14 @@ if(${_has_srv})
   endforeach()
 endif()
 
+if(${_has_action})
+  # Like messages, actions need no extension, their wrapper messages are generic structs
+  # of rclrs_common
+  list(APPEND _generated_action_rs_files
+    "${_output_path}/rust/src/action.rs"
+  )
+endif()
+
 set(_dependency_files "")
 set(_dependencies "")
 foreach(_pkg_name ${rosidl_generate_interfaces_DEPENDENCY_PACKAGE_NAMES})
@@ -112,9 +119,11 @@ endforeach()
 set(target_dependencies
   "${rosidl_generator_rs_BIN}"
   ${rosidl_generator_rs_GENERATOR_FILES}
-  "${rosidl_generator_rs_TEMPLATE_DIR}/msg.c.em"
+  "${rosidl_generator_rs_TEMPLATE_DIR}/action.rs.em"
   "${rosidl_generator_rs_TEMPLATE_DIR}/srv.c.em"
   "${rosidl_generator_rs_TEMPLATE_DIR}/msg.rs.em"
+  "${rosidl_generator_rs_TEMPLATE_DIR}/msg_rmw.rs.em"
+  "${rosidl_generator_rs_TEMPLATE_DIR}/msg_struct.rs.em"
   "${rosidl_generator_rs_TEMPLATE_DIR}/srv.rs.em"
   ${rosidl_generate_interfaces_ABS_IDL_FILES}
   ${_idl_file_without_actions}
@@ -143,6 +152,8 @@ set(_target_suffix "__rs")
 
 set(_crate_deps "")
 set(CRATES_DEPENDENCIES "")
+set(SERDE_FEATURES "")
+set(STD_FEATURES "")
 find_package(rclrs_common REQUIRED)
 foreach(_crate_dep ${rclrs_common_CRATES})
   list(APPEND _crate_deps "${_crate_dep}")
@@ -153,7 +164,10 @@ foreach(_pkg_name ${rosidl_generate_interfaces_DEPENDENCY_PACKAGE_NAMES})
   find_package(${_pkg_name} REQUIRED)
   foreach(_crate_dep ${${_pkg_name}_CRATES})
     list(APPEND _crate_deps "${_crate_dep}")
-    set(CRATES_DEPENDENCIES "${CRATES_DEPENDENCIES}\n${_pkg_name} = { path = '${_crate_dep}' }")
+    set(CRATES_DEPENDENCIES "${CRATES_DEPENDENCIES}\n${_pkg_name} = { path = '${_crate_dep}', default-features = false }")
+    # Nested messages of other packages have to be serializable as well
+    set(SERDE_FEATURES "${SERDE_FEATURES}, \"${_pkg_name}/serde\"")
+    set(STD_FEATURES "${STD_FEATURES}, \"${_pkg_name}/std\"")
   endforeach()
 endforeach()
 
@@ -175,64 +189,68 @@ set_property(
   ${_generated_msg_rs_files}
   ${_generated_msg_c_files}
   ${_generated_srv_rs_files}
+  ${_generated_action_rs_files}
   PROPERTY GENERATED 1)
 
 set(_type_support_by_generated_c_files ${_type_support_by_generated_msg_c_files} ${_type_support_by_generated_srv_c_files})
-set(_generated_rs_files ${_generated_msg_rs_files} ${_generated_srv_rs_files})
+set(_generated_rs_files ${_generated_msg_rs_files} ${_generated_srv_rs_files} ${_generated_action_rs_files})
 
 set(_rsext_suffix "__rsext")
 foreach(_typesupport_impl ${_typesupport_impls})
   find_package(${_typesupport_impl} REQUIRED)
 
-  set(_extension_compile_flags "")
-  if(CMAKE_COMPILER_IS_GNUCXX OR CMAKE_CXX_COMPILER_ID MATCHES "Clang")
-    set(_extension_compile_flags -Wall -Wextra)
-  endif()
-
   set(_target_name "${PROJECT_NAME}__${_typesupport_impl}${_rsext_suffix}")
 
-  add_library(${_target_name} SHARED
-    ${_generated_extension_${_typesupport_impl}_files}
-  )
-  add_dependencies(
-    ${_target_name}
-    ${rosidl_generate_interfaces_TARGET}${_target_suffix}
-    ${rosidl_generate_interfaces_TARGET}__rosidl_typesupport_c
-  )
+  # Only services have an extension library
+  if(NOT "${_generated_extension_${_typesupport_impl}_files}" STREQUAL "")
+    set(_extension_compile_flags "")
+    if(CMAKE_COMPILER_IS_GNUCXX OR CMAKE_CXX_COMPILER_ID MATCHES "Clang")
+      set(_extension_compile_flags -Wall -Wextra)
+    endif()
 
-  target_link_libraries(
-    ${_target_name}
-    ${PROJECT_NAME}__${_typesupport_impl}
-    ${rosidl_generate_interfaces_TARGET}__rosidl_generator_c
-  )
+    add_library(${_target_name} SHARED
+      ${_generated_extension_${_typesupport_impl}_files}
+    )
+    add_dependencies(
+      ${_target_name}
+      ${rosidl_generate_interfaces_TARGET}${_target_suffix}
+      ${rosidl_generate_interfaces_TARGET}__rosidl_typesupport_c
+    )
 
-  rosidl_target_interfaces(${_target_name}
-    ${rosidl_generate_interfaces_TARGET} rosidl_typesupport_c)
+    target_link_libraries(
+      ${_target_name}
+      ${PROJECT_NAME}__${_typesupport_impl}
+      ${rosidl_generate_interfaces_TARGET}__rosidl_generator_c
+    )
 
-  target_include_directories(${_target_name}
-    PUBLIC
-    ${CMAKE_CURRENT_BINARY_DIR}/rosidl_generator_c
-    ${CMAKE_CURRENT_BINARY_DIR}/rosidl_generator_rs
-  )
+    rosidl_target_interfaces(${_target_name}
+      ${rosidl_generate_interfaces_TARGET} rosidl_typesupport_c)
 
-  ament_target_dependencies(${_target_name}
-    "rosidl_runtime_c"
-    "rosidl_typesupport_c"
-    "rosidl_typesupport_interface"
-  )
-  foreach(_pkg_name ${rosidl_generate_interfaces_DEPENDENCY_PACKAGE_NAMES})
-    ament_target_dependencies(${_target_name}
-      ${_pkg_name}
+    target_include_directories(${_target_name}
+      PUBLIC
+      ${CMAKE_CURRENT_BINARY_DIR}/rosidl_generator_c
+      ${CMAKE_CURRENT_BINARY_DIR}/rosidl_generator_rs
     )
-  endforeach()
 
-  add_dependencies(${_target_name}
-    ${rosidl_generate_interfaces_TARGET}__${_typesupport_impl}
-  )
-  ament_target_dependencies(${_target_name}
-    "rosidl_runtime_c"
-    "rosidl_generator_rs"
-  )
+    ament_target_dependencies(${_target_name}
+      "rosidl_runtime_c"
+      "rosidl_typesupport_c"
+      "rosidl_typesupport_interface"
+    )
+    foreach(_pkg_name ${rosidl_generate_interfaces_DEPENDENCY_PACKAGE_NAMES})
+      ament_target_dependencies(${_target_name}
+        ${_pkg_name}
+      )
+    endforeach()
+
+    add_dependencies(${_target_name}
+      ${rosidl_generate_interfaces_TARGET}__${_typesupport_impl}
+    )
+    ament_target_dependencies(${_target_name}
+      "rosidl_runtime_c"
+      "rosidl_generator_rs"
+    )
+  endif()
 
   if(NOT rosidl_generate_interfaces_SKIP_INSTALL)
     install(
@@ -243,10 +261,12 @@ foreach(_typesupport_impl ${_typesupport_impls})
     # install("${PROJECT_NAME}/lib.rs" "share/${PROJECT_NAME}/rust/src")
     # install("${PROJECT_NAME}/msg.rs" "share/${PROJECT_NAME}/rust/src")
     # install("${PROJECT_NAME}/srv.rs" "share/${PROJECT_NAME}/rust/src")
-    install(TARGETS ${_target_name}
-      ARCHIVE DESTINATION lib
-      LIBRARY DESTINATION lib
-    )
+    if(TARGET ${_target_name})
+      install(TARGETS ${_target_name}
+        ARCHIVE DESTINATION lib
+        LIBRARY DESTINATION lib
+      )
+    endif()
 
     configure_file("${rosidl_generator_rs_TEMPLATE_DIR}/Cargo.toml.in"
       "share/${PROJECT_NAME}/rust/Cargo.toml"
@@ -262,7 +282,8 @@ endforeach()
 if(BUILD_TESTING AND rosidl_generate_interfaces_ADD_LINTER_TESTS)
   if(
     NOT _generated_msg_rs_files STREQUAL "" OR
-    NOT _generated_srv_rs_files STREQUAL ""
+    NOT _generated_srv_rs_files STREQUAL "" OR
+    NOT _generated_action_rs_files STREQUAL ""
   )
     find_package(ament_cmake_cppcheck REQUIRED)
     ament_cppcheck(
@@ -0,0 +1,52 @@
+#![allow(non_camel_case_types)]
+
+use rclrs_common;
+
+@[for subfolder, action_spec in action_specs]@
+@[    for msg_spec in [action_spec.goal, action_spec.result, action_spec.feedback]]@
+@{
+TEMPLATE(
+    'msg_struct.rs.em',
+    package_name=package_name, subfolder=subfolder, msg_spec=msg_spec,
+    get_rs_name=get_rs_name, get_rs_type=get_rs_type,
+)
+}@
+@[    end for]@
+@{
+action_name = action_spec.namespaced_type.name
+}@
+
+#[link(name = "@(package_name)__rosidl_typesupport_c")]
+extern "C" {
+    fn rosidl_typesupport_c__get_action_type_support_handle__@(package_name)__@(subfolder)__@(action_name)() -> *const rclrs_common::rmw::rosidl_action_type_support_t;
+}
+
+pub struct @(action_name);
+
+impl rclrs_common::traits::ActionDefinition for @(action_name) {
+  type Goal = @(action_name)_Goal;
+  type Result = @(action_name)_Result;
+  type Feedback = @(action_name)_Feedback;
+
+  fn type_support() -> *const rclrs_common::rmw::rosidl_action_type_support_t {
+    return unsafe { rosidl_typesupport_c__get_action_type_support_handle__@(package_name)__@(subfolder)__@(action_name)() };
+  }
+}
+
+@[end for]@
+
+/// The `#[repr(C)]` goals, results and feedbacks that are passed to `rcl`, see
+/// `rclrs_common::traits::MessageDefinition::RmwMsg`
+pub mod rmw {
+@[for subfolder, action_spec in action_specs]@
+@[    for msg_spec in [action_spec.goal, action_spec.result, action_spec.feedback]]@
+@{
+TEMPLATE(
+    'msg_rmw.rs.em',
+    package_name=package_name, msg_spec=msg_spec,
+    get_rs_name=get_rs_name, get_rmw_type=get_rmw_type,
+)
+}@
+@[    end for]@
+@[end for]@
+}
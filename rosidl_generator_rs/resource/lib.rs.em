@@ -1,3 +1,6 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 extern crate rclrs_common;
 extern crate libc;
 
@@ -7,4 +10,8 @@ pub mod msg;
 
 @[if len(srv_specs) > 0]@
 pub mod srv;
+@[end if]@
+
+@[if len(action_specs) > 0]@
+pub mod action;
 @[end if]@
\ No newline at end of file
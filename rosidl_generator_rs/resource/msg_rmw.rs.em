@@ -0,0 +1,17 @@
+@{
+type_name = msg_spec.structure.namespaced_type.name
+}@
+
+#[repr(C)]
+pub struct @(type_name) {
+@[for member in msg_spec.structure.members]@
+    pub @(get_rs_name(member.name)): @(get_rmw_type(member.type).replace(package_name, 'crate')),
+@[end for]@
+}
+
+impl Default for @(type_name) {
+  fn default() -> Self {
+    rclrs_common::traits::MessageDefinition::into_rmw(&super::@(type_name)::default())
+  }
+}
+
@@ -0,0 +1,77 @@
+@{
+from rosidl_parser.definition import Array
+}@
+@{
+type_name = msg_spec.structure.namespaced_type.name
+}@
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct @(type_name) {
+@[for member in msg_spec.structure.members]@
+@[    if isinstance(member.type, Array)]@
+    #[cfg_attr(feature = "serde", serde(with = "rclrs_common::array::serde"))]
+@[    end if]@
+    pub @(get_rs_name(member.name)): @(get_rs_type(member.type).replace(package_name, 'crate')),
+@[end for]@
+}
+
+impl Default for @(type_name) {
+  fn default() -> Self {
+    Self {
+@[for member in msg_spec.structure.members]@
+@[    if isinstance(member.type, Array)]@
+      @(get_rs_name(member.name)): rclrs_common::array::default(),
+@[    else]@
+      @(get_rs_name(member.name)): Default::default(),
+@[    end if]@
+@[end for]@
+    }
+  }
+}
+
+#[link(name = "@(package_name)__rosidl_typesupport_c")]
+extern "C" {
+    fn rosidl_typesupport_c__get_message_type_support_handle__@(package_name)__@(subfolder)__@(type_name)() -> *const rclrs_common::rmw::rosidl_message_type_support_t;
+}
+
+impl rclrs_common::traits::MessageDefinition for @(type_name) {
+  type RmwMsg = rmw::@(type_name);
+
+  fn type_support() -> *const rclrs_common::rmw::rosidl_message_type_support_t {
+    return unsafe { rosidl_typesupport_c__get_message_type_support_handle__@(package_name)__@(subfolder)__@(type_name)() };
+  }
+
+  fn into_rmw(message: &Self) -> Self::RmwMsg {
+    Self::RmwMsg {
+@[for member in msg_spec.structure.members]@
+      @(get_rs_name(member.name)): rclrs_common::rmw::RmwField::to_rmw(&message.@(get_rs_name(member.name))),
+@[end for]@
+    }
+  }
+
+  fn from_rmw(message: &Self::RmwMsg) -> Self {
+    Self {
+@[for member in msg_spec.structure.members]@
+      @(get_rs_name(member.name)): rclrs_common::rmw::RmwField::from_rmw(&message.@(get_rs_name(member.name))),
+@[end for]@
+    }
+  }
+}
+
+impl rclrs_common::cdr::Cdr for @(type_name) {
+  fn serialize(&self, _writer: &mut rclrs_common::cdr::CdrWriter) {
+@[for member in msg_spec.structure.members]@
+    rclrs_common::cdr::Cdr::serialize(&self.@(get_rs_name(member.name)), _writer);
+@[end for]@
+  }
+
+  fn deserialize(_reader: &mut rclrs_common::cdr::CdrReader<'_>) -> Result<Self, rclrs_common::cdr::CdrError> {
+    Ok(Self {
+@[for member in msg_spec.structure.members]@
+      @(get_rs_name(member.name)): rclrs_common::cdr::Cdr::deserialize(_reader)?,
+@[end for]@
+    })
+  }
+}
+
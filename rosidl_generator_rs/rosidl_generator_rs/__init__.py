@@ -22,9 +22,13 @@ from rosidl_cmake import get_newest_modification_time
 from rosidl_cmake import read_generator_arguments
 
 from rosidl_parser.definition import AbstractGenericString
+from rosidl_parser.definition import AbstractWString
+from rosidl_parser.definition import Action
 from rosidl_parser.definition import AbstractNestedType
 from rosidl_parser.definition import AbstractSequence
 from rosidl_parser.definition import BoundedSequence
+from rosidl_parser.definition import BoundedString
+from rosidl_parser.definition import BoundedWString
 from rosidl_parser.definition import Array
 from rosidl_parser.definition import BasicType
 from rosidl_parser.definition import BASIC_TYPES
@@ -71,7 +75,6 @@ def generate_rs(generator_arguments_file, typesupport_impls):
 
     mapping_msgs = {
         os.path.join(template_dir, 'msg.rs.em'): ['rust/src/%s.rs'],
-        os.path.join(template_dir, 'msg.c.em'): type_support_impl_by_filename.keys(),
     }
 
     mapping_srvs = {
@@ -79,6 +82,10 @@ def generate_rs(generator_arguments_file, typesupport_impls):
         os.path.join(template_dir, 'srv.c.em'): type_support_impl_by_filename.keys(),
     }
 
+    mapping_actions = {
+        os.path.join(template_dir, 'action.rs.em'): ['rust/src/%s.rs'],
+    }
+
     # Ensure the required templates exist
     for template_file in mapping_msgs.keys():
         assert os.path.exists(template_file), \
@@ -86,10 +93,13 @@ def generate_rs(generator_arguments_file, typesupport_impls):
     for template_file in mapping_srvs.keys():
         assert os.path.exists(template_file), \
             'Services template file %s not found' % template_file
+    for template_file in mapping_actions.keys():
+        assert os.path.exists(template_file), \
+            'Actions template file %s not found' % template_file
 
     data = {
-        'get_c_type': get_c_type,
         'get_rs_type': get_rs_type,
+        'get_rmw_type': get_rmw_type,
         'get_rs_name': get_rs_name,
         'constant_value_to_rs': constant_value_to_rs,
         'value_to_rs': value_to_rs,
@@ -100,6 +110,7 @@ def generate_rs(generator_arguments_file, typesupport_impls):
         'get_builtin_rs_type': get_builtin_rs_type,
         'msg_specs': [],
         'srv_specs': [],
+        'action_specs': [],
         'package_name': args['package_name'],
         'typesupport_impls': typesupport_impls,
     }
@@ -112,6 +123,9 @@ def generate_rs(generator_arguments_file, typesupport_impls):
     for service in idl_content.get_elements_of_type(Service):
         data['srv_specs'].append(('srv', service))
 
+    for action in idl_content.get_elements_of_type(Action):
+        data['action_specs'].append(('action', action))
+
     if data['msg_specs']:
         for template_file, generated_filenames in mapping_msgs.items():
             for generated_filename in generated_filenames:
@@ -134,6 +148,17 @@ def generate_rs(generator_arguments_file, typesupport_impls):
                     generated_file,
                     minimum_timestamp=latest_target_timestamp)
 
+    if data['action_specs']:
+        for template_file, generated_filenames in mapping_actions.items():
+            for generated_filename in generated_filenames:
+                generated_file = os.path.join(args['output_dir'],
+                                              generated_filename % 'action')
+                expand_template(
+                    os.path.join(template_dir, template_file),
+                    data.copy(),
+                    generated_file,
+                    minimum_timestamp=latest_target_timestamp)
+
     expand_template(
         os.path.join(template_dir, 'lib.rs.em'),
         data.copy(),
@@ -240,15 +265,20 @@ def constant_value_to_rs(type_, value):
 
 def get_builtin_rs_type(type_, package_name=None):
     if isinstance(type_, BasicType):
+        # An f64 would neither have its precision nor the layout of the C struct field
+        assert type_.typename != 'long double', \
+            "type 'long double' has no Rust equivalent"
         if type_.typename == 'boolean':
             return 'bool'
         elif type_.typename in ['byte', 'octet']:
             return 'u8'
         elif type_.typename == 'char':
             return 'u8'
+        elif type_.typename == 'wchar':
+            return 'u16'
         elif type_.typename in ['float']:
             return 'f32'
-        elif type_.typename in ['double', 'long double']:
+        elif type_.typename == 'double':
             return 'f64'
         elif type_.typename == 'int8':
             return 'i8'
@@ -266,12 +296,21 @@ def get_builtin_rs_type(type_, package_name=None):
             return 'i64'
         elif type_.typename == 'uint64':
             return 'u64'
+    elif isinstance(type_, BoundedString):
+        return 'rclrs_common::bounded::BoundedString<{}>'.format(type_.maximum_size)
+    elif isinstance(type_, BoundedWString):
+        return 'rclrs_common::bounded::BoundedWString<{}>'.format(type_.maximum_size)
+    elif isinstance(type_, AbstractWString):
+        return 'rclrs_common::wstring::WString'
     elif isinstance(type_, AbstractGenericString):
-        return 'std::string::String'
+        return 'alloc::string::String'
     elif isinstance(type_, Array):
-        return '[{}; {}]'.format(get_rs_type(type_.value_type), 32 if type_.size <= 32 else 32)
+        return '[{}; {}]'.format(get_rs_type(type_.value_type), type_.size)
+    elif isinstance(type_, BoundedSequence):
+        return 'rclrs_common::bounded::BoundedSequence<{}, {}>'.format(
+            get_rs_type(type_.value_type), type_.maximum_size)
     elif isinstance(type_, AbstractSequence):
-        return 'Vec<{}>'.format(get_rs_type(type_.value_type))
+        return 'alloc::vec::Vec<{}>'.format(get_rs_type(type_.value_type))
 
     assert False, "unknown type '%s'" % type_.typename
 
@@ -283,43 +322,20 @@ def get_rs_type(type_):
     return get_builtin_rs_type(type_)
 
 
-def get_builtin_c_type(type_):
-
+# The types of the fields of the `#[repr(C)]` messages, which have the layout of the
+# `rosidl_generator_c` structs, see `rclrs_common::rmw`
+def get_rmw_type(type_):
+    if isinstance(type_, NamespacedType):
+        return '::'.join(type_.namespaces + ['rmw', type_.name])
     if isinstance(type_, BasicType):
-        if type_.typename == 'boolean':
-            return 'bool'
-        if type_.typename in ['byte', 'octet']:
-            return 'uint8_t'
-        if type_.typename == 'char':
-            return 'char'
-        if type_.typename in ['float']:
-            return 'float'
-        if type_.typename in ['double', 'long double']:
-            return 'double'
-        if type_.typename == 'int8':
-            return 'int8_t'
-        if type_.typename == 'uint8':
-            return 'uint8_t'
-        if type_.typename == 'int16':
-            return 'int16_t'
-        if type_.typename == 'uint16':
-            return 'uint16_t'
-        if type_.typename == 'int32':
-            return 'int32_t'
-        if type_.typename == 'uint32':
-            return 'uint32_t'
-        if type_.typename == 'int64':
-            return 'int64_t'
-        if type_.typename == 'uint64':
-            return 'uint64_t'
-    elif isinstance(type_, AbstractGenericString):
-        return 'const char *'
-
-    assert False, "unknown type '%s'" % type_.typename
-
-
-def get_c_type(type_, subfolder='msg'):
-    if not isinstance(type_, BasicType) and not isinstance(type_, AbstractGenericString):
-        return 'uintptr_t'
-
-    return get_builtin_c_type(type_)
+        return get_builtin_rs_type(type_)
+    if isinstance(type_, AbstractWString):
+        return 'rclrs_common::rmw::U16String'
+    if isinstance(type_, AbstractGenericString):
+        return 'rclrs_common::rmw::String'
+    if isinstance(type_, Array):
+        return '[{}; {}]'.format(get_rmw_type(type_.value_type), type_.size)
+    if isinstance(type_, AbstractSequence):
+        return 'rclrs_common::rmw::Sequence<{}>'.format(get_rmw_type(type_.value_type))
+
+    assert False, "unknown type '%s'" % type_
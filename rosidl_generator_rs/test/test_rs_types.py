@@ -0,0 +1,83 @@
+# Copyright 2016-2017 Esteve Fernandez <esteve@apache.org>
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import pathlib
+
+from rosidl_adapter.msg import convert_msg_to_idl
+from rosidl_parser.definition import BasicType
+from rosidl_parser.definition import IdlLocator
+from rosidl_parser.definition import Message
+from rosidl_parser.parser import parse_idl_file
+
+from rosidl_generator_rs import get_rmw_type
+from rosidl_generator_rs import get_rs_type
+
+PACKAGE_DIR = pathlib.Path(__file__).resolve().parents[1]
+
+
+def parse_members(msg_name, output_dir):
+    idl_file = convert_msg_to_idl(
+        PACKAGE_DIR, 'rosidl_generator_rs', pathlib.Path('msg') / (msg_name + '.msg'),
+        output_dir)
+    idl = parse_idl_file(IdlLocator(output_dir, idl_file.relative_to(output_dir)))
+    message = idl.content.get_elements_of_type(Message)[0]
+    return {member.name: member.type for member in message.structure.members}
+
+
+def test_char(tmp_path):
+    members = parse_members('Char', tmp_path)
+    # The fields of the `#[repr(C)]` messages must have the size of the C types
+    assert get_rs_type(members['empty_char']) == 'u8'
+    assert get_rmw_type(members['empty_char']) == 'u8'
+    assert get_rs_type(BasicType('char')) == 'u8'
+    assert get_rmw_type(BasicType('char')) == 'u8'
+    assert get_rs_type(BasicType('wchar')) == 'u16'
+    assert get_rmw_type(BasicType('wchar')) == 'u16'
+
+
+def test_byte(tmp_path):
+    members = parse_members('Byte', tmp_path)
+    assert get_rs_type(members['empty_byte']) == 'u8'
+    assert get_rmw_type(members['empty_byte']) == 'u8'
+
+
+def test_strings(tmp_path):
+    members = parse_members('Strings', tmp_path)
+    assert get_rs_type(members['empty_string']) == 'alloc::string::String'
+    assert get_rmw_type(members['empty_string']) == 'rclrs_common::rmw::String'
+    assert get_rs_type(members['ub_string']) == 'rclrs_common::bounded::BoundedString<22>'
+    assert get_rmw_type(members['ub_string']) == 'rclrs_common::rmw::String'
+
+
+def test_wstrings(tmp_path):
+    members = parse_members('WStrings', tmp_path)
+    assert get_rs_type(members['empty_wstring']) == 'rclrs_common::wstring::WString'
+    assert get_rmw_type(members['empty_wstring']) == 'rclrs_common::rmw::U16String'
+    assert get_rs_type(members['ub_wstring']) == 'rclrs_common::bounded::BoundedWString<22>'
+    assert get_rmw_type(members['ub_wstring']) == 'rclrs_common::rmw::U16String'
+
+
+def test_nested(tmp_path):
+    members = parse_members('Nested', tmp_path)
+    assert get_rmw_type(members['primitives']) == 'rosidl_generator_rs::msg::rmw::Primitives'
+    assert get_rmw_type(members['two_primitives']) == \
+        '[rosidl_generator_rs::msg::rmw::Primitives; 2]'
+    # Bounded sequences have the layout of unbounded ones in C
+    assert get_rmw_type(members['up_to_three_primitives']) == \
+        'rclrs_common::rmw::Sequence<rosidl_generator_rs::msg::rmw::Primitives>'
+    assert get_rmw_type(members['unbounded_primitives']) == \
+        'rclrs_common::rmw::Sequence<rosidl_generator_rs::msg::rmw::Primitives>'
+    # The crates are no_std, so the types of alloc are used explicitly
+    assert get_rs_type(members['unbounded_primitives']) == \
+        'alloc::vec::Vec<rosidl_generator_rs::msg::Primitives>'